@@ -0,0 +1,2 @@
+-- Add migration script here
+ALTER TABLE people ADD COLUMN role TEXT NOT NULL DEFAULT 'Member';
@@ -0,0 +1,2 @@
+-- Add migration script here
+ALTER TABLE collective_involvements ADD COLUMN draft BOOLEAN NOT NULL DEFAULT FALSE;
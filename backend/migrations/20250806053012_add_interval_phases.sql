@@ -0,0 +1,11 @@
+-- Add migration script here
+ALTER TABLE intervals ADD COLUMN phase TEXT NOT NULL DEFAULT 'Draft';
+ALTER TABLE intervals ADD COLUMN participation_deadline TEXT;
+
+-- Existing intervals were only ever treated by date, so give them the matching phase
+UPDATE intervals
+SET phase = CASE
+    WHEN end_date < date('now') THEN 'Closed'
+    WHEN start_date <= date('now') THEN 'Active'
+    ELSE 'OpenForPlanning'
+END;
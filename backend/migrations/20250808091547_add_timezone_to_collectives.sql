@@ -0,0 +1,2 @@
+-- Add migration script here
+ALTER TABLE collectives ADD COLUMN timezone TEXT NOT NULL DEFAULT 'UTC';
@@ -0,0 +1,17 @@
+-- Add migration script here
+-- Interval ids are unique across all collectives, so each interval also gets a sequence
+-- number within its own collective. Existing ids are left as they are, so involvements
+-- still point at the same intervals.
+ALTER TABLE intervals ADD COLUMN sequence INTEGER NOT NULL DEFAULT 0;
+
+UPDATE intervals
+SET sequence = (
+    SELECT numbered.sequence
+    FROM (
+        SELECT id, ROW_NUMBER() OVER (PARTITION BY collective_id ORDER BY start_date, id) AS sequence
+        FROM intervals
+    ) numbered
+    WHERE numbered.id = intervals.id
+);
+
+CREATE UNIQUE INDEX "intervals_collective_sequence_unique" ON intervals (collective_id, sequence);
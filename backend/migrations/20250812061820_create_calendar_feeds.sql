@@ -0,0 +1,14 @@
+-- Add migration script here
+CREATE TABLE IF NOT EXISTS "calendar_feeds" (
+    "id" INTEGER NOT NULL,
+    "collective_id" INTEGER NOT NULL,
+    "person_id" INTEGER,
+    "token" TEXT NOT NULL,
+    "created_at" TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    "revoked_at" TEXT,
+    PRIMARY KEY("id" AUTOINCREMENT),
+    CONSTRAINT "calendar_feeds_collectives_FK" FOREIGN KEY("collective_id") REFERENCES "collectives"("id"),
+    CONSTRAINT "calendar_feeds_people_FK" FOREIGN KEY("person_id") REFERENCES "people"("id")
+);
+
+CREATE UNIQUE INDEX "calendar_feeds_token_unique" ON calendar_feeds (token);
@@ -0,0 +1,4 @@
+-- Add migration script here
+ALTER TABLE crews ADD COLUMN min_members INTEGER;
+ALTER TABLE crews ADD COLUMN max_members INTEGER;
+ALTER TABLE crews ADD COLUMN needs_convenor BOOLEAN NOT NULL DEFAULT TRUE;
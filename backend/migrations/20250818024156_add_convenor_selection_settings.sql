@@ -0,0 +1,3 @@
+-- Add migration script here
+ALTER TABLE crews ADD COLUMN convenor_strategy TEXT NOT NULL DEFAULT 'LongestSinceConvened';
+ALTER TABLE collectives ADD COLUMN convenor_selection_timing TEXT NOT NULL DEFAULT 'OnEveryUpdate';
@@ -0,0 +1,5 @@
+-- Add migration script here
+ALTER TABLE crews ADD COLUMN allow_co_convenors BOOLEAN NOT NULL DEFAULT FALSE;
+
+-- Set when a convenor was assigned by hand, so automatic selection leaves them alone
+ALTER TABLE crew_involvements ADD COLUMN convenor_assigned_by INTEGER REFERENCES people(id);
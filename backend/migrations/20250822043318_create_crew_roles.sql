@@ -0,0 +1,23 @@
+-- Add migration script here
+CREATE TABLE IF NOT EXISTS "crew_roles" (
+    "id" INTEGER NOT NULL,
+    "crew_id" INTEGER NOT NULL,
+    "name" TEXT NOT NULL,
+    "strategy" TEXT NOT NULL DEFAULT 'ManualOnly',
+    PRIMARY KEY("id" AUTOINCREMENT),
+    CONSTRAINT "crew_roles_crews_FK" FOREIGN KEY("crew_id") REFERENCES "crews"("id")
+);
+
+CREATE TABLE IF NOT EXISTS "crew_role_assignments" (
+    "id" INTEGER NOT NULL,
+    "crew_role_id" INTEGER NOT NULL,
+    "person_id" INTEGER NOT NULL,
+    "interval_id" INTEGER NOT NULL,
+    "assigned" BOOLEAN NOT NULL DEFAULT FALSE,
+    "volunteered" BOOLEAN NOT NULL DEFAULT FALSE,
+    PRIMARY KEY("id" AUTOINCREMENT),
+    CONSTRAINT "crew_role_assignments_unique" UNIQUE("crew_role_id","person_id","interval_id"),
+    CONSTRAINT "crew_role_assignments_crew_roles_FK" FOREIGN KEY("crew_role_id") REFERENCES "crew_roles"("id"),
+    CONSTRAINT "crew_role_assignments_people_FK" FOREIGN KEY("person_id") REFERENCES "people"("id"),
+    CONSTRAINT "crew_role_assignments_intervals_FK" FOREIGN KEY("interval_id") REFERENCES "intervals"("id")
+);
@@ -0,0 +1,3 @@
+-- Add migration script here
+ALTER TABLE crews ADD COLUMN requires_approval BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE crew_involvements ADD COLUMN membership TEXT NOT NULL DEFAULT 'Member';
@@ -0,0 +1,2 @@
+-- Add migration script here
+ALTER TABLE crews ADD COLUMN parent_crew_id INTEGER REFERENCES crews(id) ON DELETE SET NULL;
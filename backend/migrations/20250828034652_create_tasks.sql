@@ -0,0 +1,20 @@
+-- Add migration script here
+CREATE TABLE IF NOT EXISTS "tasks" (
+    "id" INTEGER NOT NULL,
+    "collective_id" INTEGER NOT NULL,
+    "crew_id" INTEGER,
+    "title" TEXT NOT NULL,
+    "description" TEXT,
+    "assignee_id" INTEGER,
+    "due_date" TEXT,
+    "status" TEXT NOT NULL DEFAULT 'Open',
+    "recurrence" TEXT NOT NULL DEFAULT 'Once',
+    "interval_id" INTEGER,
+    "previous_task_id" INTEGER,
+    PRIMARY KEY("id" AUTOINCREMENT),
+    CONSTRAINT "tasks_collectives_FK" FOREIGN KEY("collective_id") REFERENCES "collectives"("id"),
+    CONSTRAINT "tasks_crews_FK" FOREIGN KEY("crew_id") REFERENCES "crews"("id") ON DELETE CASCADE,
+    CONSTRAINT "tasks_people_FK" FOREIGN KEY("assignee_id") REFERENCES "people"("id") ON DELETE SET NULL,
+    CONSTRAINT "tasks_intervals_FK" FOREIGN KEY("interval_id") REFERENCES "intervals"("id"),
+    CONSTRAINT "tasks_tasks_FK" FOREIGN KEY("previous_task_id") REFERENCES "tasks"("id") ON DELETE SET NULL
+);
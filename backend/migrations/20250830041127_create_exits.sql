@@ -0,0 +1,28 @@
+-- Add migration script here
+ALTER TABLE collectives ADD COLUMN exit_grace_period_days INTEGER NOT NULL DEFAULT 30;
+ALTER TABLE users ADD COLUMN disabled BOOLEAN NOT NULL DEFAULT FALSE;
+
+CREATE TABLE IF NOT EXISTS "exit_checklist_items" (
+    "id" INTEGER NOT NULL,
+    "collective_id" INTEGER NOT NULL,
+    "title" TEXT NOT NULL,
+    "description" TEXT,
+    "position" INTEGER NOT NULL,
+    PRIMARY KEY("id" AUTOINCREMENT),
+    CONSTRAINT "exit_checklist_items_collectives_FK" FOREIGN KEY("collective_id") REFERENCES "collectives"("id")
+);
+
+CREATE TABLE IF NOT EXISTS "exits" (
+    "id" INTEGER NOT NULL,
+    "collective_id" INTEGER NOT NULL,
+    "person_id" INTEGER NOT NULL,
+    "interval_id" INTEGER NOT NULL,
+    "started_on" TEXT NOT NULL,
+    "disable_on" TEXT NOT NULL,
+    "disabled" BOOLEAN NOT NULL DEFAULT FALSE,
+    PRIMARY KEY("id" AUTOINCREMENT),
+    CONSTRAINT "exits_unique" UNIQUE("person_id","interval_id"),
+    CONSTRAINT "exits_collectives_FK" FOREIGN KEY("collective_id") REFERENCES "collectives"("id"),
+    CONSTRAINT "exits_people_FK" FOREIGN KEY("person_id") REFERENCES "people"("id"),
+    CONSTRAINT "exits_intervals_FK" FOREIGN KEY("interval_id") REFERENCES "intervals"("id")
+);
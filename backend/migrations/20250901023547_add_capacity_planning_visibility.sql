@@ -0,0 +1,13 @@
+-- Add migration script here
+ALTER TABLE collectives ADD COLUMN care_crew_id INTEGER REFERENCES crews(id);
+
+ALTER TABLE collective_involvements ADD COLUMN wellbeing_visibility TEXT NOT NULL DEFAULT 'Collective';
+ALTER TABLE collective_involvements ADD COLUMN focus_visibility TEXT NOT NULL DEFAULT 'Collective';
+ALTER TABLE collective_involvements ADD COLUMN capacity_visibility TEXT NOT NULL DEFAULT 'Collective';
+
+-- Private capacity planning was only shown to the person and admins
+UPDATE collective_involvements
+SET wellbeing_visibility = 'Care', focus_visibility = 'Care', capacity_visibility = 'Care'
+WHERE private_capacity_planning = TRUE;
+
+ALTER TABLE collective_involvements DROP COLUMN private_capacity_planning;
@@ -0,0 +1,64 @@
+-- Add migration script here
+CREATE TABLE IF NOT EXISTS "check_in_questions" (
+    "id" INTEGER NOT NULL,
+    "collective_id" INTEGER NOT NULL,
+    -- The question set applies from this interval until a later one has its own
+    "interval_id" INTEGER NOT NULL,
+    "key" TEXT NOT NULL,
+    "prompt" TEXT NOT NULL,
+    "kind" TEXT NOT NULL,
+    -- JSON array of the choices for MultipleChoice questions
+    "options" TEXT NOT NULL DEFAULT '[]',
+    "required" BOOLEAN NOT NULL DEFAULT FALSE,
+    "position" INTEGER NOT NULL,
+    PRIMARY KEY("id" AUTOINCREMENT),
+    CONSTRAINT "check_in_questions_collectives_FK" FOREIGN KEY("collective_id") REFERENCES "collectives"("id"),
+    CONSTRAINT "check_in_questions_intervals_FK" FOREIGN KEY("interval_id") REFERENCES "intervals"("id"),
+    UNIQUE("interval_id", "key")
+);
+
+CREATE TABLE IF NOT EXISTS "check_in_answers" (
+    "id" INTEGER NOT NULL,
+    "collective_involvement_id" INTEGER NOT NULL,
+    "question_key" TEXT NOT NULL,
+    "answer" TEXT NOT NULL,
+    "visibility" TEXT NOT NULL DEFAULT 'Collective',
+    PRIMARY KEY("id" AUTOINCREMENT),
+    CONSTRAINT "check_in_answers_collective_involvements_FK" FOREIGN KEY("collective_involvement_id") REFERENCES "collective_involvements"("id") ON DELETE CASCADE,
+    UNIQUE("collective_involvement_id", "question_key")
+);
+
+-- The fixed capacity planning fields become each collective's first question set
+INSERT INTO check_in_questions (collective_id, interval_id, key, prompt, kind, position)
+SELECT intervals.collective_id, intervals.id, questions.key, questions.prompt, 'FreeText', questions.position
+FROM intervals
+CROSS JOIN (
+    SELECT 'wellbeing' AS key, 'Wellbeing' AS prompt, 0 AS position
+    UNION ALL SELECT 'focus', 'Focus', 1
+    UNION ALL SELECT 'capacity', 'Capacity', 2
+) AS questions
+WHERE intervals.id = (
+    SELECT first.id FROM intervals first
+    WHERE first.collective_id = intervals.collective_id
+    ORDER BY first.start_date
+    LIMIT 1
+);
+
+INSERT INTO check_in_answers (collective_involvement_id, question_key, answer, visibility)
+SELECT id, 'wellbeing', wellbeing, wellbeing_visibility FROM collective_involvements
+WHERE wellbeing IS NOT NULL AND wellbeing != '';
+
+INSERT INTO check_in_answers (collective_involvement_id, question_key, answer, visibility)
+SELECT id, 'focus', focus, focus_visibility FROM collective_involvements
+WHERE focus IS NOT NULL AND focus != '';
+
+INSERT INTO check_in_answers (collective_involvement_id, question_key, answer, visibility)
+SELECT id, 'capacity', capacity, capacity_visibility FROM collective_involvements
+WHERE capacity IS NOT NULL AND capacity != '';
+
+ALTER TABLE collective_involvements DROP COLUMN wellbeing;
+ALTER TABLE collective_involvements DROP COLUMN focus;
+ALTER TABLE collective_involvements DROP COLUMN capacity;
+ALTER TABLE collective_involvements DROP COLUMN wellbeing_visibility;
+ALTER TABLE collective_involvements DROP COLUMN focus_visibility;
+ALTER TABLE collective_involvements DROP COLUMN capacity_visibility;
@@ -0,0 +1,3 @@
+-- Add migration script here
+-- Who last saved the involvement. Empty when it was drafted automatically
+ALTER TABLE collective_involvements ADD COLUMN updated_by INTEGER REFERENCES people(id);
@@ -0,0 +1,18 @@
+-- Add migration script here
+-- Nothing wrote to the table, and its foreign key pointed at a table that doesn't exist
+DROP TABLE IF EXISTS "involvement_updates";
+
+CREATE TABLE IF NOT EXISTS "involvement_updates" (
+    "id" INTEGER NOT NULL,
+    "involvement_id" INTEGER NOT NULL,
+    "occurred_at" TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    -- Empty for the change that first recorded the involvement
+    "old_status" TEXT,
+    "new_status" TEXT NOT NULL,
+    "context" TEXT,
+    -- Empty when the change was made automatically
+    "author_id" INTEGER,
+    PRIMARY KEY("id" AUTOINCREMENT),
+    CONSTRAINT "involvement_updates_collective_involvements_FK" FOREIGN KEY("involvement_id") REFERENCES "collective_involvements"("id") ON DELETE CASCADE,
+    CONSTRAINT "involvement_updates_people_FK" FOREIGN KEY("author_id") REFERENCES "people"("id")
+);
@@ -0,0 +1,12 @@
+-- Add migration script here
+-- Changes to someone's status part way through an interval. Their involvement's own status
+-- holds from the start of the interval until the first of these
+CREATE TABLE IF NOT EXISTS "involvement_status_segments" (
+    "id" INTEGER NOT NULL,
+    "involvement_id" INTEGER NOT NULL,
+    "status" TEXT NOT NULL,
+    "effective_from" TEXT NOT NULL,
+    PRIMARY KEY("id" AUTOINCREMENT),
+    CONSTRAINT "involvement_status_segments_collective_involvements_FK" FOREIGN KEY("involvement_id") REFERENCES "collective_involvements"("id") ON DELETE CASCADE,
+    UNIQUE("involvement_id", "effective_from")
+);
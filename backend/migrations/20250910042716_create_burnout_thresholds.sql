@@ -0,0 +1,12 @@
+-- Add migration script here
+-- When the burnout risk report flags someone. Collectives without a row use the defaults
+CREATE TABLE IF NOT EXISTS "burnout_thresholds" (
+    "collective_id" INTEGER NOT NULL,
+    "convenor_streak" INTEGER NOT NULL DEFAULT 3,
+    "low_capacity_score" INTEGER NOT NULL DEFAULT -1,
+    "crews_at_low_capacity" INTEGER NOT NULL DEFAULT 2,
+    "falling_score_intervals" INTEGER NOT NULL DEFAULT 2,
+    "volunteering_streak" INTEGER NOT NULL DEFAULT 4,
+    PRIMARY KEY("collective_id"),
+    CONSTRAINT "burnout_thresholds_collectives_FK" FOREIGN KEY("collective_id") REFERENCES "collectives"("id") ON DELETE CASCADE
+);
@@ -0,0 +1,200 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use utoipa::ToSchema;
+
+use crate::{
+    analytics::repo::{
+        InvolvementHistoryRecord, find_burnout_thresholds, find_involvement_history,
+    },
+    shared::entities::{CollectiveId, Interval, InvolvementStatus},
+};
+
+// The scores people can give for their capacity, from lower to higher
+const CAPACITY_SCORES: std::ops::RangeInclusive<i64> = -1..=1;
+
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone, PartialEq)]
+pub struct BurnoutThresholds {
+    // Intervals in a row convening at least one crew
+    pub convenor_streak: i64,
+    // A capacity score at or below this, while in at least `crews_at_low_capacity` crews
+    pub low_capacity_score: i64,
+    pub crews_at_low_capacity: i64,
+    // Intervals in a row the capacity score has fallen
+    pub falling_score_intervals: i64,
+    // Intervals in a row participating and volunteering to convene, without a hiatus
+    pub volunteering_streak: i64,
+}
+
+impl Default for BurnoutThresholds {
+    fn default() -> Self {
+        BurnoutThresholds {
+            convenor_streak: 3,
+            low_capacity_score: -1,
+            crews_at_low_capacity: 2,
+            falling_score_intervals: 2,
+            volunteering_streak: 4,
+        }
+    }
+}
+
+impl BurnoutThresholds {
+    pub fn validate(&self) -> Result<(), &'static str> {
+        let streaks = [
+            self.convenor_streak,
+            self.crews_at_low_capacity,
+            self.falling_score_intervals,
+            self.volunteering_streak,
+        ];
+        if streaks.iter().any(|threshold| *threshold < 1) {
+            return Err("Streak and crew thresholds must be at least 1");
+        }
+        if !CAPACITY_SCORES.contains(&self.low_capacity_score) {
+            return Err("The low capacity score must be a capacity score, from -1 to 1");
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone)]
+pub enum RiskSignal {
+    ConvenorStreak {
+        intervals: i64,
+    },
+    ManyCrewsAtLowCapacity {
+        crew_count: i64,
+        capacity_score: i64,
+    },
+    // Oldest first, ending with the interval being reported on
+    FallingCapacity {
+        scores: Vec<i64>,
+    },
+    VolunteeringStreak {
+        intervals: i64,
+    },
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone)]
+pub struct PersonRisk {
+    pub person_id: i64,
+    pub signals: Vec<RiskSignal>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone)]
+pub struct BurnoutRiskReport {
+    pub interval_id: i64,
+    pub thresholds: BurnoutThresholds,
+    // Only those with at least one signal
+    pub people: Vec<PersonRisk>,
+}
+
+// Looks back over everyone's history up to the interval for patterns that have come before
+// people burning out
+pub async fn find_burnout_risk_report(
+    collective_id: CollectiveId,
+    interval: &Interval,
+    pool: &SqlitePool,
+) -> Result<BurnoutRiskReport, sqlx::Error> {
+    let thresholds = find_burnout_thresholds(collective_id.clone(), pool).await?;
+    let history = find_involvement_history(collective_id, interval.typed_id(), pool).await?;
+
+    let mut history_by_person: BTreeMap<i64, Vec<InvolvementHistoryRecord>> = BTreeMap::new();
+    for record in history {
+        history_by_person
+            .entry(record.person_id)
+            .or_default()
+            .push(record);
+    }
+
+    let people = history_by_person
+        .into_iter()
+        .map(|(person_id, history)| PersonRisk {
+            person_id,
+            signals: risk_signals(&history, interval.sequence, &thresholds),
+        })
+        .filter(|risk| !risk.signals.is_empty())
+        .collect();
+
+    Ok(BurnoutRiskReport {
+        interval_id: interval.id,
+        thresholds,
+        people,
+    })
+}
+
+// The history needs to be one person's, in interval order
+fn risk_signals(
+    history: &[InvolvementHistoryRecord],
+    sequence: i64,
+    thresholds: &BurnoutThresholds,
+) -> Vec<RiskSignal> {
+    let mut signals = vec![];
+
+    let convened = trailing_streak(history, sequence, |record| record.convened);
+    if convened.len() as i64 >= thresholds.convenor_streak {
+        signals.push(RiskSignal::ConvenorStreak {
+            intervals: convened.len() as i64,
+        });
+    }
+
+    if let Some(latest) = trailing_streak(history, sequence, |_| true).last()
+        && let Some(capacity_score) = latest.capacity_score
+        && capacity_score <= thresholds.low_capacity_score
+        && latest.crew_count >= thresholds.crews_at_low_capacity
+    {
+        signals.push(RiskSignal::ManyCrewsAtLowCapacity {
+            crew_count: latest.crew_count,
+            capacity_score,
+        });
+    }
+
+    let scores: Vec<i64> =
+        trailing_streak(history, sequence, |record| record.capacity_score.is_some())
+            .iter()
+            .filter_map(|record| record.capacity_score)
+            .collect();
+    let falls = scores
+        .windows(2)
+        .rev()
+        .take_while(|pair| pair[1] < pair[0])
+        .count();
+    if falls as i64 >= thresholds.falling_score_intervals {
+        signals.push(RiskSignal::FallingCapacity {
+            scores: scores[scores.len() - falls - 1..].to_vec(),
+        });
+    }
+
+    let volunteered = trailing_streak(history, sequence, |record| {
+        record.volunteered && record.status == InvolvementStatus::Participating
+    });
+    if volunteered.len() as i64 >= thresholds.volunteering_streak {
+        signals.push(RiskSignal::VolunteeringStreak {
+            intervals: volunteered.len() as i64,
+        });
+    }
+
+    signals
+}
+
+// The records in a row that match, ending with the interval with the given sequence. An
+// interval the person wasn't involved in ends the streak
+fn trailing_streak(
+    history: &[InvolvementHistoryRecord],
+    sequence: i64,
+    matches: impl Fn(&InvolvementHistoryRecord) -> bool,
+) -> &[InvolvementHistoryRecord] {
+    let mut start = history.len();
+    let mut expected_sequence = sequence;
+    while start > 0 {
+        let record = &history[start - 1];
+        if record.sequence != expected_sequence || !matches(record) {
+            break;
+        }
+        start -= 1;
+        expected_sequence -= 1;
+    }
+
+    &history[start..]
+}
@@ -0,0 +1,206 @@
+use axum::{Extension, Json, extract::Path, http::StatusCode, response::IntoResponse};
+use sqlx::SqlitePool;
+use utoipa_axum::{router::OpenApiRouter, routes};
+
+use crate::{
+    analytics::{
+        burnout::{BurnoutRiskReport, BurnoutThresholds, find_burnout_risk_report},
+        repo::{CollectiveAnalytics, PersonCapacityTrend},
+    },
+    auth::{
+        auth_backend::AuthSession,
+        permissions::{find_acting_person, require_admin},
+        visibility::{Redact, Viewer},
+    },
+    intervals::repo::find_interval,
+    people::repo::find_person_by_id,
+    shared::{
+        default_collective_id,
+        entities::{IntervalId, PersonId, VisibilityLevel},
+    },
+};
+
+pub mod burnout;
+pub mod repo;
+
+pub fn router() -> OpenApiRouter {
+    OpenApiRouter::new()
+        .routes(routes!(get_collective_analytics))
+        .routes(routes!(get_person_capacity_trend))
+        .routes(routes!(get_burnout_risk_report))
+        .routes(routes!(get_burnout_thresholds, update_burnout_thresholds))
+}
+
+// Capacity and participation across the collective, interval by interval
+#[utoipa::path(get, path = "/",
+    responses(
+        (status = 200, body = CollectiveAnalytics),
+        (status = UNAUTHORIZED, description = "Not logged in", body = ()),
+        (status = NOT_FOUND, description = "Not a member of the collective", body = ()),
+        (status = INTERNAL_SERVER_ERROR, description = "Internal server error", body = ()),
+    ),
+)]
+pub async fn get_collective_analytics(
+    Extension(pool): Extension<SqlitePool>,
+    auth_session: AuthSession,
+) -> impl IntoResponse {
+    if let Err(status) = find_acting_person(&auth_session, default_collective_id(), &pool).await {
+        return (status, ()).into_response();
+    }
+
+    match repo::find_collective_analytics(default_collective_id(), &pool).await {
+        Ok(analytics) => (StatusCode::OK, Json(analytics)).into_response(),
+        Err(_) => (StatusCode::INTERNAL_SERVER_ERROR, ()).into_response(),
+    }
+}
+
+// Someone's capacity scores over time, for them and whoever can see what they share for care
+#[utoipa::path(get, path = "/people/{person_id}/capacity",
+    params(
+        ("person_id" = i64, Path, description = "Person ID")
+    ),
+    responses(
+        (status = 200, body = PersonCapacityTrend),
+        (status = UNAUTHORIZED, description = "Not logged in", body = ()),
+        (status = FORBIDDEN, description = "Not allowed to see the person's capacity", body = ()),
+        (status = NOT_FOUND, description = "Person not found", body = ()),
+        (status = INTERNAL_SERVER_ERROR, description = "Internal server error", body = ()),
+    ),
+)]
+pub async fn get_person_capacity_trend(
+    Path(person_id): Path<i64>,
+    Extension(pool): Extension<SqlitePool>,
+    auth_session: AuthSession,
+) -> impl IntoResponse {
+    let acting_person =
+        match find_acting_person(&auth_session, default_collective_id(), &pool).await {
+            Ok(acting_person) => acting_person,
+            Err(status) => return (status, ()).into_response(),
+        };
+
+    let person_id = PersonId::new(person_id);
+    match find_person_by_id(person_id.clone(), default_collective_id(), &pool).await {
+        Ok(_) => {}
+        Err(sqlx::Error::RowNotFound) => return (StatusCode::NOT_FOUND, ()).into_response(),
+        Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR, ()).into_response(),
+    }
+
+    let Ok(viewer) = Viewer::for_person(&acting_person, default_collective_id(), &pool).await
+    else {
+        return (StatusCode::INTERNAL_SERVER_ERROR, ()).into_response();
+    };
+
+    let trend =
+        match repo::find_person_capacity_trend(default_collective_id(), person_id, &pool).await {
+            Ok(trend) => trend,
+            Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR, ()).into_response(),
+        };
+
+    // Checked before the points, as there are none before the first interval
+    let own_trend = acting_person.person_id.id == trend.person_id;
+    let can_see_any = trend
+        .points
+        .iter()
+        .any(|point| viewer.can_see(VisibilityLevel::Care, trend.person_id, point.interval_id));
+    if !own_trend && !can_see_any {
+        return (StatusCode::FORBIDDEN, ()).into_response();
+    }
+
+    (StatusCode::OK, Json(trend.redact_for(&viewer))).into_response()
+}
+
+// Who might be heading for burnout, for the care crew and care roles only
+#[utoipa::path(get, path = "/burnout/interval/{interval_id}",
+    params(
+        ("interval_id" = i64, Path, description = "Interval ID to report on")
+    ),
+    responses(
+        (status = 200, body = BurnoutRiskReport),
+        (status = UNAUTHORIZED, description = "Not logged in", body = ()),
+        (status = FORBIDDEN, description = "Only those caring for the interval can see the report", body = ()),
+        (status = NOT_FOUND, description = "Interval not found", body = ()),
+        (status = INTERNAL_SERVER_ERROR, description = "Internal server error", body = ()),
+    ),
+)]
+pub async fn get_burnout_risk_report(
+    Path(interval_id): Path<i64>,
+    Extension(pool): Extension<SqlitePool>,
+    auth_session: AuthSession,
+) -> impl IntoResponse {
+    let acting_person =
+        match find_acting_person(&auth_session, default_collective_id(), &pool).await {
+            Ok(acting_person) => acting_person,
+            Err(status) => return (status, ()).into_response(),
+        };
+
+    let interval = match find_interval(IntervalId::new(interval_id), &pool).await {
+        Ok(interval) => interval,
+        Err(sqlx::Error::RowNotFound) => return (StatusCode::NOT_FOUND, ()).into_response(),
+        Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR, ()).into_response(),
+    };
+
+    let Ok(viewer) = Viewer::for_person(&acting_person, default_collective_id(), &pool).await
+    else {
+        return (StatusCode::INTERNAL_SERVER_ERROR, ()).into_response();
+    };
+    if !viewer.cares_in(interval.id) {
+        return (StatusCode::FORBIDDEN, ()).into_response();
+    }
+
+    match find_burnout_risk_report(default_collective_id(), &interval, &pool).await {
+        Ok(report) => (StatusCode::OK, Json(report)).into_response(),
+        Err(_) => (StatusCode::INTERNAL_SERVER_ERROR, ()).into_response(),
+    }
+}
+
+#[utoipa::path(get, path = "/burnout/thresholds",
+    responses(
+        (status = 200, body = BurnoutThresholds),
+        (status = UNAUTHORIZED, description = "Not logged in", body = ()),
+        (status = NOT_FOUND, description = "Not a member of the collective", body = ()),
+        (status = INTERNAL_SERVER_ERROR, description = "Internal server error", body = ()),
+    ),
+)]
+pub async fn get_burnout_thresholds(
+    Extension(pool): Extension<SqlitePool>,
+    auth_session: AuthSession,
+) -> impl IntoResponse {
+    if let Err(status) = find_acting_person(&auth_session, default_collective_id(), &pool).await {
+        return (status, ()).into_response();
+    }
+
+    match repo::find_burnout_thresholds(default_collective_id(), &pool).await {
+        Ok(thresholds) => (StatusCode::OK, Json(thresholds)).into_response(),
+        Err(_) => (StatusCode::INTERNAL_SERVER_ERROR, ()).into_response(),
+    }
+}
+
+#[utoipa::path(put, path = "/burnout/thresholds",
+    request_body(content = BurnoutThresholds, content_type = "application/json"),
+    responses(
+        (status = 200, body = BurnoutThresholds),
+        (status = BAD_REQUEST, description = "The thresholds aren't valid", body = ()),
+        (status = FORBIDDEN, description = "Only admins can change the thresholds", body = ()),
+        (status = INTERNAL_SERVER_ERROR, description = "Internal server error", body = ()),
+    ),
+)]
+pub async fn update_burnout_thresholds(
+    Extension(pool): Extension<SqlitePool>,
+    auth_session: AuthSession,
+    Json(input): Json<BurnoutThresholds>,
+) -> impl IntoResponse {
+    if let Err(status) = require_admin(&auth_session, default_collective_id(), &pool).await {
+        return (status, ()).into_response();
+    }
+
+    if let Err(message) = input.validate() {
+        return (StatusCode::BAD_REQUEST, message).into_response();
+    }
+
+    println!("Updating burnout thresholds: {:?}", input);
+
+    match repo::upsert_burnout_thresholds(default_collective_id(), input, &pool).await {
+        Ok(thresholds) => (StatusCode::OK, Json(thresholds)).into_response(),
+        Err(_) => (StatusCode::INTERNAL_SERVER_ERROR, ()).into_response(),
+    }
+}
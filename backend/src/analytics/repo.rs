@@ -0,0 +1,348 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use utoipa::ToSchema;
+
+use crate::{
+    analytics::burnout::BurnoutThresholds,
+    shared::entities::{CollectiveId, IntervalId, InvolvementStatus, PersonId},
+};
+
+// Capacity scores are only reported for groups of at least this many people, so that nobody's
+// score can be worked out from the figures for a small crew
+pub const MIN_GROUP_SIZE: i64 = 5;
+
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone)]
+pub struct ScoreCount {
+    pub score: i64,
+    pub count: i64,
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone)]
+pub struct CrewCapacity {
+    pub crew_id: i64,
+    pub member_count: i64,
+    // None when too few members gave a score
+    pub average_capacity: Option<f64>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone)]
+pub struct IntervalAnalytics {
+    pub interval_id: i64,
+    pub start_date: String,
+    pub involvement_count: i64,
+    pub participating_count: i64,
+    pub on_hiatus_count: i64,
+    pub exiting_count: i64,
+    // Each as a share of everyone involved in the interval, from 0 to 1
+    pub participation_rate: f64,
+    pub hiatus_rate: f64,
+    pub exit_rate: f64,
+    pub scored_count: i64,
+    // None, with an empty distribution, when too few people gave a score
+    pub average_capacity: Option<f64>,
+    pub capacity_distribution: Vec<ScoreCount>,
+    pub crews: Vec<CrewCapacity>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone)]
+pub struct CollectiveAnalytics {
+    pub min_group_size: i64,
+    // Every interval, oldest first
+    pub intervals: Vec<IntervalAnalytics>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone)]
+pub struct CapacityPoint {
+    pub interval_id: i64,
+    pub start_date: String,
+    pub status: Option<InvolvementStatus>,
+    pub capacity_score: Option<i64>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone)]
+pub struct PersonCapacityTrend {
+    pub person_id: i64,
+    // Every interval, oldest first, including those the person wasn't involved in
+    pub points: Vec<CapacityPoint>,
+}
+
+// Drafts haven't been confirmed by the person, so they're left out of every figure
+pub async fn find_collective_analytics(
+    collective_id: CollectiveId,
+    pool: &SqlitePool,
+) -> Result<CollectiveAnalytics, sqlx::Error> {
+    let rows = sqlx::query!(
+        "SELECT
+            intervals.id as \"interval_id!: i64\",
+            intervals.start_date,
+            COUNT(collective_involvements.id) as \"involvement_count!: i64\",
+            COUNT(CASE WHEN collective_involvements.status = 'Participating' THEN 1 END)
+                as \"participating_count!: i64\",
+            COUNT(CASE WHEN collective_involvements.status = 'OnHiatus' THEN 1 END)
+                as \"on_hiatus_count!: i64\",
+            COUNT(CASE WHEN collective_involvements.status = 'Exiting' THEN 1 END)
+                as \"exiting_count!: i64\",
+            COUNT(collective_involvements.capacity_score) as \"scored_count!: i64\",
+            CASE WHEN COUNT(collective_involvements.capacity_score) >= ?
+                THEN AVG(collective_involvements.capacity_score) END
+                as \"average_capacity?: f64\"
+        FROM intervals
+        LEFT JOIN collective_involvements
+            ON collective_involvements.interval_id = intervals.id
+            AND collective_involvements.draft = FALSE
+        WHERE intervals.collective_id = ?
+        GROUP BY intervals.id
+        ORDER BY intervals.start_date",
+        MIN_GROUP_SIZE,
+        collective_id.id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let mut distributions = find_capacity_distributions(collective_id.clone(), pool).await?;
+    let mut crews = find_crew_capacities(collective_id, pool).await?;
+
+    let intervals = rows
+        .into_iter()
+        .map(|row| {
+            let rate = |count: i64| {
+                if row.involvement_count == 0 {
+                    0.0
+                } else {
+                    count as f64 / row.involvement_count as f64
+                }
+            };
+
+            IntervalAnalytics {
+                interval_id: row.interval_id,
+                start_date: row.start_date,
+                involvement_count: row.involvement_count,
+                participating_count: row.participating_count,
+                on_hiatus_count: row.on_hiatus_count,
+                exiting_count: row.exiting_count,
+                participation_rate: rate(row.participating_count),
+                hiatus_rate: rate(row.on_hiatus_count),
+                exit_rate: rate(row.exiting_count),
+                scored_count: row.scored_count,
+                average_capacity: row.average_capacity,
+                capacity_distribution: distributions.remove(&row.interval_id).unwrap_or_default(),
+                crews: crews.remove(&row.interval_id).unwrap_or_default(),
+            }
+        })
+        .collect();
+
+    Ok(CollectiveAnalytics {
+        min_group_size: MIN_GROUP_SIZE,
+        intervals,
+    })
+}
+
+// How many people gave each capacity score, by interval
+async fn find_capacity_distributions(
+    collective_id: CollectiveId,
+    pool: &SqlitePool,
+) -> Result<HashMap<i64, Vec<ScoreCount>>, sqlx::Error> {
+    let rows = sqlx::query!(
+        "SELECT
+            interval_id,
+            capacity_score as \"score!: i64\",
+            COUNT(*) as \"count!: i64\"
+        FROM collective_involvements
+        WHERE collective_id = ? AND draft = FALSE AND capacity_score IS NOT NULL
+            AND interval_id IN (
+                SELECT interval_id
+                FROM collective_involvements
+                WHERE collective_id = ? AND draft = FALSE
+                GROUP BY interval_id
+                HAVING COUNT(capacity_score) >= ?
+            )
+        GROUP BY interval_id, capacity_score
+        ORDER BY capacity_score",
+        collective_id.id,
+        collective_id.id,
+        MIN_GROUP_SIZE
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let mut distributions: HashMap<i64, Vec<ScoreCount>> = HashMap::new();
+    for row in rows {
+        distributions
+            .entry(row.interval_id)
+            .or_default()
+            .push(ScoreCount {
+                score: row.score,
+                count: row.count,
+            });
+    }
+
+    Ok(distributions)
+}
+
+// Each crew's members and average capacity score, by interval
+async fn find_crew_capacities(
+    collective_id: CollectiveId,
+    pool: &SqlitePool,
+) -> Result<HashMap<i64, Vec<CrewCapacity>>, sqlx::Error> {
+    let rows = sqlx::query!(
+        "SELECT
+            crew_involvements.interval_id,
+            crew_involvements.crew_id,
+            COUNT(*) as \"member_count!: i64\",
+            CASE WHEN COUNT(collective_involvements.capacity_score) >= ?
+                THEN AVG(collective_involvements.capacity_score) END
+                as \"average_capacity?: f64\"
+        FROM crew_involvements
+        INNER JOIN collective_involvements
+            ON collective_involvements.person_id = crew_involvements.person_id
+            AND collective_involvements.interval_id = crew_involvements.interval_id
+        WHERE collective_involvements.collective_id = ?
+            AND collective_involvements.draft = FALSE
+            AND crew_involvements.membership = 'Member'
+        GROUP BY crew_involvements.interval_id, crew_involvements.crew_id
+        ORDER BY crew_involvements.crew_id",
+        MIN_GROUP_SIZE,
+        collective_id.id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let mut crews: HashMap<i64, Vec<CrewCapacity>> = HashMap::new();
+    for row in rows {
+        crews
+            .entry(row.interval_id)
+            .or_default()
+            .push(CrewCapacity {
+                crew_id: row.crew_id,
+                member_count: row.member_count,
+                average_capacity: row.average_capacity,
+            });
+    }
+
+    Ok(crews)
+}
+
+// Unredacted, so callers need to redact it for whoever it's going to
+pub async fn find_person_capacity_trend(
+    collective_id: CollectiveId,
+    person_id: PersonId,
+    pool: &SqlitePool,
+) -> Result<PersonCapacityTrend, sqlx::Error> {
+    let points = sqlx::query_as!(
+        CapacityPoint,
+        "SELECT
+            intervals.id as \"interval_id!: i64\",
+            intervals.start_date,
+            collective_involvements.status as \"status?: InvolvementStatus\",
+            collective_involvements.capacity_score
+        FROM intervals
+        LEFT JOIN collective_involvements
+            ON collective_involvements.interval_id = intervals.id
+            AND collective_involvements.person_id = ?
+            AND collective_involvements.draft = FALSE
+        WHERE intervals.collective_id = ?
+        ORDER BY intervals.start_date",
+        person_id.id,
+        collective_id.id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(PersonCapacityTrend {
+        person_id: person_id.id,
+        points,
+    })
+}
+
+// Someone's involvement in an interval, with what they took on in their crews
+pub struct InvolvementHistoryRecord {
+    pub person_id: i64,
+    pub sequence: i64,
+    pub status: InvolvementStatus,
+    pub capacity_score: Option<i64>,
+    pub crew_count: i64,
+    pub convened: bool,
+    pub volunteered: bool,
+}
+
+// Everyone's involvements up to and including the interval, by person and then in interval order
+pub async fn find_involvement_history(
+    collective_id: CollectiveId,
+    up_to_interval_id: IntervalId,
+    pool: &SqlitePool,
+) -> Result<Vec<InvolvementHistoryRecord>, sqlx::Error> {
+    sqlx::query_as!(
+        InvolvementHistoryRecord,
+        "SELECT
+            collective_involvements.person_id as \"person_id!: i64\",
+            intervals.sequence as \"sequence!: i64\",
+            collective_involvements.status as \"status!: InvolvementStatus\",
+            collective_involvements.capacity_score,
+            COUNT(crew_involvements.id) as \"crew_count!: i64\",
+            COALESCE(MAX(crew_involvements.convenor), FALSE) as \"convened!: bool\",
+            COALESCE(MAX(crew_involvements.volunteered_convenor), FALSE) as \"volunteered!: bool\"
+        FROM collective_involvements
+        INNER JOIN intervals ON intervals.id = collective_involvements.interval_id
+        LEFT JOIN crew_involvements
+            ON crew_involvements.person_id = collective_involvements.person_id
+            AND crew_involvements.interval_id = collective_involvements.interval_id
+            AND crew_involvements.membership = 'Member'
+        WHERE collective_involvements.collective_id = ?
+            AND collective_involvements.draft = FALSE
+            AND intervals.start_date <= (SELECT start_date FROM intervals WHERE id = ?)
+        GROUP BY collective_involvements.id
+        ORDER BY collective_involvements.person_id, intervals.sequence",
+        collective_id.id,
+        up_to_interval_id.id
+    )
+    .fetch_all(pool)
+    .await
+}
+
+pub async fn find_burnout_thresholds(
+    collective_id: CollectiveId,
+    pool: &SqlitePool,
+) -> Result<BurnoutThresholds, sqlx::Error> {
+    let thresholds = sqlx::query_as!(
+        BurnoutThresholds,
+        "SELECT convenor_streak, low_capacity_score, crews_at_low_capacity,
+            falling_score_intervals, volunteering_streak
+        FROM burnout_thresholds
+        WHERE collective_id = ?",
+        collective_id.id
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(thresholds.unwrap_or_default())
+}
+
+pub async fn upsert_burnout_thresholds(
+    collective_id: CollectiveId,
+    thresholds: BurnoutThresholds,
+    pool: &SqlitePool,
+) -> Result<BurnoutThresholds, sqlx::Error> {
+    sqlx::query!(
+        "INSERT INTO burnout_thresholds (collective_id, convenor_streak, low_capacity_score,
+            crews_at_low_capacity, falling_score_intervals, volunteering_streak)
+        VALUES (?, ?, ?, ?, ?, ?)
+        ON CONFLICT (collective_id) DO UPDATE SET
+            convenor_streak = excluded.convenor_streak,
+            low_capacity_score = excluded.low_capacity_score,
+            crews_at_low_capacity = excluded.crews_at_low_capacity,
+            falling_score_intervals = excluded.falling_score_intervals,
+            volunteering_streak = excluded.volunteering_streak",
+        collective_id.id,
+        thresholds.convenor_streak,
+        thresholds.low_capacity_score,
+        thresholds.crews_at_low_capacity,
+        thresholds.falling_score_intervals,
+        thresholds.volunteering_streak
+    )
+    .execute(pool)
+    .await?;
+
+    find_burnout_thresholds(collective_id, pool).await
+}
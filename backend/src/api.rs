@@ -7,6 +7,12 @@ pub fn private_api_router() -> OpenApiRouter {
         .nest("/intervals", crate::intervals::router())
         .nest("/crews", crate::crews::router())
         .nest("/people", crate::people::router())
+        .nest("/calendar", crate::calendar::router())
+        .nest("/tasks", crate::tasks::router())
+        .nest("/hiatus", crate::hiatus::router())
+        .nest("/exits", crate::exits::router())
+        .nest("/check-ins", crate::check_ins::router())
+        .nest("/analytics", crate::analytics::router())
 }
 
 pub fn public_api_router() -> OpenApiRouter {
@@ -24,12 +24,13 @@ impl<'a> AuthRepo<'a> {
         AuthRepo { pool }
     }
 
+    // Disabled accounts, like those of people who have exited, are treated as not existing
     pub async fn user_for_email(&self, email: String) -> Result<Option<AuthUser>, AuthRepoError> {
         sqlx::query_as!(
             AuthUser,
             "SELECT id, email, hashed_password
             FROM users
-            WHERE LOWER(email) = LOWER(?)",
+            WHERE LOWER(email) = LOWER(?) AND disabled = FALSE",
             email
         )
         .fetch_optional(self.pool)
@@ -42,7 +43,7 @@ impl<'a> AuthRepo<'a> {
             AuthUser,
             "SELECT id, email, hashed_password
             FROM users
-            WHERE id = ?",
+            WHERE id = ? AND disabled = FALSE",
             user_id
         )
         .fetch_optional(self.pool)
@@ -4,6 +4,8 @@ pub mod auth_backend;
 mod auth_email;
 mod auth_repo;
 pub mod auth_routes;
+pub mod permissions;
+pub mod visibility;
 
 pub fn router() -> utoipa_axum::router::OpenApiRouter {
     auth_router()
@@ -0,0 +1,67 @@
+use axum::http::StatusCode;
+use sqlx::SqlitePool;
+
+use crate::{
+    auth::auth_backend::AuthSession,
+    shared::entities::{CollectiveId, PersonId, PersonRole},
+};
+
+#[derive(Debug, Clone)]
+pub struct ActingPerson {
+    pub person_id: PersonId,
+    pub role: PersonRole,
+}
+
+impl ActingPerson {
+    pub fn is_admin(&self) -> bool {
+        self.role == PersonRole::Admin
+    }
+}
+
+// Resolves the logged in user to their person in the collective, returning the
+// status code to respond with if that isn't possible.
+pub async fn find_acting_person(
+    auth_session: &AuthSession,
+    collective_id: CollectiveId,
+    pool: &SqlitePool,
+) -> Result<ActingPerson, StatusCode> {
+    let Some(user) = auth_session.user.as_ref() else {
+        return Err(StatusCode::UNAUTHORIZED);
+    };
+
+    let result = sqlx::query!(
+        "SELECT id, role as \"role: PersonRole\"
+        FROM people
+        WHERE user_id = ? AND collective_id = ?",
+        user.id,
+        collective_id.id
+    )
+    .fetch_optional(pool)
+    .await;
+
+    match result {
+        Ok(Some(row)) => Ok(ActingPerson {
+            person_id: PersonId::new(row.id),
+            role: row.role,
+        }),
+        Ok(None) => Err(StatusCode::NOT_FOUND),
+        Err(error) => {
+            eprintln!("Failed to find acting person: {}", error);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+pub async fn require_admin(
+    auth_session: &AuthSession,
+    collective_id: CollectiveId,
+    pool: &SqlitePool,
+) -> Result<ActingPerson, StatusCode> {
+    let acting_person = find_acting_person(auth_session, collective_id, pool).await?;
+
+    if acting_person.is_admin() {
+        Ok(acting_person)
+    } else {
+        Err(StatusCode::FORBIDDEN)
+    }
+}
@@ -0,0 +1,404 @@
+use std::collections::HashSet;
+
+use axum::http::StatusCode;
+use sqlx::SqlitePool;
+
+use crate::{
+    analytics::repo::PersonCapacityTrend,
+    auth::{
+        auth_backend::AuthSession,
+        permissions::{ActingPerson, find_acting_person},
+    },
+    me::{history::ParticipationHistory, repo::PersonIntervalInvolvementData},
+    my_collective::repo::{InitialData, IntervalInvolvementData, InvolvementData},
+    shared::entities::{CollectiveId, CollectiveInvolvement, PersonRole, VisibilityLevel},
+};
+
+// Roles that can see anything shared for care, along with the care crew
+const CARE_ROLES: [PersonRole; 1] = [PersonRole::Admin];
+
+// Who involvement data is being sent to. Everything read out of the API or pushed as an
+// event goes through `redact_for` with the viewer it's going to.
+#[derive(Clone, Debug, Default)]
+pub struct Viewer {
+    person_id: Option<i64>,
+    role: Option<PersonRole>,
+    // (interval, person) for everyone in a crew the viewer convenes
+    convened_people: HashSet<(i64, i64)>,
+    // Intervals the viewer is in the care crew for
+    care_interval_ids: HashSet<i64>,
+}
+
+impl Viewer {
+    // Realtime events reach every connected client, so they only carry what anyone can see
+    pub fn everyone() -> Self {
+        Viewer::default()
+    }
+
+    pub async fn for_person(
+        acting_person: &ActingPerson,
+        collective_id: CollectiveId,
+        pool: &SqlitePool,
+    ) -> Result<Self, sqlx::Error> {
+        let convened_people = sqlx::query!(
+            "SELECT member.interval_id, member.person_id
+            FROM crew_involvements convenor
+            INNER JOIN crew_involvements member
+                ON member.crew_id = convenor.crew_id AND member.interval_id = convenor.interval_id
+            WHERE convenor.person_id = ? AND convenor.convenor = TRUE
+                AND member.membership = 'Member'",
+            acting_person.person_id.id
+        )
+        .fetch_all(pool)
+        .await?
+        .into_iter()
+        .map(|row| (row.interval_id, row.person_id))
+        .collect();
+
+        let care_interval_ids = sqlx::query!(
+            "SELECT crew_involvements.interval_id
+            FROM crew_involvements
+            INNER JOIN collectives ON collectives.care_crew_id = crew_involvements.crew_id
+            WHERE collectives.id = ? AND crew_involvements.person_id = ?
+                AND crew_involvements.membership = 'Member'",
+            collective_id.id,
+            acting_person.person_id.id
+        )
+        .fetch_all(pool)
+        .await?
+        .into_iter()
+        .map(|row| row.interval_id)
+        .collect();
+
+        Ok(Viewer {
+            person_id: Some(acting_person.person_id.id),
+            role: Some(acting_person.role.clone()),
+            convened_people,
+            care_interval_ids,
+        })
+    }
+
+    // Whether the viewer is trusted with what people share for care in the interval
+    pub fn cares_in(&self, interval_id: i64) -> bool {
+        self.care_interval_ids.contains(&interval_id)
+            || self
+                .role
+                .as_ref()
+                .is_some_and(|role| CARE_ROLES.contains(role))
+    }
+
+    pub fn can_see(&self, level: VisibilityLevel, person_id: i64, interval_id: i64) -> bool {
+        if self.person_id == Some(person_id) {
+            return true;
+        }
+
+        match level {
+            VisibilityLevel::OnlyMe => false,
+            VisibilityLevel::CrewConvenors => {
+                self.convened_people.contains(&(interval_id, person_id))
+            }
+            VisibilityLevel::Care => self.cares_in(interval_id),
+            VisibilityLevel::Collective => true,
+        }
+    }
+}
+
+// Anyone who isn't logged in, or isn't in the collective, only sees what everyone can
+pub async fn find_viewer(
+    auth_session: &AuthSession,
+    collective_id: CollectiveId,
+    pool: &SqlitePool,
+) -> Result<Viewer, StatusCode> {
+    match find_acting_person(auth_session, collective_id.clone(), pool).await {
+        Ok(acting_person) => Viewer::for_person(&acting_person, collective_id, pool)
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR),
+        Err(StatusCode::UNAUTHORIZED | StatusCode::NOT_FOUND) => Ok(Viewer::everyone()),
+        Err(status) => Err(status),
+    }
+}
+
+pub trait Redact {
+    fn redact_for(self, viewer: &Viewer) -> Self;
+}
+
+impl<T: Redact> Redact for Vec<T> {
+    fn redact_for(self, viewer: &Viewer) -> Self {
+        self.into_iter()
+            .map(|item| item.redact_for(viewer))
+            .collect()
+    }
+}
+
+impl<T: Redact> Redact for Option<T> {
+    fn redact_for(self, viewer: &Viewer) -> Self {
+        self.map(|item| item.redact_for(viewer))
+    }
+}
+
+impl Redact for CollectiveInvolvement {
+    fn redact_for(mut self, viewer: &Viewer) -> Self {
+        let (person_id, interval_id) = (self.person_id, self.interval_id);
+        self.check_in_answers
+            .retain(|answer| viewer.can_see(answer.visibility, person_id, interval_id));
+        self
+    }
+}
+
+impl Redact for PersonIntervalInvolvementData {
+    fn redact_for(mut self, viewer: &Viewer) -> Self {
+        self.collective_involvement = self.collective_involvement.redact_for(viewer);
+        self
+    }
+}
+
+impl Redact for IntervalInvolvementData {
+    fn redact_for(mut self, viewer: &Viewer) -> Self {
+        self.collective_involvements = self.collective_involvements.redact_for(viewer);
+        self
+    }
+}
+
+impl Redact for InvolvementData {
+    fn redact_for(mut self, viewer: &Viewer) -> Self {
+        self.current_interval = self.current_interval.redact_for(viewer);
+        self.next_interval = self.next_interval.redact_for(viewer);
+        self
+    }
+}
+
+impl Redact for InitialData {
+    fn redact_for(mut self, viewer: &Viewer) -> Self {
+        self.involvements = self.involvements.redact_for(viewer);
+        self
+    }
+}
+
+impl Redact for ParticipationHistory {
+    fn redact_for(mut self, viewer: &Viewer) -> Self {
+        self.intervals = self.intervals.redact_for(viewer);
+        self
+    }
+}
+
+// Capacity scores are shared for care, so only the person and those caring for them see them
+impl Redact for PersonCapacityTrend {
+    fn redact_for(mut self, viewer: &Viewer) -> Self {
+        let person_id = self.person_id;
+        for point in &mut self.points {
+            if !viewer.can_see(VisibilityLevel::Care, person_id, point.interval_id) {
+                point.capacity_score = None;
+            }
+        }
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{Value, json};
+
+    use crate::{shared::events::AppEvent, test_support::TestApp};
+
+    struct Seeded {
+        interval_id: i64,
+        owner_id: i64,
+    }
+
+    // Someone in a crew who has answered a question at each visibility level, along with their
+    // crew's convenor, someone in the care crew and someone unrelated
+    async fn seed(app: &TestApp) -> Seeded {
+        let owner_id = app.add_person("owner@example.com", "Member").await;
+        let convenor_id = app.add_person("convenor@example.com", "Member").await;
+        let carer_id = app.add_person("carer@example.com", "Member").await;
+        app.add_person("other@example.com", "Member").await;
+        let interval_id = app.add_interval(7, "Active", 1).await;
+
+        app.execute(
+            "INSERT INTO crews (id, name, collective_id) VALUES (1, 'Crew', 1), (2, 'Care', 1)",
+        )
+        .await;
+        app.execute("UPDATE collectives SET care_crew_id = 2 WHERE id = 1")
+            .await;
+        app.execute(&format!(
+            "INSERT INTO crew_involvements (person_id, crew_id, interval_id, convenor)
+            VALUES ({owner_id}, 1, {interval_id}, FALSE), ({convenor_id}, 1, {interval_id}, TRUE),
+                ({carer_id}, 2, {interval_id}, FALSE)"
+        ))
+        .await;
+        for (position, key) in ["only_me", "convenors", "care", "everyone"]
+            .iter()
+            .enumerate()
+        {
+            app.execute(&format!(
+                "INSERT INTO check_in_questions (collective_id, interval_id, key, prompt, kind, position)
+                VALUES (1, {interval_id}, '{key}', '{key}?', 'FreeText', {position})"
+            ))
+            .await;
+        }
+
+        let (status, _) = app
+            .request(
+                "POST",
+                &format!("/api/me/interval/{interval_id}/my_participation"),
+                &app.log_in("owner@example.com").await,
+                Some(participation_input()),
+            )
+            .await;
+        assert_eq!(status, 200);
+
+        Seeded {
+            interval_id,
+            owner_id,
+        }
+    }
+
+    fn participation_input() -> Value {
+        json!({
+            "collective_id": 1,
+            "capacity_score": 1,
+            "participation_intention": "OptIn",
+            "check_in_answers": [
+                { "question_key": "only_me", "answer": "a", "visibility": "OnlyMe" },
+                { "question_key": "convenors", "answer": "b", "visibility": "CrewConvenors" },
+                { "question_key": "care", "answer": "c", "visibility": "Care" },
+                { "question_key": "everyone", "answer": "d", "visibility": "Collective" },
+            ],
+        })
+    }
+
+    // Who's looking, and the answers they should see
+    const VIEWERS: [(&str, &[&str]); 4] = [
+        (
+            "owner@example.com",
+            &["only_me", "convenors", "care", "everyone"],
+        ),
+        ("convenor@example.com", &["convenors", "everyone"]),
+        ("carer@example.com", &["care", "everyone"]),
+        ("other@example.com", &["everyone"]),
+    ];
+
+    // Capacity scores are shown to everyone alongside the involvement, so are never redacted
+    fn assert_redacted(involvement: &Value, email: &str, keys: &[&str]) {
+        let visible: Vec<&str> = involvement["check_in_answers"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|answer| answer["question_key"].as_str().unwrap())
+            .collect();
+        assert_eq!(visible, keys, "answers seen by {}", email);
+        assert_eq!(
+            involvement["capacity_score"],
+            json!(1),
+            "capacity score seen by {}",
+            email
+        );
+    }
+
+    fn involvement_for(involvements: &Value, person_id: i64) -> &Value {
+        involvements
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|involvement| involvement["person_id"] == person_id)
+            .expect("No involvement for the person")
+    }
+
+    #[tokio::test]
+    async fn collective_state_only_has_what_each_viewer_can_see() {
+        let app = TestApp::new().await;
+        let seeded = seed(&app).await;
+
+        for (email, keys) in VIEWERS {
+            let session = app.log_in(email).await;
+            let state = app.get("/api/my_collective/state", &session).await;
+            let involvements =
+                &state["involvements"]["current_interval"]["collective_involvements"];
+            assert_redacted(involvement_for(involvements, seeded.owner_id), email, keys);
+        }
+    }
+
+    #[tokio::test]
+    async fn interval_involvements_only_have_what_each_viewer_can_see() {
+        let app = TestApp::new().await;
+        let seeded = seed(&app).await;
+
+        for (email, keys) in VIEWERS {
+            let session = app.log_in(email).await;
+            let data = app
+                .get(
+                    &format!(
+                        "/api/my_collective/interval/{}/involvements",
+                        seeded.interval_id
+                    ),
+                    &session,
+                )
+                .await;
+            assert_redacted(
+                involvement_for(&data["collective_involvements"], seeded.owner_id),
+                email,
+                keys,
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn history_only_has_what_each_viewer_can_see() {
+        let app = TestApp::new().await;
+        let seeded = seed(&app).await;
+
+        for (email, keys) in VIEWERS {
+            let session = app.log_in(email).await;
+            let history = app
+                .get(
+                    &format!("/api/people/{}/history", seeded.owner_id),
+                    &session,
+                )
+                .await;
+            assert_redacted(
+                &history["intervals"][0]["collective_involvement"],
+                email,
+                keys,
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn saving_broadcasts_only_what_everyone_can_see() {
+        let app = TestApp::new().await;
+        let seeded = seed(&app).await;
+        let mut events = app.realtime_state.subscribe().await;
+
+        let session = app.log_in("owner@example.com").await;
+        let (status, response) = app
+            .request(
+                "POST",
+                &format!("/api/me/interval/{}/my_participation", seeded.interval_id),
+                &session,
+                Some(participation_input()),
+            )
+            .await;
+        assert_eq!(status, 200);
+
+        // The person saving gets everything back
+        let own_data = &response[0]["MeEvent"]["IntervalDataChanged"];
+        assert_redacted(
+            &own_data["collective_involvement"],
+            "owner@example.com",
+            &["only_me", "convenors", "care", "everyone"],
+        );
+
+        let mut broadcast = None;
+        while let Ok(authored) = events.try_recv() {
+            if let AppEvent::MeEvent(_) = authored.event {
+                broadcast = Some(serde_json::to_value(authored.event).unwrap());
+            }
+        }
+        let broadcast = broadcast.expect("Interval data wasn't broadcast");
+        assert_redacted(
+            &broadcast["MeEvent"]["IntervalDataChanged"]["collective_involvement"],
+            "everyone",
+            &["everyone"],
+        );
+    }
+}
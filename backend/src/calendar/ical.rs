@@ -0,0 +1,130 @@
+use chrono::{DateTime, Days, NaiveDate, Utc};
+
+use crate::{
+    calendar::repo::ConvenorDuty, intervals::repo::parse_date_only, shared::entities::Interval,
+};
+
+// An all day event, running from the start date to the end date inclusive
+pub struct CalendarEvent {
+    pub uid: String,
+    pub summary: String,
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+}
+
+pub fn interval_events(intervals: &[Interval]) -> Vec<CalendarEvent> {
+    let mut events = vec![];
+
+    for interval in intervals {
+        let (Some(start_date), Some(end_date)) = (
+            parse_date_only(&interval.start_date),
+            parse_date_only(&interval.end_date),
+        ) else {
+            eprintln!("Skipping interval {} with invalid dates", interval.id);
+            continue;
+        };
+
+        events.push(CalendarEvent {
+            uid: format!("interval-{}", interval.id),
+            summary: format!("Interval {}", interval.sequence),
+            start_date,
+            end_date,
+        });
+
+        if let Some(deadline) = interval
+            .participation_deadline
+            .as_deref()
+            .and_then(parse_date_only)
+        {
+            events.push(CalendarEvent {
+                uid: format!("deadline-{}", interval.id),
+                summary: format!("Participation deadline for interval {}", interval.sequence),
+                start_date: deadline,
+                end_date: deadline,
+            });
+        }
+    }
+
+    events
+}
+
+pub fn convenor_events(duties: &[ConvenorDuty]) -> Vec<CalendarEvent> {
+    duties
+        .iter()
+        .filter_map(|duty| {
+            Some(CalendarEvent {
+                uid: format!("convenor-{}", duty.crew_involvement_id),
+                summary: format!(
+                    "Convening {} (interval {})",
+                    duty.crew_name, duty.interval_sequence
+                ),
+                start_date: parse_date_only(&duty.start_date)?,
+                end_date: parse_date_only(&duty.end_date)?,
+            })
+        })
+        .collect()
+}
+
+pub fn render_calendar(name: &str, events: &[CalendarEvent], now: DateTime<Utc>) -> String {
+    let timestamp = now.format("%Y%m%dT%H%M%SZ").to_string();
+
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        "PRODID:-//RADicalise//Calendar Feed//EN".to_string(),
+        "CALSCALE:GREGORIAN".to_string(),
+        format!("X-WR-CALNAME:{}", escape_text(name)),
+    ];
+
+    for event in events {
+        // All day events end on the day after they finish
+        let end_date = event
+            .end_date
+            .checked_add_days(Days::new(1))
+            .unwrap_or(event.end_date);
+
+        lines.push("BEGIN:VEVENT".to_string());
+        lines.push(format!("UID:{}@radicalise", event.uid));
+        lines.push(format!("DTSTAMP:{}", timestamp));
+        lines.push(format!(
+            "DTSTART;VALUE=DATE:{}",
+            event.start_date.format("%Y%m%d")
+        ));
+        lines.push(format!("DTEND;VALUE=DATE:{}", end_date.format("%Y%m%d")));
+        lines.push(format!("SUMMARY:{}", escape_text(&event.summary)));
+        lines.push("END:VEVENT".to_string());
+    }
+
+    lines.push("END:VCALENDAR".to_string());
+
+    lines
+        .iter()
+        .map(|line| fold_line(line))
+        .collect::<Vec<String>>()
+        .join("\r\n")
+        + "\r\n"
+}
+
+fn escape_text(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+// Lines longer than 75 bytes are split, with each continuation starting with a space
+fn fold_line(line: &str) -> String {
+    let mut folded = String::new();
+    let mut line_length = 0;
+
+    for character in line.chars() {
+        if line_length + character.len_utf8() > 75 {
+            folded.push_str("\r\n ");
+            line_length = 1;
+        }
+        folded.push(character);
+        line_length += character.len_utf8();
+    }
+
+    folded
+}
@@ -0,0 +1,210 @@
+use axum::{
+    Extension, Json,
+    extract::Path,
+    http::{StatusCode, header},
+    response::IntoResponse,
+};
+use sqlx::SqlitePool;
+use utoipa_axum::{router::OpenApiRouter, routes};
+
+use crate::{
+    auth::{
+        auth_backend::AuthSession,
+        permissions::{find_acting_person, require_admin},
+    },
+    calendar::{
+        ical::{CalendarEvent, convenor_events, interval_events, render_calendar},
+        repo::{CalendarFeed, find_convenor_duties},
+    },
+    intervals::repo::find_all_intervals,
+    my_collective::repo::find_collective,
+    shared::{
+        default_collective_id,
+        entities::{CollectiveId, PersonId},
+    },
+};
+
+pub mod ical;
+pub mod repo;
+
+pub fn router() -> OpenApiRouter {
+    OpenApiRouter::new()
+        .routes(routes!(list_calendar_feeds))
+        .routes(routes!(create_my_calendar_feed))
+        .routes(routes!(create_collective_calendar_feed))
+        .routes(routes!(revoke_calendar_feed))
+}
+
+#[utoipa::path(get, path = "/feeds",
+    responses(
+        (status = 200, description = "My calendar feeds, and the collective's if I'm an admin", body = Vec<CalendarFeed>),
+        (status = INTERNAL_SERVER_ERROR, description = "Internal server error", body = ()),
+    ),
+)]
+async fn list_calendar_feeds(
+    Extension(pool): Extension<SqlitePool>,
+    auth_session: AuthSession,
+) -> impl IntoResponse {
+    let acting_person =
+        match find_acting_person(&auth_session, default_collective_id(), &pool).await {
+            Ok(acting_person) => acting_person,
+            Err(status) => return (status, ()).into_response(),
+        };
+
+    let Ok(mut feeds) = repo::find_person_calendar_feeds(
+        default_collective_id(),
+        acting_person.person_id.clone(),
+        &pool,
+    )
+    .await
+    else {
+        return (StatusCode::INTERNAL_SERVER_ERROR, ()).into_response();
+    };
+
+    if acting_person.is_admin() {
+        match repo::find_collective_calendar_feeds(default_collective_id(), &pool).await {
+            Ok(collective_feeds) => feeds.extend(collective_feeds),
+            Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR, ()).into_response(),
+        }
+    }
+
+    (StatusCode::OK, Json(feeds)).into_response()
+}
+
+#[utoipa::path(post, path = "/feeds/me",
+    responses(
+        (status = 201, description = "Created a feed of intervals, deadlines and my convenor duties", body = CalendarFeed),
+        (status = INTERNAL_SERVER_ERROR, description = "Internal server error", body = ()),
+    ),
+)]
+async fn create_my_calendar_feed(
+    Extension(pool): Extension<SqlitePool>,
+    auth_session: AuthSession,
+) -> impl IntoResponse {
+    let acting_person =
+        match find_acting_person(&auth_session, default_collective_id(), &pool).await {
+            Ok(acting_person) => acting_person,
+            Err(status) => return (status, ()).into_response(),
+        };
+
+    match repo::create_calendar_feed(
+        default_collective_id(),
+        Some(acting_person.person_id),
+        &pool,
+    )
+    .await
+    {
+        Ok(feed) => (StatusCode::CREATED, Json(feed)).into_response(),
+        Err(_) => (StatusCode::INTERNAL_SERVER_ERROR, ()).into_response(),
+    }
+}
+
+#[utoipa::path(post, path = "/feeds/collective",
+    responses(
+        (status = 201, description = "Created a collective-wide feed of intervals and deadlines", body = CalendarFeed),
+        (status = FORBIDDEN, description = "Only admins can create collective-wide feeds", body = ()),
+        (status = INTERNAL_SERVER_ERROR, description = "Internal server error", body = ()),
+    ),
+)]
+async fn create_collective_calendar_feed(
+    Extension(pool): Extension<SqlitePool>,
+    auth_session: AuthSession,
+) -> impl IntoResponse {
+    if let Err(status) = require_admin(&auth_session, default_collective_id(), &pool).await {
+        return (status, ()).into_response();
+    }
+
+    match repo::create_calendar_feed(default_collective_id(), None, &pool).await {
+        Ok(feed) => (StatusCode::CREATED, Json(feed)).into_response(),
+        Err(_) => (StatusCode::INTERNAL_SERVER_ERROR, ()).into_response(),
+    }
+}
+
+#[utoipa::path(delete, path = "/feeds/{feed_id}",
+    params(
+        ("feed_id" = i64, Path, description = "Calendar feed ID")
+    ),
+    responses(
+        (status = 200, description = "Revoked the feed, so its link stops working", body = ()),
+        (status = FORBIDDEN, description = "Only the feed's owner, or an admin, can revoke it", body = ()),
+        (status = NOT_FOUND, description = "Feed not found", body = ()),
+        (status = INTERNAL_SERVER_ERROR, description = "Internal server error", body = ()),
+    ),
+)]
+async fn revoke_calendar_feed(
+    Path(feed_id): Path<i64>,
+    Extension(pool): Extension<SqlitePool>,
+    auth_session: AuthSession,
+) -> impl IntoResponse {
+    let acting_person =
+        match find_acting_person(&auth_session, default_collective_id(), &pool).await {
+            Ok(acting_person) => acting_person,
+            Err(status) => return (status, ()).into_response(),
+        };
+
+    let feed = match repo::find_calendar_feed(default_collective_id(), feed_id, &pool).await {
+        Ok(Some(feed)) => feed,
+        Ok(None) => return (StatusCode::NOT_FOUND, ()).into_response(),
+        Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR, ()).into_response(),
+    };
+
+    let owns_feed = feed.person_id == Some(acting_person.person_id.id);
+    if !owns_feed && !acting_person.is_admin() {
+        return (StatusCode::FORBIDDEN, ()).into_response();
+    }
+
+    match repo::revoke_calendar_feed(feed.id, &pool).await {
+        Ok(_) => (StatusCode::OK, ()).into_response(),
+        Err(_) => (StatusCode::INTERNAL_SERVER_ERROR, ()).into_response(),
+    }
+}
+
+// Calendar apps can't log in, so the token in the URL is the only thing giving access.
+// It only ever unlocks this feed.
+#[utoipa::path(get, path = "/calendar/{token}",
+    params(
+        ("token" = String, Path, description = "Secret token for the calendar feed")
+    ),
+    responses(
+        (status = 200, description = "iCalendar feed", body = String, content_type = "text/calendar"),
+        (status = NOT_FOUND, description = "Feed not found or revoked", body = ()),
+        (status = INTERNAL_SERVER_ERROR, description = "Internal server error", body = ()),
+    ),
+)]
+pub async fn get_calendar_feed(
+    Path(token): Path<String>,
+    Extension(pool): Extension<SqlitePool>,
+) -> impl IntoResponse {
+    let feed = match repo::find_calendar_feed_by_token(&token, &pool).await {
+        Ok(Some(feed)) => feed,
+        Ok(None) => return (StatusCode::NOT_FOUND, ()).into_response(),
+        Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR, ()).into_response(),
+    };
+
+    let collective_id = CollectiveId::new(feed.collective_id);
+    let Ok(collective) = find_collective(collective_id.clone(), &pool).await else {
+        return (StatusCode::INTERNAL_SERVER_ERROR, ()).into_response();
+    };
+    let Ok(intervals) = find_all_intervals(collective_id, &pool).await else {
+        return (StatusCode::INTERNAL_SERVER_ERROR, ()).into_response();
+    };
+
+    let mut events: Vec<CalendarEvent> = interval_events(&intervals);
+
+    if let Some(person_id) = feed.person_id {
+        match find_convenor_duties(PersonId::new(person_id), &pool).await {
+            Ok(duties) => events.extend(convenor_events(&duties)),
+            Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR, ()).into_response(),
+        }
+    }
+
+    let name = collective.name.unwrap_or_else(|| "RADicalise".to_string());
+    let calendar = render_calendar(&name, &events, chrono::Utc::now());
+
+    (
+        StatusCode::OK,
+        [(header::CONTENT_TYPE, "text/calendar; charset=utf-8")],
+        calendar,
+    )
+        .into_response()
+}
@@ -0,0 +1,143 @@
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::shared::entities::{CollectiveId, PersonId};
+
+// A secret link to a calendar feed. Feeds without a person are collective-wide.
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone)]
+pub struct CalendarFeed {
+    pub id: i64,
+    pub collective_id: i64,
+    pub person_id: Option<i64>,
+    pub token: String,
+    pub created_at: String,
+}
+
+pub struct ConvenorDuty {
+    pub crew_involvement_id: i64,
+    pub crew_name: String,
+    pub interval_sequence: i64,
+    pub start_date: String,
+    pub end_date: String,
+}
+
+pub async fn create_calendar_feed(
+    collective_id: CollectiveId,
+    person_id: Option<PersonId>,
+    pool: &SqlitePool,
+) -> Result<CalendarFeed, sqlx::Error> {
+    let token = Uuid::new_v4().to_string();
+    let person_id = person_id.map(|person_id| person_id.id);
+
+    sqlx::query_as!(
+        CalendarFeed,
+        "INSERT INTO calendar_feeds (collective_id, person_id, token)
+        VALUES (?, ?, ?)
+        RETURNING id, collective_id, person_id, token, created_at",
+        collective_id.id,
+        person_id,
+        token
+    )
+    .fetch_one(pool)
+    .await
+}
+
+pub async fn find_calendar_feed(
+    collective_id: CollectiveId,
+    feed_id: i64,
+    pool: &SqlitePool,
+) -> Result<Option<CalendarFeed>, sqlx::Error> {
+    sqlx::query_as!(
+        CalendarFeed,
+        "SELECT id, collective_id, person_id, token, created_at
+        FROM calendar_feeds
+        WHERE id = ? AND collective_id = ? AND revoked_at IS NULL",
+        feed_id,
+        collective_id.id
+    )
+    .fetch_optional(pool)
+    .await
+}
+
+pub async fn find_calendar_feed_by_token(
+    token: &str,
+    pool: &SqlitePool,
+) -> Result<Option<CalendarFeed>, sqlx::Error> {
+    sqlx::query_as!(
+        CalendarFeed,
+        "SELECT id, collective_id, person_id, token, created_at
+        FROM calendar_feeds
+        WHERE token = ? AND revoked_at IS NULL",
+        token
+    )
+    .fetch_optional(pool)
+    .await
+}
+
+pub async fn find_person_calendar_feeds(
+    collective_id: CollectiveId,
+    person_id: PersonId,
+    pool: &SqlitePool,
+) -> Result<Vec<CalendarFeed>, sqlx::Error> {
+    sqlx::query_as!(
+        CalendarFeed,
+        "SELECT id, collective_id, person_id, token, created_at
+        FROM calendar_feeds
+        WHERE collective_id = ? AND person_id = ? AND revoked_at IS NULL",
+        collective_id.id,
+        person_id.id
+    )
+    .fetch_all(pool)
+    .await
+}
+
+pub async fn find_collective_calendar_feeds(
+    collective_id: CollectiveId,
+    pool: &SqlitePool,
+) -> Result<Vec<CalendarFeed>, sqlx::Error> {
+    sqlx::query_as!(
+        CalendarFeed,
+        "SELECT id, collective_id, person_id, token, created_at
+        FROM calendar_feeds
+        WHERE collective_id = ? AND person_id IS NULL AND revoked_at IS NULL",
+        collective_id.id
+    )
+    .fetch_all(pool)
+    .await
+}
+
+pub async fn revoke_calendar_feed(feed_id: i64, pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        "UPDATE calendar_feeds SET revoked_at = datetime('now') WHERE id = ?",
+        feed_id
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn find_convenor_duties(
+    person_id: PersonId,
+    pool: &SqlitePool,
+) -> Result<Vec<ConvenorDuty>, sqlx::Error> {
+    sqlx::query_as!(
+        ConvenorDuty,
+        "SELECT
+            crew_involvements.id as crew_involvement_id,
+            crews.name as crew_name,
+            intervals.sequence as interval_sequence,
+            intervals.start_date,
+            intervals.end_date
+        FROM crew_involvements
+        INNER JOIN crews ON crews.id = crew_involvements.crew_id
+        INNER JOIN intervals ON intervals.id = crew_involvements.interval_id
+        WHERE crew_involvements.person_id = ? AND crew_involvements.convenor = TRUE
+        ORDER BY intervals.start_date ASC",
+        person_id.id
+    )
+    .fetch_all(pool)
+    .await
+}
@@ -0,0 +1,10 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::shared::entities::CheckInQuestionSet;
+
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone)]
+pub enum CheckInsEvent {
+    // Applies to the interval it was defined for, and any later ones that inherit it
+    QuestionSetChanged(CheckInQuestionSet),
+}
@@ -0,0 +1,200 @@
+use std::collections::HashSet;
+
+use axum::{Extension, Json, extract::Path, http::StatusCode, response::IntoResponse};
+use sqlx::SqlitePool;
+use utoipa_axum::{router::OpenApiRouter, routes};
+
+use crate::{
+    auth::{auth_backend::AuthSession, permissions::require_admin},
+    check_ins::events::CheckInsEvent,
+    intervals::repo::find_interval,
+    realtime::RealtimeState,
+    shared::{
+        default_collective_id,
+        entities::{
+            CheckInAnswer, CheckInQuestion, CheckInQuestionKind, CheckInQuestionSet, IntervalId,
+        },
+        events::AppEvent,
+    },
+};
+
+pub mod events;
+pub mod repo;
+
+const SCALE_MIN: i64 = 1;
+const SCALE_MAX: i64 = 5;
+
+pub fn router() -> OpenApiRouter {
+    OpenApiRouter::new().routes(routes!(get_question_set, update_question_set))
+}
+
+#[utoipa::path(get, path = "/interval/{interval_id}",
+    params(
+        ("interval_id" = i64, Path, description = "Interval ID")
+    ),
+    responses(
+        (status = 200, body = CheckInQuestionSet),
+        (status = UNAUTHORIZED, description = "Not logged in", body = ()),
+        (status = INTERNAL_SERVER_ERROR, description = "Internal server error", body = ()),
+    ),
+)]
+pub async fn get_question_set(
+    Path(interval_id): Path<i64>,
+    Extension(pool): Extension<SqlitePool>,
+    auth_session: AuthSession,
+) -> impl IntoResponse {
+    if auth_session.user.is_none() {
+        return (StatusCode::UNAUTHORIZED, ()).into_response();
+    }
+
+    match repo::find_question_set(default_collective_id(), IntervalId::new(interval_id), &pool)
+        .await
+    {
+        Ok(question_set) => (StatusCode::OK, Json(question_set)).into_response(),
+        Err(_) => (StatusCode::INTERNAL_SERVER_ERROR, ()).into_response(),
+    }
+}
+
+// Saves the questions as the version asked from this interval onwards
+#[utoipa::path(put, path = "/interval/{interval_id}",
+    params(
+        ("interval_id" = i64, Path, description = "Interval ID")
+    ),
+    request_body(content = Vec<CheckInQuestion>, content_type = "application/json"),
+    responses(
+        (status = 200, body = Vec<AppEvent>),
+        (status = BAD_REQUEST, description = "The questions aren't valid", body = ()),
+        (status = FORBIDDEN, description = "Only admins can change the check-in questions", body = ()),
+        (status = NOT_FOUND, description = "Interval not found", body = ()),
+        (status = INTERNAL_SERVER_ERROR, description = "Internal server error", body = ()),
+    ),
+)]
+pub async fn update_question_set(
+    Path(interval_id): Path<i64>,
+    Extension(pool): Extension<SqlitePool>,
+    Extension(realtime_state): Extension<RealtimeState>,
+    auth_session: AuthSession,
+    Json(input): Json<Vec<CheckInQuestion>>,
+) -> impl IntoResponse {
+    if let Err(status) = require_admin(&auth_session, default_collective_id(), &pool).await {
+        return (status, ()).into_response();
+    }
+
+    let interval_id = IntervalId::new(interval_id);
+    match find_interval(interval_id.clone(), &pool).await {
+        Ok(_) => {}
+        Err(sqlx::Error::RowNotFound) => return (StatusCode::NOT_FOUND, ()).into_response(),
+        Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR, ()).into_response(),
+    }
+
+    if let Err(message) = validate_questions(&input) {
+        return (StatusCode::BAD_REQUEST, message).into_response();
+    }
+
+    println!(
+        "Updating check-in questions from interval {}: {:?}",
+        interval_id.id, input
+    );
+
+    match repo::replace_question_set(default_collective_id(), interval_id, input, &pool).await {
+        Ok(question_set) => {
+            let event = AppEvent::CheckInsEvent(CheckInsEvent::QuestionSetChanged(question_set));
+            realtime_state
+                .broadcast_app_event(Some(auth_session), event.clone())
+                .await;
+            (StatusCode::OK, Json(vec![event])).into_response()
+        }
+        Err(_) => (StatusCode::INTERNAL_SERVER_ERROR, ()).into_response(),
+    }
+}
+
+fn validate_questions(questions: &[CheckInQuestion]) -> Result<(), &'static str> {
+    let mut keys = HashSet::new();
+    for question in questions {
+        if question.key.trim().is_empty() || question.prompt.trim().is_empty() {
+            return Err("Every question needs a key and a prompt");
+        }
+        if !keys.insert(question.key.as_str()) {
+            return Err("Question keys must be unique");
+        }
+
+        let has_options = !question.options.is_empty();
+        if has_options != (question.kind == CheckInQuestionKind::MultipleChoice) {
+            return Err("Only multiple choice questions have options, and they need some");
+        }
+    }
+
+    Ok(())
+}
+
+fn check_answer(question: &CheckInQuestion, answer: &str) -> Result<(), &'static str> {
+    match question.kind {
+        CheckInQuestionKind::FreeText => Ok(()),
+        CheckInQuestionKind::Scale => match answer.trim().parse::<i64>() {
+            Ok(value) if (SCALE_MIN..=SCALE_MAX).contains(&value) => Ok(()),
+            _ => Err("Scale answers must be a whole number from 1 to 5"),
+        },
+        CheckInQuestionKind::MultipleChoice => {
+            if question.options.iter().any(|option| option == answer) {
+                Ok(())
+            } else {
+                Err("Multiple choice answers must be one of the options")
+            }
+        }
+    }
+}
+
+// Checks someone's answers against the interval's questions. Required questions only need
+// answering by those who are participating.
+pub fn validate_answers(
+    question_set: &CheckInQuestionSet,
+    answers: &[CheckInAnswer],
+    participating: bool,
+) -> Result<(), &'static str> {
+    let mut answered = HashSet::new();
+    for answer in answers.iter().filter(|answer| is_answered(answer)) {
+        let Some(question) = question_set
+            .questions
+            .iter()
+            .find(|question| question.key == answer.question_key)
+        else {
+            return Err("Answer for an unknown question");
+        };
+        if !answered.insert(answer.question_key.as_str()) {
+            return Err("Each question can only be answered once");
+        }
+        check_answer(question, &answer.answer)?;
+    }
+
+    let unanswered_required = question_set
+        .questions
+        .iter()
+        .any(|question| question.required && !answered.contains(question.key.as_str()));
+    if participating && unanswered_required {
+        return Err("Required questions need answering");
+    }
+
+    Ok(())
+}
+
+// Blank answers are dropped rather than saved
+pub fn is_answered(answer: &CheckInAnswer) -> bool {
+    !answer.answer.trim().is_empty()
+}
+
+// The answers that still fit the question set, for carrying answers over from an interval
+// with a different version of it
+pub fn answers_for_question_set(
+    question_set: &CheckInQuestionSet,
+    answers: Vec<CheckInAnswer>,
+) -> Vec<CheckInAnswer> {
+    answers
+        .into_iter()
+        .filter(|answer| {
+            question_set.questions.iter().any(|question| {
+                question.key == answer.question_key
+                    && check_answer(question, &answer.answer).is_ok()
+            })
+        })
+        .collect()
+}
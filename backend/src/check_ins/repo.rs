@@ -0,0 +1,192 @@
+use std::collections::HashMap;
+
+use sqlx::SqlitePool;
+
+use crate::shared::entities::{
+    CheckInAnswer, CheckInQuestion, CheckInQuestionKind, CheckInQuestionSet, CollectiveId,
+    IntervalId, VisibilityLevel,
+};
+
+// The version of the question set that applies to the interval, which is the one defined for
+// the latest interval up to and including it
+pub async fn find_question_set(
+    collective_id: CollectiveId,
+    interval_id: IntervalId,
+    pool: &SqlitePool,
+) -> Result<CheckInQuestionSet, sqlx::Error> {
+    let defined_for_interval_id = sqlx::query_scalar!(
+        "SELECT check_in_questions.interval_id
+        FROM check_in_questions
+        INNER JOIN intervals ON intervals.id = check_in_questions.interval_id
+        WHERE check_in_questions.collective_id = ?
+            AND intervals.start_date <= (SELECT start_date FROM intervals WHERE id = ?)
+        ORDER BY intervals.start_date DESC
+        LIMIT 1",
+        collective_id.id,
+        interval_id.id
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    let Some(defined_for_interval_id) = defined_for_interval_id else {
+        return Ok(CheckInQuestionSet {
+            defined_for_interval_id: None,
+            questions: vec![],
+        });
+    };
+
+    let rows = sqlx::query!(
+        "SELECT key, prompt, kind as \"kind: CheckInQuestionKind\", options, required
+        FROM check_in_questions
+        WHERE collective_id = ? AND interval_id = ?
+        ORDER BY position",
+        collective_id.id,
+        defined_for_interval_id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let mut questions = vec![];
+    for row in rows {
+        questions.push(CheckInQuestion {
+            key: row.key,
+            prompt: row.prompt,
+            kind: row.kind,
+            options: serde_json::from_str(&row.options)
+                .map_err(|error| sqlx::Error::Decode(Box::new(error)))?,
+            required: row.required,
+        });
+    }
+
+    Ok(CheckInQuestionSet {
+        defined_for_interval_id: Some(defined_for_interval_id),
+        questions,
+    })
+}
+
+// Defines a new version of the question set from the interval onwards, or replaces the
+// interval's version if it already had one
+pub async fn replace_question_set(
+    collective_id: CollectiveId,
+    interval_id: IntervalId,
+    questions: Vec<CheckInQuestion>,
+    pool: &SqlitePool,
+) -> Result<CheckInQuestionSet, sqlx::Error> {
+    let mut transaction = pool.begin().await?;
+
+    sqlx::query!(
+        "DELETE FROM check_in_questions WHERE collective_id = ? AND interval_id = ?",
+        collective_id.id,
+        interval_id.id
+    )
+    .execute(&mut *transaction)
+    .await?;
+
+    for (position, question) in questions.iter().enumerate() {
+        let position = position as i64;
+        let options = serde_json::to_string(&question.options)
+            .map_err(|error| sqlx::Error::Encode(Box::new(error)))?;
+        sqlx::query!(
+            "INSERT INTO check_in_questions
+                (collective_id, interval_id, key, prompt, kind, options, required, position)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+            collective_id.id,
+            interval_id.id,
+            question.key,
+            question.prompt,
+            question.kind,
+            options,
+            question.required,
+            position
+        )
+        .execute(&mut *transaction)
+        .await?;
+    }
+
+    transaction.commit().await?;
+
+    find_question_set(collective_id, interval_id, pool).await
+}
+
+pub async fn find_answers(
+    collective_involvement_id: i64,
+    pool: &SqlitePool,
+) -> Result<Vec<CheckInAnswer>, sqlx::Error> {
+    sqlx::query_as!(
+        CheckInAnswer,
+        "SELECT question_key, answer, visibility as \"visibility: VisibilityLevel\"
+        FROM check_in_answers
+        WHERE collective_involvement_id = ?
+        ORDER BY id",
+        collective_involvement_id
+    )
+    .fetch_all(pool)
+    .await
+}
+
+// Everyone's answers for the interval, by collective involvement
+pub async fn find_answers_for_interval(
+    collective_id: CollectiveId,
+    interval_id: IntervalId,
+    pool: &SqlitePool,
+) -> Result<HashMap<i64, Vec<CheckInAnswer>>, sqlx::Error> {
+    let rows = sqlx::query!(
+        "SELECT check_in_answers.collective_involvement_id, check_in_answers.question_key,
+            check_in_answers.answer,
+            check_in_answers.visibility as \"visibility: VisibilityLevel\"
+        FROM check_in_answers
+        INNER JOIN collective_involvements
+            ON collective_involvements.id = check_in_answers.collective_involvement_id
+        WHERE collective_involvements.collective_id = ?
+            AND collective_involvements.interval_id = ?
+        ORDER BY check_in_answers.id",
+        collective_id.id,
+        interval_id.id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let mut answers: HashMap<i64, Vec<CheckInAnswer>> = HashMap::new();
+    for row in rows {
+        answers
+            .entry(row.collective_involvement_id)
+            .or_default()
+            .push(CheckInAnswer {
+                question_key: row.question_key,
+                answer: row.answer,
+                visibility: row.visibility,
+            });
+    }
+
+    Ok(answers)
+}
+
+pub async fn replace_answers(
+    collective_involvement_id: i64,
+    answers: Vec<CheckInAnswer>,
+    pool: &SqlitePool,
+) -> Result<(), sqlx::Error> {
+    let mut transaction = pool.begin().await?;
+
+    sqlx::query!(
+        "DELETE FROM check_in_answers WHERE collective_involvement_id = ?",
+        collective_involvement_id
+    )
+    .execute(&mut *transaction)
+    .await?;
+
+    for answer in answers {
+        sqlx::query!(
+            "INSERT INTO check_in_answers (collective_involvement_id, question_key, answer, visibility)
+            VALUES (?, ?, ?, ?)",
+            collective_involvement_id,
+            answer.question_key,
+            answer.answer,
+            answer.visibility
+        )
+        .execute(&mut *transaction)
+        .await?;
+    }
+
+    transaction.commit().await
+}
@@ -0,0 +1,784 @@
+use std::cmp::Reverse;
+
+use chrono::NaiveDate;
+use sqlx::SqlitePool;
+
+use crate::{
+    crews::{
+        events::ConvenorChange,
+        repo::{
+            add_crew_convenor, find_crew, find_crew_involvements, find_last_convened_sequences,
+            has_manually_assigned_convenor, intervals_participated_since_last_convened,
+            set_crew_convenors, step_down_as_convenor,
+        },
+        roles::update_role_assignees_if_needed,
+    },
+    intervals::{
+        clock::{Clock, collective_today},
+        repo::{IntervalType, find_interval, get_interval_type, planning_date},
+    },
+    my_collective::{
+        involvements_repo::find_all_collective_involvements,
+        repo::{find_all_crew_involvements, find_collective},
+    },
+    shared::entities::{
+        CollectiveId, ConvenorSelectionTiming, ConvenorStrategy, CrewId, CrewInvolvement,
+        CrewMembership, Interval, IntervalId, IntervalPhase, InvolvementStatus, PersonId,
+    },
+};
+
+// Everything a strategy can know about someone who volunteered to convene
+#[derive(Debug, Clone)]
+pub struct ConvenorCandidate {
+    pub person_id: i64,
+    pub intervals_since_convened: i64,
+    pub last_convened_sequence: Option<i64>,
+    pub capacity_score: Option<i64>,
+}
+
+pub trait ConvenorSelector: Send + Sync {
+    // Picks a convenor from the candidates, or None to leave the crew without one
+    fn select(&self, candidates: &[ConvenorCandidate]) -> Option<i64>;
+
+    // Whether a convenor that has already been chosen should be left alone
+    fn keeps_existing_convenor(&self) -> bool {
+        false
+    }
+}
+
+// The longest participating since they last convened this crew, then the lowest person id
+pub struct LongestSinceConvened;
+
+impl ConvenorSelector for LongestSinceConvened {
+    fn select(&self, candidates: &[ConvenorCandidate]) -> Option<i64> {
+        candidates
+            .iter()
+            .max_by_key(|candidate| {
+                (
+                    candidate.intervals_since_convened,
+                    Reverse(candidate.person_id),
+                )
+            })
+            .map(|candidate| candidate.person_id)
+    }
+}
+
+// Whoever convened this crew least recently, with people who never have going first
+pub struct RoundRobin;
+
+impl ConvenorSelector for RoundRobin {
+    fn select(&self, candidates: &[ConvenorCandidate]) -> Option<i64> {
+        candidates
+            .iter()
+            .min_by_key(|candidate| (candidate.last_convened_sequence, candidate.person_id))
+            .map(|candidate| candidate.person_id)
+    }
+}
+
+// The highest capacity score, falling back to the longest since convened
+pub struct CapacityWeighted;
+
+impl ConvenorSelector for CapacityWeighted {
+    fn select(&self, candidates: &[ConvenorCandidate]) -> Option<i64> {
+        candidates
+            .iter()
+            .max_by_key(|candidate| {
+                (
+                    candidate.capacity_score.unwrap_or(0),
+                    candidate.intervals_since_convened,
+                    Reverse(candidate.person_id),
+                )
+            })
+            .map(|candidate| candidate.person_id)
+    }
+}
+
+// Convenors are only ever assigned by hand
+pub struct ManualOnly;
+
+impl ConvenorSelector for ManualOnly {
+    fn select(&self, _candidates: &[ConvenorCandidate]) -> Option<i64> {
+        None
+    }
+
+    fn keeps_existing_convenor(&self) -> bool {
+        true
+    }
+}
+
+pub fn selector_for(strategy: ConvenorStrategy) -> Box<dyn ConvenorSelector> {
+    match strategy {
+        ConvenorStrategy::LongestSinceConvened => Box::new(LongestSinceConvened),
+        ConvenorStrategy::RoundRobin => Box::new(RoundRobin),
+        ConvenorStrategy::CapacityWeighted => Box::new(CapacityWeighted),
+        ConvenorStrategy::ManualOnly => Box::new(ManualOnly),
+    }
+}
+
+// Once an interval is locked, crews without a convenor always get one chosen, whatever the timing
+pub fn selects_on_update(timing: ConvenorSelectionTiming, phase: IntervalPhase) -> bool {
+    timing == ConvenorSelectionTiming::OnEveryUpdate || phase >= IntervalPhase::Locked
+}
+
+// Returns the change, if the crew's convenors changed
+pub async fn update_convenor_if_needed(
+    collective_id: CollectiveId,
+    crew_id: CrewId,
+    interval_id: IntervalId,
+    phase: IntervalPhase,
+    changed_by: i64,
+    clock: &impl Clock,
+    pool: &SqlitePool,
+) -> Result<Option<ConvenorChange>, sqlx::Error> {
+    let crew = find_crew(collective_id.clone(), crew_id.clone(), pool).await?;
+    let selector = selector_for(crew.convenor_strategy);
+
+    let crew_involvements =
+        find_crew_involvements(crew_id.clone(), interval_id.clone(), pool).await?;
+    let current_convenors = convenor_ids(&crew_involvements);
+
+    // Once planning is locked, keep any convenor that has already been chosen. The same goes
+    // for convenors who were assigned by hand
+    let planning_locked = matches!(phase, IntervalPhase::Locked | IntervalPhase::Active);
+    let keep_existing = planning_locked
+        || selector.keeps_existing_convenor()
+        || has_manually_assigned_convenor(crew_id.clone(), interval_id.clone(), pool).await?;
+    if keep_existing && !current_convenors.is_empty() {
+        println!(
+            "Crew {} keeping its convenor for interval {} in phase {:?}",
+            crew_id.id, interval_id.id, phase
+        );
+        return Ok(None);
+    }
+
+    // Pending members can't be chosen until they've been approved
+    let volunteered: Vec<&CrewInvolvement> = crew_involvements
+        .iter()
+        .filter(|involvement| involvement.volunteered_convenor)
+        .filter(|involvement| involvement.membership == CrewMembership::Member)
+        .collect();
+    let candidates = find_convenor_candidates(
+        collective_id,
+        crew_id.clone(),
+        interval_id.clone(),
+        volunteered,
+        clock,
+        pool,
+    )
+    .await?;
+    // Co-convenors who have already been chosen keep both seats filled
+    let max_convenors = if crew.allow_co_convenors { 2 } else { 1 };
+    let seats = current_convenors.len().clamp(1, max_convenors);
+    let mut best_convenor = select_convenors(selector.as_ref(), candidates, seats);
+
+    let mut sorted_current_convenors = current_convenors.clone();
+    sorted_current_convenors.sort_unstable();
+    best_convenor.sort_unstable();
+    if sorted_current_convenors == best_convenor {
+        return Ok(None);
+    }
+
+    set_crew_convenors(
+        crew_id.clone(),
+        interval_id.clone(),
+        best_convenor.clone(),
+        None,
+        pool,
+    )
+    .await?;
+
+    Ok(Some(ConvenorChange {
+        crew_id: crew_id.id,
+        interval_id: interval_id.id,
+        previous_convenor_ids: current_convenors,
+        convenor_ids: best_convenor,
+        changed_by,
+    }))
+}
+
+// Fills up to the number of seats, choosing each in turn from those not yet chosen
+fn select_convenors(
+    selector: &dyn ConvenorSelector,
+    mut candidates: Vec<ConvenorCandidate>,
+    seats: usize,
+) -> Vec<i64> {
+    let mut chosen = vec![];
+    while chosen.len() < seats
+        && let Some(person_id) = selector.select(&candidates)
+    {
+        chosen.push(person_id);
+        candidates.retain(|candidate| candidate.person_id != person_id);
+    }
+    chosen
+}
+
+// Chooses convenors, and rotates roles, for every crew with involvements in the interval
+pub async fn select_convenors_for_interval(
+    collective_id: CollectiveId,
+    interval_id: IntervalId,
+    phase: IntervalPhase,
+    changed_by: i64,
+    clock: &impl Clock,
+    pool: &SqlitePool,
+) -> Result<Vec<ConvenorChange>, sqlx::Error> {
+    let mut crew_ids: Vec<i64> = find_all_crew_involvements(interval_id.clone(), pool)
+        .await?
+        .into_iter()
+        .map(|involvement| involvement.crew_id)
+        .collect();
+    crew_ids.sort_unstable();
+    crew_ids.dedup();
+
+    let mut changes = vec![];
+    for crew_id in crew_ids {
+        let change = update_convenor_if_needed(
+            collective_id.clone(),
+            CrewId::new(crew_id),
+            interval_id.clone(),
+            phase,
+            changed_by,
+            clock,
+            pool,
+        )
+        .await?;
+        changes.extend(change);
+
+        update_role_assignees_if_needed(
+            collective_id.clone(),
+            CrewId::new(crew_id),
+            interval_id.clone(),
+            phase,
+            pool,
+        )
+        .await?;
+    }
+
+    Ok(changes)
+}
+
+// Assigns convenors by hand, overriding any automatic selection. Everyone assigned must be
+// in the crew for the interval
+pub async fn assign_convenors(
+    collective_id: CollectiveId,
+    crew_id: CrewId,
+    interval_id: IntervalId,
+    person_ids: Vec<i64>,
+    assigned_by: PersonId,
+    pool: &SqlitePool,
+) -> Result<ConvenorChange, sqlx::Error> {
+    let crew = find_crew(collective_id, crew_id.clone(), pool).await?;
+    let max_convenors = if crew.allow_co_convenors { 2 } else { 1 };
+    if person_ids.len() > max_convenors {
+        return Err(sqlx::Error::InvalidArgument(format!(
+            "Crew {} can have at most {} convenors",
+            crew_id.id, max_convenors
+        )));
+    }
+
+    let crew_involvements =
+        find_crew_involvements(crew_id.clone(), interval_id.clone(), pool).await?;
+    for person_id in &person_ids {
+        if !crew_involvements.iter().any(|involvement| {
+            involvement.person_id == *person_id && involvement.membership == CrewMembership::Member
+        }) {
+            return Err(sqlx::Error::InvalidArgument(format!(
+                "Person {} isn't in crew {} for interval {}",
+                person_id, crew_id.id, interval_id.id
+            )));
+        }
+    }
+
+    set_crew_convenors(
+        crew_id.clone(),
+        interval_id.clone(),
+        person_ids.clone(),
+        Some(assigned_by.id),
+        pool,
+    )
+    .await?;
+
+    Ok(ConvenorChange {
+        crew_id: crew_id.id,
+        interval_id: interval_id.id,
+        previous_convenor_ids: convenor_ids(&crew_involvements),
+        convenor_ids: person_ids,
+        changed_by: assigned_by.id,
+    })
+}
+
+// Steps a convenor down, then fills their seat from the members who volunteered. A co-convenor
+// who stays on keeps their seat. Collectives that choose convenors on lock wait until then
+pub async fn step_down_convenor(
+    collective_id: CollectiveId,
+    crew_id: CrewId,
+    interval: &Interval,
+    person_id: PersonId,
+    clock: &impl Clock,
+    pool: &SqlitePool,
+) -> Result<ConvenorChange, sqlx::Error> {
+    let interval_id = interval.typed_id();
+    let previous_convenor_ids =
+        convenor_ids(&find_crew_involvements(crew_id.clone(), interval_id.clone(), pool).await?);
+
+    step_down_as_convenor(
+        crew_id.clone(),
+        interval_id.clone(),
+        person_id.clone(),
+        pool,
+    )
+    .await?;
+
+    let collective = find_collective(collective_id.clone(), pool).await?;
+    let remaining_convenor_ids = previous_convenor_ids
+        .iter()
+        .copied()
+        .filter(|convenor_id| *convenor_id != person_id.id)
+        .collect::<Vec<i64>>();
+    if selects_on_update(collective.convenor_selection_timing, interval.phase) {
+        if remaining_convenor_ids.is_empty() {
+            update_convenor_if_needed(
+                collective_id,
+                crew_id.clone(),
+                interval_id.clone(),
+                interval.phase,
+                person_id.id,
+                clock,
+                pool,
+            )
+            .await?;
+        } else {
+            fill_vacated_convenor_seat(
+                collective_id,
+                crew_id.clone(),
+                interval_id.clone(),
+                &remaining_convenor_ids,
+                clock,
+                pool,
+            )
+            .await?;
+        }
+    }
+
+    let convenor_ids =
+        convenor_ids(&find_crew_involvements(crew_id.clone(), interval_id.clone(), pool).await?);
+
+    Ok(ConvenorChange {
+        crew_id: crew_id.id,
+        interval_id: interval_id.id,
+        previous_convenor_ids,
+        convenor_ids,
+        changed_by: person_id.id,
+    })
+}
+
+// Chooses someone to convene alongside the convenors who are staying on, if the crew can have
+// co-convenors
+async fn fill_vacated_convenor_seat(
+    collective_id: CollectiveId,
+    crew_id: CrewId,
+    interval_id: IntervalId,
+    remaining_convenor_ids: &[i64],
+    clock: &impl Clock,
+    pool: &SqlitePool,
+) -> Result<(), sqlx::Error> {
+    let crew = find_crew(collective_id.clone(), crew_id.clone(), pool).await?;
+    if !crew.allow_co_convenors {
+        return Ok(());
+    }
+
+    let crew_involvements =
+        find_crew_involvements(crew_id.clone(), interval_id.clone(), pool).await?;
+    let volunteered: Vec<&CrewInvolvement> = crew_involvements
+        .iter()
+        .filter(|involvement| involvement.volunteered_convenor)
+        .filter(|involvement| involvement.membership == CrewMembership::Member)
+        .filter(|involvement| !remaining_convenor_ids.contains(&involvement.person_id))
+        .collect();
+    let candidates = find_convenor_candidates(
+        collective_id,
+        crew_id.clone(),
+        interval_id.clone(),
+        volunteered,
+        clock,
+        pool,
+    )
+    .await?;
+
+    if let Some(person_id) = selector_for(crew.convenor_strategy).select(&candidates) {
+        add_crew_convenor(crew_id, interval_id, person_id, pool).await?;
+    }
+
+    Ok(())
+}
+
+// Steps down anyone convening the current interval who has since stopped participating, for
+// example by starting a hiatus part way through it
+pub async fn step_down_unavailable_convenors(
+    collective_id: CollectiveId,
+    interval: &Interval,
+    today: NaiveDate,
+    clock: &impl Clock,
+    pool: &SqlitePool,
+) -> Result<Vec<ConvenorChange>, sqlx::Error> {
+    if get_interval_type(interval.clone(), today) != IntervalType::Current {
+        return Ok(vec![]);
+    }
+
+    let today = today.format("%Y-%m-%d").to_string();
+    let collective_involvements =
+        find_all_collective_involvements(collective_id.clone(), interval.typed_id(), pool).await?;
+    let unavailable_convenors: Vec<CrewInvolvement> =
+        find_all_crew_involvements(interval.typed_id(), pool)
+            .await?
+            .into_iter()
+            .filter(|involvement| involvement.convenor)
+            .filter(|involvement| {
+                collective_involvements
+                    .iter()
+                    .any(|collective_involvement| {
+                        collective_involvement.person_id == involvement.person_id
+                            && collective_involvement.status_on(&today)
+                                != InvolvementStatus::Participating
+                    })
+            })
+            .collect();
+
+    let mut changes = vec![];
+    for involvement in unavailable_convenors {
+        println!(
+            "Stepping down person {} as convenor of crew {} for interval {}, as they're no longer participating",
+            involvement.person_id, involvement.crew_id, interval.id
+        );
+        let change = step_down_convenor(
+            collective_id.clone(),
+            CrewId::new(involvement.crew_id),
+            interval,
+            PersonId::new(involvement.person_id),
+            clock,
+            pool,
+        )
+        .await?;
+        changes.push(change);
+    }
+
+    Ok(changes)
+}
+
+pub fn convenor_ids(crew_involvements: &[CrewInvolvement]) -> Vec<i64> {
+    crew_involvements
+        .iter()
+        .filter(|involvement| involvement.convenor)
+        .map(|involvement| involvement.person_id)
+        .collect()
+}
+
+async fn find_convenor_candidates(
+    collective_id: CollectiveId,
+    crew_id: CrewId,
+    interval_id: IntervalId,
+    volunteered: Vec<&CrewInvolvement>,
+    clock: &impl Clock,
+    pool: &SqlitePool,
+) -> Result<Vec<ConvenorCandidate>, sqlx::Error> {
+    if volunteered.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let intervals_since_convened =
+        intervals_participated_since_last_convened(crew_id.clone(), interval_id.clone(), pool)
+            .await?;
+    let last_convened_sequences =
+        find_last_convened_sequences(crew_id, interval_id.clone(), pool).await?;
+    let interval = find_interval(interval_id.clone(), pool).await?;
+    let today = collective_today(collective_id.clone(), clock, pool).await?;
+    let on_date = planning_date(&interval, today);
+    let collective_involvements =
+        find_all_collective_involvements(collective_id, interval_id, pool).await?;
+
+    // Nobody can be chosen for a time they won't be participating
+    let candidates = volunteered
+        .into_iter()
+        .filter(|involvement| {
+            !collective_involvements
+                .iter()
+                .any(|collective_involvement| {
+                    collective_involvement.person_id == involvement.person_id
+                        && collective_involvement.status_on(&on_date)
+                            != InvolvementStatus::Participating
+                })
+        })
+        .map(|involvement| {
+            let person_id = involvement.person_id;
+            ConvenorCandidate {
+                person_id,
+                intervals_since_convened: intervals_since_convened
+                    .get(&person_id)
+                    .cloned()
+                    .unwrap_or(0),
+                last_convened_sequence: last_convened_sequences.get(&person_id).cloned(),
+                capacity_score: collective_involvements
+                    .iter()
+                    .find(|collective_involvement| collective_involvement.person_id == person_id)
+                    .and_then(|collective_involvement| collective_involvement.capacity_score),
+            }
+        })
+        .collect();
+
+    Ok(candidates)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{intervals::clock::SystemClock, test_support::TestApp};
+
+    fn candidate(
+        person_id: i64,
+        intervals_since_convened: i64,
+        last_convened_sequence: Option<i64>,
+        capacity_score: Option<i64>,
+    ) -> ConvenorCandidate {
+        ConvenorCandidate {
+            person_id,
+            intervals_since_convened,
+            last_convened_sequence,
+            capacity_score,
+        }
+    }
+
+    #[test]
+    fn longest_since_convened_picks_the_longest_wait() {
+        let candidates = [
+            candidate(1, 2, Some(4), None),
+            candidate(2, 5, Some(1), None),
+            candidate(3, 3, None, None),
+        ];
+        assert_eq!(LongestSinceConvened.select(&candidates), Some(2));
+    }
+
+    #[test]
+    fn longest_since_convened_breaks_ties_by_lowest_person() {
+        let candidates = [candidate(7, 3, None, None), candidate(4, 3, None, None)];
+        assert_eq!(LongestSinceConvened.select(&candidates), Some(4));
+    }
+
+    #[test]
+    fn round_robin_picks_people_who_have_never_convened_first() {
+        let candidates = [
+            candidate(1, 0, Some(2), None),
+            candidate(2, 0, None, None),
+            candidate(3, 0, Some(1), None),
+        ];
+        assert_eq!(RoundRobin.select(&candidates), Some(2));
+    }
+
+    #[test]
+    fn round_robin_then_picks_whoever_convened_least_recently() {
+        let candidates = [
+            candidate(1, 0, Some(5), None),
+            candidate(2, 0, Some(3), None),
+        ];
+        assert_eq!(RoundRobin.select(&candidates), Some(2));
+    }
+
+    #[test]
+    fn round_robin_breaks_ties_by_lowest_person() {
+        let candidates = [
+            candidate(9, 0, Some(3), None),
+            candidate(6, 0, Some(3), None),
+        ];
+        assert_eq!(RoundRobin.select(&candidates), Some(6));
+
+        let candidates = [candidate(9, 0, None, None), candidate(6, 0, None, None)];
+        assert_eq!(RoundRobin.select(&candidates), Some(6));
+    }
+
+    #[test]
+    fn capacity_weighted_picks_the_highest_score() {
+        let candidates = [
+            candidate(1, 5, None, Some(-1)),
+            candidate(2, 0, None, Some(1)),
+            candidate(3, 9, None, Some(0)),
+        ];
+        assert_eq!(CapacityWeighted.select(&candidates), Some(2));
+    }
+
+    #[test]
+    fn capacity_weighted_counts_no_score_as_usual_capacity() {
+        let candidates = [candidate(1, 0, None, Some(-1)), candidate(2, 0, None, None)];
+        assert_eq!(CapacityWeighted.select(&candidates), Some(2));
+
+        let candidates = [candidate(1, 0, None, Some(1)), candidate(2, 9, None, None)];
+        assert_eq!(CapacityWeighted.select(&candidates), Some(1));
+    }
+
+    #[test]
+    fn capacity_weighted_breaks_ties_by_longest_since_convened_then_lowest_person() {
+        let candidates = [
+            candidate(1, 1, None, Some(1)),
+            candidate(2, 4, None, Some(1)),
+        ];
+        assert_eq!(CapacityWeighted.select(&candidates), Some(2));
+
+        let candidates = [
+            candidate(8, 4, None, Some(1)),
+            candidate(5, 4, None, Some(1)),
+        ];
+        assert_eq!(CapacityWeighted.select(&candidates), Some(5));
+    }
+
+    #[test]
+    fn manual_only_never_picks_and_keeps_existing_convenors() {
+        let candidates = [candidate(1, 5, None, Some(1))];
+        assert_eq!(ManualOnly.select(&candidates), None);
+        assert!(ManualOnly.keeps_existing_convenor());
+    }
+
+    #[test]
+    fn no_volunteers_leaves_every_crew_without_a_convenor() {
+        for strategy in [
+            ConvenorStrategy::LongestSinceConvened,
+            ConvenorStrategy::RoundRobin,
+            ConvenorStrategy::CapacityWeighted,
+            ConvenorStrategy::ManualOnly,
+        ] {
+            assert_eq!(selector_for(strategy).select(&[]), None);
+        }
+    }
+
+    #[test]
+    fn on_lock_timing_waits_until_the_interval_is_locked() {
+        use ConvenorSelectionTiming::{OnEveryUpdate, OnLock};
+
+        assert!(!selects_on_update(OnLock, IntervalPhase::OpenForPlanning));
+        assert!(selects_on_update(OnLock, IntervalPhase::Locked));
+        assert!(selects_on_update(OnLock, IntervalPhase::Active));
+        assert!(selects_on_update(
+            OnEveryUpdate,
+            IntervalPhase::OpenForPlanning
+        ));
+    }
+
+    // Two co-convenors, along with a member who volunteered to convene
+    async fn seed_co_convenors(app: &TestApp, phase: &str) -> (Interval, [i64; 3]) {
+        let first_id = app.add_person("first@example.com", "Member").await;
+        let second_id = app.add_person("second@example.com", "Member").await;
+        let volunteer_id = app.add_person("volunteer@example.com", "Member").await;
+        let interval_id = app.add_interval(7, phase, 1).await;
+
+        app.execute(
+            "INSERT INTO crews (id, name, collective_id, allow_co_convenors, convenor_strategy)
+            VALUES (1, 'Crew', 1, TRUE, 'LongestSinceConvened')",
+        )
+        .await;
+        app.execute(&format!(
+            "INSERT INTO crew_involvements
+                (person_id, crew_id, interval_id, convenor, volunteered_convenor)
+            VALUES ({first_id}, 1, {interval_id}, TRUE, TRUE),
+                ({second_id}, 1, {interval_id}, TRUE, TRUE),
+                ({volunteer_id}, 1, {interval_id}, FALSE, TRUE)"
+        ))
+        .await;
+
+        let interval = find_interval(IntervalId::new(interval_id), &app.pool)
+            .await
+            .unwrap();
+        (interval, [first_id, second_id, volunteer_id])
+    }
+
+    #[tokio::test]
+    async fn stepping_down_a_co_convenor_only_fills_their_seat() {
+        let app = TestApp::new().await;
+        let (interval, [first_id, second_id, volunteer_id]) =
+            seed_co_convenors(&app, "Active").await;
+
+        let change = step_down_convenor(
+            CollectiveId::new(1),
+            CrewId::new(1),
+            &interval,
+            PersonId::new(first_id),
+            &SystemClock,
+            &app.pool,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(change.previous_convenor_ids, vec![first_id, second_id]);
+        assert_eq!(change.convenor_ids, vec![second_id, volunteer_id]);
+    }
+
+    #[tokio::test]
+    async fn stepping_down_a_co_convenor_keeps_the_other_without_co_convenors() {
+        let app = TestApp::new().await;
+        let (interval, [first_id, second_id, _]) = seed_co_convenors(&app, "Active").await;
+        app.execute("UPDATE crews SET allow_co_convenors = FALSE")
+            .await;
+
+        let change = step_down_convenor(
+            CollectiveId::new(1),
+            CrewId::new(1),
+            &interval,
+            PersonId::new(first_id),
+            &SystemClock,
+            &app.pool,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(change.convenor_ids, vec![second_id]);
+    }
+
+    #[tokio::test]
+    async fn updating_after_a_seat_is_filled_keeps_both_co_convenors() {
+        let app = TestApp::new().await;
+        let (interval, [first_id, second_id, volunteer_id]) =
+            seed_co_convenors(&app, "OpenForPlanning").await;
+
+        step_down_convenor(
+            CollectiveId::new(1),
+            CrewId::new(1),
+            &interval,
+            PersonId::new(first_id),
+            &SystemClock,
+            &app.pool,
+        )
+        .await
+        .unwrap();
+        let change = update_convenor_if_needed(
+            CollectiveId::new(1),
+            CrewId::new(1),
+            interval.typed_id(),
+            interval.phase,
+            second_id,
+            &SystemClock,
+            &app.pool,
+        )
+        .await
+        .unwrap();
+
+        assert!(change.is_none());
+        let involvements = find_crew_involvements(CrewId::new(1), interval.typed_id(), &app.pool)
+            .await
+            .unwrap();
+        assert_eq!(convenor_ids(&involvements), vec![second_id, volunteer_id]);
+    }
+
+    #[test]
+    fn selecting_convenors_fills_each_seat_in_turn() {
+        let candidates = vec![
+            candidate(1, 1, None, None),
+            candidate(2, 3, None, None),
+            candidate(3, 2, None, None),
+        ];
+
+        assert_eq!(
+            select_convenors(&LongestSinceConvened, candidates.clone(), 2),
+            vec![2, 3]
+        );
+        assert_eq!(
+            select_convenors(&LongestSinceConvened, candidates[..1].to_vec(), 2),
+            vec![1]
+        );
+        assert!(select_convenors(&ManualOnly, candidates, 2).is_empty());
+    }
+}
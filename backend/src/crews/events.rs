@@ -1,9 +1,62 @@
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 
-use crate::shared::entities::CrewWithLinks;
+use crate::{crews::staffing::StaffingReport, shared::entities::CrewWithLinks};
 
 #[derive(Serialize, Deserialize, ToSchema, Clone, Debug)]
+pub struct ConvenorChange {
+    pub crew_id: i64,
+    pub interval_id: i64,
+    pub previous_convenor_ids: Vec<i64>,
+    pub convenor_ids: Vec<i64>,
+    // The person whose action led to the change
+    pub changed_by: i64,
+}
+
+impl ConvenorChange {
+    // Everyone whose convenor status is affected by the change
+    pub fn affected_person_ids(&self) -> Vec<i64> {
+        let mut person_ids: Vec<i64> = self
+            .previous_convenor_ids
+            .iter()
+            .chain(self.convenor_ids.iter())
+            .cloned()
+            .collect();
+        person_ids.sort_unstable();
+        person_ids.dedup();
+        person_ids
+    }
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Clone, Debug)]
+pub struct CrewJoinRequest {
+    pub crew_id: i64,
+    pub interval_id: i64,
+    pub person_id: i64,
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Clone, Debug)]
+pub struct RoleAssigneeChange {
+    pub crew_id: i64,
+    pub crew_role_id: i64,
+    pub interval_id: i64,
+    pub previous_assignee_ids: Vec<i64>,
+    pub assignee_ids: Vec<i64>,
+    pub changed_by: i64,
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Clone, Debug)]
+#[allow(clippy::enum_variant_names)]
 pub enum CrewsEvent {
     CrewUpdated(CrewWithLinks),
+    CrewCreated(CrewWithLinks),
+    CrewArchived(CrewWithLinks),
+    CrewUnarchived(CrewWithLinks),
+    CrewDeleted(i64),
+    StaffingChanged(StaffingReport),
+    ConvenorsChanged(ConvenorChange),
+    RoleAssigneesChanged(RoleAssigneeChange),
+    JoinRequested(CrewJoinRequest),
+    JoinRequestApproved(CrewJoinRequest),
+    JoinRequestDeclined(CrewJoinRequest),
 }
@@ -0,0 +1,120 @@
+use resend_rs::Resend;
+use sqlx::SqlitePool;
+
+use crate::{
+    crews::{
+        convenor_selection::convenor_ids,
+        events::CrewJoinRequest,
+        repo::{approve_crew_member, decline_crew_member, find_crew, find_crew_involvements},
+    },
+    notifications::{Notification, notify_people, repo::find_admin_person_ids},
+    people::repo::find_person_by_id,
+    shared::entities::{CollectiveId, CrewId, IntervalId, PersonId},
+};
+
+// Lets the crew's convenors know someone has asked to join, or the admins if the crew
+// has no convenor yet
+pub async fn notify_join_requested(
+    collective_id: CollectiveId,
+    request: &CrewJoinRequest,
+    resend: &Resend,
+    pool: &SqlitePool,
+) -> Result<(), sqlx::Error> {
+    let crew_id = CrewId::new(request.crew_id);
+    let interval_id = IntervalId::new(request.interval_id);
+
+    let crew = find_crew(collective_id.clone(), crew_id.clone(), pool).await?;
+    let person = find_person_by_id(
+        PersonId::new(request.person_id),
+        collective_id.clone(),
+        pool,
+    )
+    .await?;
+
+    let mut recipients: Vec<PersonId> =
+        convenor_ids(&find_crew_involvements(crew_id, interval_id, pool).await?)
+            .into_iter()
+            .map(PersonId::new)
+            .collect();
+    if recipients.is_empty() {
+        recipients = find_admin_person_ids(collective_id, pool).await?;
+    }
+
+    let notification = Notification {
+        subject: format!("{} has asked to join {}", person.display_name, crew.name),
+        message: format!(
+            "{} has asked to join {}. As it needs approval, they'll be a pending member until it's approved or declined.",
+            person.display_name, crew.name
+        ),
+    };
+    notify_people(recipients, &notification, resend, pool).await;
+
+    Ok(())
+}
+
+pub async fn approve_join_request(
+    collective_id: CollectiveId,
+    request: &CrewJoinRequest,
+    resend: &Resend,
+    pool: &SqlitePool,
+) -> Result<(), sqlx::Error> {
+    let crew_id = CrewId::new(request.crew_id);
+    let crew = find_crew(collective_id, crew_id.clone(), pool).await?;
+
+    approve_crew_member(
+        crew_id,
+        IntervalId::new(request.interval_id),
+        PersonId::new(request.person_id),
+        pool,
+    )
+    .await?;
+
+    let notification = Notification {
+        subject: format!("You've joined {}", crew.name),
+        message: format!("Your request to join {} has been approved.", crew.name),
+    };
+    notify_people(
+        vec![PersonId::new(request.person_id)],
+        &notification,
+        resend,
+        pool,
+    )
+    .await;
+
+    Ok(())
+}
+
+pub async fn decline_join_request(
+    collective_id: CollectiveId,
+    request: &CrewJoinRequest,
+    resend: &Resend,
+    pool: &SqlitePool,
+) -> Result<(), sqlx::Error> {
+    let crew_id = CrewId::new(request.crew_id);
+    let crew = find_crew(collective_id, crew_id.clone(), pool).await?;
+
+    decline_crew_member(
+        crew_id,
+        IntervalId::new(request.interval_id),
+        PersonId::new(request.person_id),
+        pool,
+    )
+    .await?;
+
+    let notification = Notification {
+        subject: format!("Your request to join {}", crew.name),
+        message: format!(
+            "Your request to join {} wasn't approved this time. Talk to its convenor to find out more.",
+            crew.name
+        ),
+    };
+    notify_people(
+        vec![PersonId::new(request.person_id)],
+        &notification,
+        resend,
+        pool,
+    )
+    .await;
+
+    Ok(())
+}
@@ -1,19 +1,152 @@
-use axum::{Extension, Json, extract::Path, http::StatusCode, response::IntoResponse};
+use axum::{
+    Extension, Json,
+    extract::Path,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use resend_rs::Resend;
+use serde::{Deserialize, Serialize};
 use sqlx::SqlitePool;
+use utoipa::ToSchema;
 use utoipa_axum::{router::OpenApiRouter, routes};
 
 use crate::{
-    auth::auth_backend::AuthSession,
-    crews::events::CrewsEvent,
+    auth::{
+        auth_backend::AuthSession,
+        permissions::{ActingPerson, find_acting_person, require_admin},
+    },
+    crews::{
+        events::{ConvenorChange, CrewJoinRequest, CrewsEvent},
+        repo::CrewDeletion,
+        roles::assign_role,
+        rotation::RotationReport,
+        staffing::StaffingReport,
+    },
+    intervals::{
+        clock::{SystemClock, collective_today},
+        phases::can_update_participation,
+        repo::{find_current_interval, find_interval},
+    },
     realtime::RealtimeState,
-    shared::{default_collective_id, entities::CrewWithLinks, events::AppEvent},
+    shared::{
+        default_collective_id,
+        entities::{ConvenorStrategy, CrewId, CrewWithLinks, Interval, IntervalId},
+        events::AppEvent,
+    },
 };
 
+pub mod convenor_selection;
 pub mod events;
+pub mod membership;
 pub mod repo;
+pub mod roles;
+pub mod rotation;
+pub mod staffing;
 
 pub fn router() -> OpenApiRouter {
-    OpenApiRouter::new().routes(routes!(update_crew))
+    OpenApiRouter::new()
+        .routes(routes!(create_crew))
+        .routes(routes!(update_crew, delete_crew))
+        .routes(routes!(archive_crew))
+        .routes(routes!(unarchive_crew))
+        .routes(routes!(get_staffing_report))
+        .routes(routes!(get_rotation_report))
+        .routes(routes!(assign_convenors))
+        .routes(routes!(step_down_convenor))
+        .routes(routes!(approve_join_request))
+        .routes(routes!(decline_join_request))
+        .routes(routes!(create_crew_role))
+        .routes(routes!(update_crew_role, delete_crew_role))
+        .routes(routes!(assign_crew_role))
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct CrewRoleInput {
+    pub name: String,
+    // ManualOnly roles are only ever assigned by hand
+    #[serde(default = "manual_only")]
+    pub strategy: ConvenorStrategy,
+}
+
+fn manual_only() -> ConvenorStrategy {
+    ConvenorStrategy::ManualOnly
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct ConvenorsInput {
+    // For convenors, up to two people if the crew allows co-convenors
+    pub person_ids: Vec<i64>,
+}
+
+#[utoipa::path(get, path = "/interval/{interval_id}/staffing",
+    params(
+        ("interval_id" = i64, Path, description = "Interval ID")
+    ),
+    responses(
+        (status = 200, body = StaffingReport),
+        (status = UNAUTHORIZED, description = "Not logged in", body = ()),
+        (status = INTERNAL_SERVER_ERROR, description = "Internal server error", body = ()),
+    ),
+)]
+pub async fn get_staffing_report(
+    Path(interval_id): Path<i64>,
+    Extension(pool): Extension<SqlitePool>,
+    auth_session: AuthSession,
+) -> impl IntoResponse {
+    if auth_session.user.is_none() {
+        return (StatusCode::UNAUTHORIZED, ()).into_response();
+    }
+
+    let result = staffing::find_staffing_report(
+        default_collective_id(),
+        IntervalId::new(interval_id),
+        &SystemClock,
+        &pool,
+    )
+    .await;
+
+    match result {
+        Ok(report) => (StatusCode::OK, Json(report)).into_response(),
+        Err(_) => (StatusCode::INTERNAL_SERVER_ERROR, ()).into_response(),
+    }
+}
+
+#[utoipa::path(post, path = "/",
+    request_body(content = CrewWithLinks, content_type = "application/json"),
+    responses(
+        (status = 201, body = Vec<AppEvent>),
+        (status = BAD_REQUEST, description = "Parent crew not found, or the staffing limits aren't valid", body = ()),
+        (status = FORBIDDEN, description = "Only admins, or the parent crew's convenors, can create crews", body = ()),
+        (status = INTERNAL_SERVER_ERROR, description = "Internal server error", body = ()),
+    ),
+)]
+pub async fn create_crew(
+    Extension(pool): Extension<SqlitePool>,
+    Extension(realtime_state): Extension<RealtimeState>,
+    auth_session: AuthSession,
+    Json(input): Json<CrewWithLinks>,
+) -> impl IntoResponse {
+    if let Err(status) =
+        require_admin_or_current_convenor(&auth_session, input.parent_crew_id, &pool).await
+    {
+        return (status, ()).into_response();
+    }
+
+    println!("Creating crew: {:?}", input);
+
+    match repo::insert_crew_with_links(default_collective_id(), input, &pool).await {
+        Ok(response) => {
+            let event = AppEvent::CrewsEvent(CrewsEvent::CrewCreated(response));
+            realtime_state
+                .broadcast_app_event(Some(auth_session), event.clone())
+                .await;
+            (StatusCode::CREATED, Json(vec![event])).into_response()
+        }
+        Err(sqlx::Error::InvalidArgument(message)) => {
+            (StatusCode::BAD_REQUEST, message).into_response()
+        }
+        Err(_) => (StatusCode::INTERNAL_SERVER_ERROR, ()).into_response(),
+    }
 }
 
 #[utoipa::path(put, path = "/{crew_id}",
@@ -21,7 +154,9 @@ pub fn router() -> OpenApiRouter {
     responses(
         (status = 200, body = Vec<AppEvent>),
         (status = INTERNAL_SERVER_ERROR, description = "Internal server error", body = ()),
-        (status = BAD_REQUEST,  body = ()),
+        (status = BAD_REQUEST, description = "Crew ID mismatch, or the parent crew or staffing limits aren't valid", body = ()),
+        (status = FORBIDDEN, description = "Only admins, or the crew's convenors, can update crews. Moving a crew also needs convening both its old and new parents", body = ()),
+        (status = NOT_FOUND, description = "Crew not found", body = ()),
     ),
 )]
 pub async fn update_crew(
@@ -31,12 +166,35 @@ pub async fn update_crew(
     auth_session: AuthSession,
     Json(input): Json<CrewWithLinks>,
 ) -> impl IntoResponse {
-    println!("Updating crew with ID {}: {:?}", crew_id, input);
-
     if input.id != crew_id {
         return (StatusCode::BAD_REQUEST, "Crew ID mismatch").into_response();
     }
 
+    let crew = match repo::find_crew(default_collective_id(), CrewId::new(crew_id), &pool).await {
+        Ok(crew) => crew,
+        Err(sqlx::Error::RowNotFound) => return (StatusCode::NOT_FOUND, ()).into_response(),
+        Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR, ()).into_response(),
+    };
+    // This includes whether joining needs a convenor's approval
+    if let Err(status) =
+        require_admin_or_current_convenor(&auth_session, Some(crew_id), &pool).await
+    {
+        return (status, ()).into_response();
+    }
+    // Moving a crew takes it out from under one crew and puts it under another, so it needs
+    // the say of both. Top level crews are left to admins
+    if input.parent_crew_id != crew.parent_crew_id {
+        for parent_crew_id in [crew.parent_crew_id, input.parent_crew_id] {
+            if let Err(status) =
+                require_admin_or_current_convenor(&auth_session, parent_crew_id, &pool).await
+            {
+                return (status, ()).into_response();
+            }
+        }
+    }
+
+    println!("Updating crew with ID {}: {:?}", crew_id, input);
+
     match repo::update_crew_with_links(default_collective_id(), input, &pool).await {
         Ok(response) => {
             let event = AppEvent::CrewsEvent(CrewsEvent::CrewUpdated(response));
@@ -45,6 +203,698 @@ pub async fn update_crew(
                 .await;
             (StatusCode::OK, Json(vec![event])).into_response()
         }
+        Err(sqlx::Error::InvalidArgument(message)) => {
+            (StatusCode::BAD_REQUEST, message).into_response()
+        }
+        Err(_) => (StatusCode::INTERNAL_SERVER_ERROR, ()).into_response(),
+    }
+}
+
+#[utoipa::path(post, path = "/{crew_id}/archive",
+    params(
+        ("crew_id" = i64, Path, description = "Crew ID")
+    ),
+    responses(
+        (status = 200, body = Vec<AppEvent>),
+        (status = FORBIDDEN, description = "Only admins, or the parent crew's convenors, can archive crews", body = ()),
+        (status = NOT_FOUND, description = "Crew not found", body = ()),
+        (status = INTERNAL_SERVER_ERROR, description = "Internal server error", body = ()),
+    ),
+)]
+pub async fn archive_crew(
+    Path(crew_id): Path<i64>,
+    Extension(pool): Extension<SqlitePool>,
+    Extension(realtime_state): Extension<RealtimeState>,
+    auth_session: AuthSession,
+) -> impl IntoResponse {
+    set_crew_archived(crew_id, true, pool, realtime_state, auth_session).await
+}
+
+#[utoipa::path(post, path = "/{crew_id}/unarchive",
+    params(
+        ("crew_id" = i64, Path, description = "Crew ID")
+    ),
+    responses(
+        (status = 200, body = Vec<AppEvent>),
+        (status = FORBIDDEN, description = "Only admins, or the parent crew's convenors, can unarchive crews", body = ()),
+        (status = NOT_FOUND, description = "Crew not found", body = ()),
+        (status = INTERNAL_SERVER_ERROR, description = "Internal server error", body = ()),
+    ),
+)]
+pub async fn unarchive_crew(
+    Path(crew_id): Path<i64>,
+    Extension(pool): Extension<SqlitePool>,
+    Extension(realtime_state): Extension<RealtimeState>,
+    auth_session: AuthSession,
+) -> impl IntoResponse {
+    set_crew_archived(crew_id, false, pool, realtime_state, auth_session).await
+}
+
+async fn set_crew_archived(
+    crew_id: i64,
+    archived: bool,
+    pool: SqlitePool,
+    realtime_state: RealtimeState,
+    auth_session: AuthSession,
+) -> Response {
+    let crew = match repo::find_crew(default_collective_id(), CrewId::new(crew_id), &pool).await {
+        Ok(crew) => crew,
+        Err(sqlx::Error::RowNotFound) => return (StatusCode::NOT_FOUND, ()).into_response(),
+        Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR, ()).into_response(),
+    };
+    if let Err(status) =
+        require_admin_or_current_convenor(&auth_session, crew.parent_crew_id, &pool).await
+    {
+        return (status, ()).into_response();
+    }
+
+    println!("Setting crew {} archived to {}", crew_id, archived);
+
+    match repo::set_crew_archived(
+        default_collective_id(),
+        CrewId::new(crew_id),
+        archived,
+        &pool,
+    )
+    .await
+    {
+        Ok(response) => {
+            let event = match archived {
+                true => CrewsEvent::CrewArchived(response),
+                false => CrewsEvent::CrewUnarchived(response),
+            };
+            let event = AppEvent::CrewsEvent(event);
+            realtime_state
+                .broadcast_app_event(Some(auth_session), event.clone())
+                .await;
+            (StatusCode::OK, Json(vec![event])).into_response()
+        }
+        Err(sqlx::Error::RowNotFound) => (StatusCode::NOT_FOUND, ()).into_response(),
+        Err(_) => (StatusCode::INTERNAL_SERVER_ERROR, ()).into_response(),
+    }
+}
+
+// Crews that anyone has ever been involved in are part of the collective's history, so
+// can only be archived, not deleted
+#[utoipa::path(delete, path = "/{crew_id}",
+    params(
+        ("crew_id" = i64, Path, description = "Crew ID")
+    ),
+    responses(
+        (status = 200, body = Vec<AppEvent>),
+        (status = CONFLICT, description = "The crew has involvements, so can only be archived", body = ()),
+        (status = FORBIDDEN, description = "Only admins can delete crews", body = ()),
+        (status = NOT_FOUND, description = "Crew not found", body = ()),
+        (status = INTERNAL_SERVER_ERROR, description = "Internal server error", body = ()),
+    ),
+)]
+pub async fn delete_crew(
+    Path(crew_id): Path<i64>,
+    Extension(pool): Extension<SqlitePool>,
+    Extension(realtime_state): Extension<RealtimeState>,
+    auth_session: AuthSession,
+) -> impl IntoResponse {
+    if let Err(status) = require_admin(&auth_session, default_collective_id(), &pool).await {
+        return (status, ()).into_response();
+    }
+
+    let crew_id = CrewId::new(crew_id);
+    println!("Deleting crew {}", crew_id.id);
+
+    match repo::delete_crew(default_collective_id(), crew_id.clone(), &pool).await {
+        Ok(CrewDeletion::HasInvolvements) => {
+            (StatusCode::CONFLICT, "Crew has involvements").into_response()
+        }
+        Ok(CrewDeletion::Deleted) => {
+            let event = AppEvent::CrewsEvent(CrewsEvent::CrewDeleted(crew_id.id));
+            realtime_state
+                .broadcast_app_event(Some(auth_session), event.clone())
+                .await;
+            (StatusCode::OK, Json(vec![event])).into_response()
+        }
+        Err(sqlx::Error::RowNotFound) => (StatusCode::NOT_FOUND, ()).into_response(),
+        Err(_) => (StatusCode::INTERNAL_SERVER_ERROR, ()).into_response(),
+    }
+}
+
+// Who has convened each crew in every interval so far, to check the rotation is fair
+#[utoipa::path(get, path = "/rotation",
+    responses(
+        (status = 200, body = RotationReport),
+        (status = UNAUTHORIZED, description = "Not logged in", body = ()),
+        (status = INTERNAL_SERVER_ERROR, description = "Internal server error", body = ()),
+    ),
+)]
+pub async fn get_rotation_report(
+    Extension(pool): Extension<SqlitePool>,
+    auth_session: AuthSession,
+) -> impl IntoResponse {
+    if auth_session.user.is_none() {
+        return (StatusCode::UNAUTHORIZED, ()).into_response();
+    }
+
+    let Ok(today) = collective_today(default_collective_id(), &SystemClock, &pool).await else {
+        return (StatusCode::INTERNAL_SERVER_ERROR, ()).into_response();
+    };
+
+    match rotation::find_rotation_report(default_collective_id(), today, &pool).await {
+        Ok(report) => (StatusCode::OK, Json(report)).into_response(),
+        Err(_) => (StatusCode::INTERNAL_SERVER_ERROR, ()).into_response(),
+    }
+}
+
+#[utoipa::path(put, path = "/{crew_id}/interval/{interval_id}/convenors",
+    params(
+        ("crew_id" = i64, Path, description = "Crew ID"),
+        ("interval_id" = i64, Path, description = "Interval ID")
+    ),
+    request_body(content = ConvenorsInput, content_type = "application/json"),
+    responses(
+        (status = 200, body = Vec<AppEvent>),
+        (status = BAD_REQUEST, description = "Too many convenors, or someone isn't in the crew", body = ()),
+        (status = FORBIDDEN, description = "Only admins and the crew's convenors can assign convenors, while the interval can still be changed", body = ()),
+        (status = NOT_FOUND, description = "Crew not found", body = ()),
+        (status = INTERNAL_SERVER_ERROR, description = "Internal server error", body = ()),
+    ),
+)]
+pub async fn assign_convenors(
+    Path((crew_id, interval_id)): Path<(i64, i64)>,
+    Extension(pool): Extension<SqlitePool>,
+    Extension(realtime_state): Extension<RealtimeState>,
+    auth_session: AuthSession,
+    Json(input): Json<ConvenorsInput>,
+) -> impl IntoResponse {
+    let crew_id = CrewId::new(crew_id);
+    let interval_id = IntervalId::new(interval_id);
+    let acting_person =
+        match require_admin_or_convenor(&auth_session, crew_id.clone(), interval_id.clone(), &pool)
+            .await
+        {
+            Ok(acting_person) => acting_person,
+            Err(status) => return (status, ()).into_response(),
+        };
+    if let Err(status) = find_updatable_interval(interval_id.clone(), &acting_person, &pool).await {
+        return (status, ()).into_response();
+    }
+
+    println!(
+        "Person {} assigning convenors {:?} for crew {} in interval {}",
+        acting_person.person_id.id, input.person_ids, crew_id.id, interval_id.id
+    );
+
+    let result = convenor_selection::assign_convenors(
+        default_collective_id(),
+        crew_id,
+        interval_id,
+        input.person_ids,
+        acting_person.person_id,
+        &pool,
+    )
+    .await;
+
+    convenors_changed_response(result, realtime_state, auth_session).await
+}
+
+#[utoipa::path(post, path = "/{crew_id}/interval/{interval_id}/step_down",
+    params(
+        ("crew_id" = i64, Path, description = "Crew ID"),
+        ("interval_id" = i64, Path, description = "Interval ID")
+    ),
+    responses(
+        (status = 200, body = Vec<AppEvent>),
+        (status = FORBIDDEN, description = "The interval can no longer be changed", body = ()),
+        (status = NOT_FOUND, description = "Not a convenor of the crew in this interval", body = ()),
+        (status = INTERNAL_SERVER_ERROR, description = "Internal server error", body = ()),
+    ),
+)]
+pub async fn step_down_convenor(
+    Path((crew_id, interval_id)): Path<(i64, i64)>,
+    Extension(pool): Extension<SqlitePool>,
+    Extension(realtime_state): Extension<RealtimeState>,
+    auth_session: AuthSession,
+) -> impl IntoResponse {
+    let acting_person =
+        match find_acting_person(&auth_session, default_collective_id(), &pool).await {
+            Ok(acting_person) => acting_person,
+            Err(status) => return (status, ()).into_response(),
+        };
+
+    let interval =
+        match find_updatable_interval(IntervalId::new(interval_id), &acting_person, &pool).await {
+            Ok(interval) => interval,
+            Err(status) => return (status, ()).into_response(),
+        };
+
+    println!(
+        "Person {} stepping down as convenor of crew {} in interval {}",
+        acting_person.person_id.id, crew_id, interval_id
+    );
+
+    let result = convenor_selection::step_down_convenor(
+        default_collective_id(),
+        CrewId::new(crew_id),
+        &interval,
+        acting_person.person_id,
+        &SystemClock,
+        &pool,
+    )
+    .await;
+
+    convenors_changed_response(result, realtime_state, auth_session).await
+}
+
+#[utoipa::path(post, path = "/{crew_id}/interval/{interval_id}/members/{person_id}/approve",
+    params(
+        ("crew_id" = i64, Path, description = "Crew ID"),
+        ("interval_id" = i64, Path, description = "Interval ID"),
+        ("person_id" = i64, Path, description = "Person ID")
+    ),
+    responses(
+        (status = 200, body = Vec<AppEvent>),
+        (status = FORBIDDEN, description = "Only admins and the crew's convenors can approve join requests, while the interval can still be changed", body = ()),
+        (status = NOT_FOUND, description = "No pending join request", body = ()),
+        (status = INTERNAL_SERVER_ERROR, description = "Internal server error", body = ()),
+    ),
+)]
+pub async fn approve_join_request(
+    Path((crew_id, interval_id, person_id)): Path<(i64, i64, i64)>,
+    Extension(pool): Extension<SqlitePool>,
+    Extension(realtime_state): Extension<RealtimeState>,
+    Extension(resend): Extension<Resend>,
+    auth_session: AuthSession,
+) -> impl IntoResponse {
+    let acting_person = match require_admin_or_convenor(
+        &auth_session,
+        CrewId::new(crew_id),
+        IntervalId::new(interval_id),
+        &pool,
+    )
+    .await
+    {
+        Ok(acting_person) => acting_person,
+        Err(status) => return (status, ()).into_response(),
+    };
+    if let Err(status) =
+        find_updatable_interval(IntervalId::new(interval_id), &acting_person, &pool).await
+    {
+        return (status, ()).into_response();
+    }
+
+    println!(
+        "Person {} approving person {} joining crew {} in interval {}",
+        acting_person.person_id.id, person_id, crew_id, interval_id
+    );
+
+    let request = CrewJoinRequest {
+        crew_id,
+        interval_id,
+        person_id,
+    };
+    let result =
+        membership::approve_join_request(default_collective_id(), &request, &resend, &pool).await;
+
+    join_request_response(
+        result.map(|_| CrewsEvent::JoinRequestApproved(request)),
+        realtime_state,
+        auth_session,
+    )
+    .await
+}
+
+#[utoipa::path(post, path = "/{crew_id}/interval/{interval_id}/members/{person_id}/decline",
+    params(
+        ("crew_id" = i64, Path, description = "Crew ID"),
+        ("interval_id" = i64, Path, description = "Interval ID"),
+        ("person_id" = i64, Path, description = "Person ID")
+    ),
+    responses(
+        (status = 200, body = Vec<AppEvent>),
+        (status = FORBIDDEN, description = "Only admins and the crew's convenors can decline join requests, while the interval can still be changed", body = ()),
+        (status = NOT_FOUND, description = "No pending join request", body = ()),
+        (status = INTERNAL_SERVER_ERROR, description = "Internal server error", body = ()),
+    ),
+)]
+pub async fn decline_join_request(
+    Path((crew_id, interval_id, person_id)): Path<(i64, i64, i64)>,
+    Extension(pool): Extension<SqlitePool>,
+    Extension(realtime_state): Extension<RealtimeState>,
+    Extension(resend): Extension<Resend>,
+    auth_session: AuthSession,
+) -> impl IntoResponse {
+    let acting_person = match require_admin_or_convenor(
+        &auth_session,
+        CrewId::new(crew_id),
+        IntervalId::new(interval_id),
+        &pool,
+    )
+    .await
+    {
+        Ok(acting_person) => acting_person,
+        Err(status) => return (status, ()).into_response(),
+    };
+    if let Err(status) =
+        find_updatable_interval(IntervalId::new(interval_id), &acting_person, &pool).await
+    {
+        return (status, ()).into_response();
+    }
+
+    println!(
+        "Person {} declining person {} joining crew {} in interval {}",
+        acting_person.person_id.id, person_id, crew_id, interval_id
+    );
+
+    let request = CrewJoinRequest {
+        crew_id,
+        interval_id,
+        person_id,
+    };
+    let result =
+        membership::decline_join_request(default_collective_id(), &request, &resend, &pool).await;
+
+    join_request_response(
+        result.map(|_| CrewsEvent::JoinRequestDeclined(request)),
+        realtime_state,
+        auth_session,
+    )
+    .await
+}
+
+async fn join_request_response(
+    result: Result<CrewsEvent, sqlx::Error>,
+    realtime_state: RealtimeState,
+    auth_session: AuthSession,
+) -> Response {
+    match result {
+        Ok(crews_event) => {
+            let event = AppEvent::CrewsEvent(crews_event);
+            realtime_state
+                .broadcast_app_event(Some(auth_session), event.clone())
+                .await;
+            (StatusCode::OK, Json(vec![event])).into_response()
+        }
+        Err(sqlx::Error::RowNotFound) => (StatusCode::NOT_FOUND, ()).into_response(),
+        Err(_) => (StatusCode::INTERNAL_SERVER_ERROR, ()).into_response(),
+    }
+}
+
+// Admins can manage any crew, and convenors can manage their own crew, and the crews
+// under it, for the interval
+async fn require_admin_or_convenor(
+    auth_session: &AuthSession,
+    crew_id: CrewId,
+    interval_id: IntervalId,
+    pool: &SqlitePool,
+) -> Result<ActingPerson, StatusCode> {
+    let acting_person = find_acting_person(auth_session, default_collective_id(), pool).await?;
+    if acting_person.is_admin() {
+        return Ok(acting_person);
+    }
+
+    let convenes =
+        repo::convenes_crew_or_parent(crew_id, interval_id, acting_person.person_id.clone(), pool)
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    if convenes {
+        Ok(acting_person)
+    } else {
+        Err(StatusCode::FORBIDDEN)
+    }
+}
+
+// Who convenes, holds roles in and belongs to each crew is part of people's participation, so
+// can only change while the person acting could change their participation in the interval
+async fn find_updatable_interval(
+    interval_id: IntervalId,
+    acting_person: &ActingPerson,
+    pool: &SqlitePool,
+) -> Result<Interval, StatusCode> {
+    let interval = match find_interval(interval_id, pool).await {
+        Ok(interval) => interval,
+        Err(sqlx::Error::RowNotFound) => return Err(StatusCode::NOT_FOUND),
+        Err(_) => return Err(StatusCode::INTERNAL_SERVER_ERROR),
+    };
+    let today = collective_today(default_collective_id(), &SystemClock, pool)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    if can_update_participation(&interval, today, acting_person.is_admin()) {
+        Ok(interval)
+    } else {
+        Err(StatusCode::FORBIDDEN)
+    }
+}
+
+// Admins, or whoever convenes the crew, or a crew above it, in the current interval. Things
+// that don't belong to a crew are left to admins
+pub async fn require_admin_or_current_convenor(
+    auth_session: &AuthSession,
+    crew_id: Option<i64>,
+    pool: &SqlitePool,
+) -> Result<ActingPerson, StatusCode> {
+    let Some(crew_id) = crew_id else {
+        return require_admin(auth_session, default_collective_id(), pool).await;
+    };
+
+    let today = collective_today(default_collective_id(), &SystemClock, pool)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let interval = match find_current_interval(default_collective_id(), today, pool).await {
+        Ok(interval) => interval,
+        // Without a current interval there are no convenors, so only admins can manage crews
+        Err(sqlx::Error::RowNotFound) => {
+            return require_admin(auth_session, default_collective_id(), pool).await;
+        }
+        Err(_) => return Err(StatusCode::INTERNAL_SERVER_ERROR),
+    };
+
+    require_admin_or_convenor(
+        auth_session,
+        CrewId::new(crew_id),
+        interval.typed_id(),
+        pool,
+    )
+    .await
+}
+
+async fn convenors_changed_response(
+    result: Result<ConvenorChange, sqlx::Error>,
+    realtime_state: RealtimeState,
+    auth_session: AuthSession,
+) -> Response {
+    match result {
+        Ok(change) => {
+            let event = AppEvent::CrewsEvent(CrewsEvent::ConvenorsChanged(change));
+            realtime_state
+                .broadcast_app_event(Some(auth_session), event.clone())
+                .await;
+            (StatusCode::OK, Json(vec![event])).into_response()
+        }
+        Err(sqlx::Error::RowNotFound) => (StatusCode::NOT_FOUND, ()).into_response(),
+        Err(sqlx::Error::InvalidArgument(message)) => {
+            (StatusCode::BAD_REQUEST, message).into_response()
+        }
+        Err(_) => (StatusCode::INTERNAL_SERVER_ERROR, ()).into_response(),
+    }
+}
+
+#[utoipa::path(post, path = "/{crew_id}/roles",
+    params(
+        ("crew_id" = i64, Path, description = "Crew ID")
+    ),
+    request_body(content = CrewRoleInput, content_type = "application/json"),
+    responses(
+        (status = 201, body = Vec<AppEvent>),
+        (status = FORBIDDEN, description = "Only admins can change crew roles", body = ()),
+        (status = INTERNAL_SERVER_ERROR, description = "Internal server error", body = ()),
+    ),
+)]
+pub async fn create_crew_role(
+    Path(crew_id): Path<i64>,
+    Extension(pool): Extension<SqlitePool>,
+    Extension(realtime_state): Extension<RealtimeState>,
+    auth_session: AuthSession,
+    Json(input): Json<CrewRoleInput>,
+) -> impl IntoResponse {
+    if let Err(status) = require_admin(&auth_session, default_collective_id(), &pool).await {
+        return (status, ()).into_response();
+    }
+
+    let crew_id = CrewId::new(crew_id);
+    if repo::find_crew(default_collective_id(), crew_id.clone(), &pool)
+        .await
+        .is_err()
+    {
+        return (StatusCode::NOT_FOUND, ()).into_response();
+    }
+
+    println!("Creating role {} for crew {}", input.name, crew_id.id);
+
+    let result = repo::insert_crew_role(crew_id.clone(), input.name, input.strategy, &pool).await;
+    if result.is_err() {
+        return (StatusCode::INTERNAL_SERVER_ERROR, ()).into_response();
+    }
+
+    crew_roles_changed_response(
+        crew_id,
+        StatusCode::CREATED,
+        pool,
+        realtime_state,
+        auth_session,
+    )
+    .await
+}
+
+#[utoipa::path(put, path = "/{crew_id}/roles/{role_id}",
+    params(
+        ("crew_id" = i64, Path, description = "Crew ID"),
+        ("role_id" = i64, Path, description = "Crew role ID")
+    ),
+    request_body(content = CrewRoleInput, content_type = "application/json"),
+    responses(
+        (status = 200, body = Vec<AppEvent>),
+        (status = FORBIDDEN, description = "Only admins can change crew roles", body = ()),
+        (status = NOT_FOUND, description = "Crew role not found", body = ()),
+        (status = INTERNAL_SERVER_ERROR, description = "Internal server error", body = ()),
+    ),
+)]
+pub async fn update_crew_role(
+    Path((crew_id, role_id)): Path<(i64, i64)>,
+    Extension(pool): Extension<SqlitePool>,
+    Extension(realtime_state): Extension<RealtimeState>,
+    auth_session: AuthSession,
+    Json(input): Json<CrewRoleInput>,
+) -> impl IntoResponse {
+    if let Err(status) = require_admin(&auth_session, default_collective_id(), &pool).await {
+        return (status, ()).into_response();
+    }
+
+    let crew_id = CrewId::new(crew_id);
+    match repo::update_crew_role(crew_id.clone(), role_id, input.name, input.strategy, &pool).await
+    {
+        Ok(_) => {
+            crew_roles_changed_response(crew_id, StatusCode::OK, pool, realtime_state, auth_session)
+                .await
+        }
+        Err(sqlx::Error::RowNotFound) => (StatusCode::NOT_FOUND, ()).into_response(),
+        Err(_) => (StatusCode::INTERNAL_SERVER_ERROR, ()).into_response(),
+    }
+}
+
+#[utoipa::path(delete, path = "/{crew_id}/roles/{role_id}",
+    params(
+        ("crew_id" = i64, Path, description = "Crew ID"),
+        ("role_id" = i64, Path, description = "Crew role ID")
+    ),
+    responses(
+        (status = 200, body = Vec<AppEvent>),
+        (status = FORBIDDEN, description = "Only admins can change crew roles", body = ()),
+        (status = NOT_FOUND, description = "Crew role not found", body = ()),
+        (status = INTERNAL_SERVER_ERROR, description = "Internal server error", body = ()),
+    ),
+)]
+pub async fn delete_crew_role(
+    Path((crew_id, role_id)): Path<(i64, i64)>,
+    Extension(pool): Extension<SqlitePool>,
+    Extension(realtime_state): Extension<RealtimeState>,
+    auth_session: AuthSession,
+) -> impl IntoResponse {
+    if let Err(status) = require_admin(&auth_session, default_collective_id(), &pool).await {
+        return (status, ()).into_response();
+    }
+
+    let crew_id = CrewId::new(crew_id);
+    match repo::delete_crew_role(crew_id.clone(), role_id, &pool).await {
+        Ok(_) => {
+            crew_roles_changed_response(crew_id, StatusCode::OK, pool, realtime_state, auth_session)
+                .await
+        }
+        Err(sqlx::Error::RowNotFound) => (StatusCode::NOT_FOUND, ()).into_response(),
+        Err(_) => (StatusCode::INTERNAL_SERVER_ERROR, ()).into_response(),
+    }
+}
+
+// Roles are part of the crew, so changes to them go out as crew updates
+async fn crew_roles_changed_response(
+    crew_id: CrewId,
+    status: StatusCode,
+    pool: SqlitePool,
+    realtime_state: RealtimeState,
+    auth_session: AuthSession,
+) -> Response {
+    match repo::find_crew_with_links(default_collective_id(), crew_id, &pool).await {
+        Ok(crew) => {
+            let event = AppEvent::CrewsEvent(CrewsEvent::CrewUpdated(crew));
+            realtime_state
+                .broadcast_app_event(Some(auth_session), event.clone())
+                .await;
+            (status, Json(vec![event])).into_response()
+        }
+        Err(_) => (StatusCode::INTERNAL_SERVER_ERROR, ()).into_response(),
+    }
+}
+
+#[utoipa::path(put, path = "/{crew_id}/roles/{role_id}/interval/{interval_id}/assignees",
+    params(
+        ("crew_id" = i64, Path, description = "Crew ID"),
+        ("role_id" = i64, Path, description = "Crew role ID"),
+        ("interval_id" = i64, Path, description = "Interval ID")
+    ),
+    request_body(content = ConvenorsInput, content_type = "application/json"),
+    responses(
+        (status = 200, body = Vec<AppEvent>),
+        (status = BAD_REQUEST, description = "Someone isn't in the crew", body = ()),
+        (status = FORBIDDEN, description = "Only admins and the crew's convenors can assign roles, while the interval can still be changed", body = ()),
+        (status = NOT_FOUND, description = "Crew role not found", body = ()),
+        (status = INTERNAL_SERVER_ERROR, description = "Internal server error", body = ()),
+    ),
+)]
+pub async fn assign_crew_role(
+    Path((crew_id, role_id, interval_id)): Path<(i64, i64, i64)>,
+    Extension(pool): Extension<SqlitePool>,
+    Extension(realtime_state): Extension<RealtimeState>,
+    auth_session: AuthSession,
+    Json(input): Json<ConvenorsInput>,
+) -> impl IntoResponse {
+    let crew_id = CrewId::new(crew_id);
+    let interval_id = IntervalId::new(interval_id);
+    let acting_person =
+        match require_admin_or_convenor(&auth_session, crew_id.clone(), interval_id.clone(), &pool)
+            .await
+        {
+            Ok(acting_person) => acting_person,
+            Err(status) => return (status, ()).into_response(),
+        };
+    if let Err(status) = find_updatable_interval(interval_id.clone(), &acting_person, &pool).await {
+        return (status, ()).into_response();
+    }
+
+    println!(
+        "Person {} assigning role {} to {:?} in interval {}",
+        acting_person.person_id.id, role_id, input.person_ids, interval_id.id
+    );
+
+    let result = assign_role(
+        crew_id,
+        role_id,
+        interval_id,
+        input.person_ids,
+        acting_person.person_id,
+        &pool,
+    )
+    .await;
+
+    match result {
+        Ok(change) => {
+            let event = AppEvent::CrewsEvent(CrewsEvent::RoleAssigneesChanged(change));
+            realtime_state
+                .broadcast_app_event(Some(auth_session), event.clone())
+                .await;
+            (StatusCode::OK, Json(vec![event])).into_response()
+        }
+        Err(sqlx::Error::RowNotFound) => (StatusCode::NOT_FOUND, ()).into_response(),
+        Err(sqlx::Error::InvalidArgument(message)) => {
+            (StatusCode::BAD_REQUEST, message).into_response()
+        }
         Err(_) => (StatusCode::INTERNAL_SERVER_ERROR, ()).into_response(),
     }
 }
@@ -1,46 +1,439 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
-use sqlx::SqlitePool;
+use chrono::NaiveDate;
+use sqlx::{SqliteConnection, SqlitePool};
 
 use crate::shared::{
-    entities::{CollectiveId, Crew, CrewId, CrewInvolvement, CrewWithLinks, IntervalId},
-    links_repo::{find_all_links_for_owner_type, hash_links_by_owner, update_links_for_owner},
+    entities::{
+        CollectiveId, ConvenorStrategy, Crew, CrewId, CrewInvolvement, CrewMembership, CrewRole,
+        CrewRoleInvolvement, CrewWithLinks, IntervalId, Link, PersonId,
+    },
+    links_repo::{
+        find_all_links_for_owner, find_all_links_for_owner_type, hash_links_by_owner,
+        update_links_for_owner,
+    },
 };
 
+impl From<CrewWithLinks> for Crew {
+    fn from(crew: CrewWithLinks) -> Self {
+        Crew {
+            id: crew.id,
+            name: crew.name,
+            description: crew.description,
+            collective_id: crew.collective_id,
+            archived: crew.archived,
+            min_members: crew.min_members,
+            max_members: crew.max_members,
+            needs_convenor: crew.needs_convenor,
+            convenor_strategy: crew.convenor_strategy,
+            allow_co_convenors: crew.allow_co_convenors,
+            requires_approval: crew.requires_approval,
+            parent_crew_id: crew.parent_crew_id,
+        }
+    }
+}
+
+fn crew_with_links(
+    crew: Crew,
+    links: Option<Vec<Link>>,
+    roles: Vec<CrewRole>,
+    sub_crew_ids: Vec<i64>,
+) -> CrewWithLinks {
+    CrewWithLinks {
+        id: crew.id,
+        name: crew.name,
+        description: crew.description,
+        collective_id: crew.collective_id,
+        links,
+        archived: crew.archived,
+        min_members: crew.min_members,
+        max_members: crew.max_members,
+        needs_convenor: crew.needs_convenor,
+        convenor_strategy: crew.convenor_strategy,
+        allow_co_convenors: crew.allow_co_convenors,
+        roles,
+        requires_approval: crew.requires_approval,
+        parent_crew_id: crew.parent_crew_id,
+        sub_crew_ids,
+    }
+}
+
+// Orders crews as a tree, with each crew followed by its sub-crews
+fn in_tree_order(crews: Vec<Crew>) -> Vec<Crew> {
+    fn add_with_sub_crews(crew: Crew, remaining: &mut Vec<Crew>, ordered: &mut Vec<Crew>) {
+        let crew_id = crew.id;
+        ordered.push(crew);
+
+        while let Some(index) = remaining
+            .iter()
+            .position(|sub_crew| sub_crew.parent_crew_id == Some(crew_id))
+        {
+            let sub_crew = remaining.remove(index);
+            add_with_sub_crews(sub_crew, remaining, ordered);
+        }
+    }
+
+    let crew_ids: Vec<i64> = crews.iter().map(|crew| crew.id).collect();
+    // Crews whose parent isn't listed, such as an archived one, are treated as top level
+    let (top_level, mut remaining): (Vec<Crew>, Vec<Crew>) = crews.into_iter().partition(|crew| {
+        crew.parent_crew_id
+            .is_none_or(|parent_crew_id| !crew_ids.contains(&parent_crew_id))
+    });
+
+    let mut ordered = Vec::new();
+    for crew in top_level {
+        add_with_sub_crews(crew, &mut remaining, &mut ordered);
+    }
+    ordered
+}
+
 pub async fn find_all_crews_with_links(
     collective_id: CollectiveId,
+    interval_ids: &[IntervalId],
     pool: &SqlitePool,
 ) -> Result<Vec<CrewWithLinks>, sqlx::Error> {
-    let crews = find_all_crews(collective_id, pool).await?;
+    let crews = in_tree_order(find_all_crews(collective_id.clone(), interval_ids, pool).await?);
+    let parent_crew_ids: Vec<(i64, Option<i64>)> = crews
+        .iter()
+        .map(|crew| (crew.id, crew.parent_crew_id))
+        .collect();
 
     let links = find_all_links_for_owner_type("crews".to_string(), pool).await?;
     let links_hash = hash_links_by_owner(links);
+    let roles = find_all_crew_roles(collective_id.clone(), pool).await?;
 
     let crews: Vec<CrewWithLinks> = crews
         .into_iter()
-        .map(|crew| CrewWithLinks {
-            id: crew.id,
-            name: crew.name,
-            description: crew.description,
-            collective_id: crew.collective_id,
-            links: Some(links_hash.get(&crew.id).cloned().unwrap_or_else(Vec::new)),
+        .map(|crew| {
+            let links = Some(links_hash.get(&crew.id).cloned().unwrap_or_else(Vec::new));
+            let crew_roles = roles
+                .iter()
+                .filter(|role| role.crew_id == crew.id)
+                .cloned()
+                .collect();
+            let sub_crew_ids = parent_crew_ids
+                .iter()
+                .filter(|(_, parent_crew_id)| *parent_crew_id == Some(crew.id))
+                .map(|(sub_crew_id, _)| *sub_crew_id)
+                .collect();
+            crew_with_links(crew, links, crew_roles, sub_crew_ids)
         })
         .collect();
 
     Ok(crews)
 }
 
+// Archived crews are only included when someone is involved in them in one of the intervals
+// being viewed
 pub async fn find_all_crews(
     collective_id: CollectiveId,
+    interval_ids: &[IntervalId],
     pool: &SqlitePool,
 ) -> Result<Vec<Crew>, sqlx::Error> {
+    let crews = sqlx::query_as!(
+        Crew,
+        "SELECT id, name, description, collective_id, archived, min_members, max_members, needs_convenor,
+            convenor_strategy as \"convenor_strategy: ConvenorStrategy\", allow_co_convenors,
+            requires_approval, parent_crew_id
+        FROM crews
+        WHERE collective_id = ?",
+        collective_id.id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let mut involved_crew_ids = HashSet::new();
+    for interval_id in interval_ids {
+        involved_crew_ids.extend(find_involved_crew_ids(interval_id.clone(), pool).await?);
+    }
+
+    Ok(crews
+        .into_iter()
+        .filter(|crew| !crew.archived || involved_crew_ids.contains(&crew.id))
+        .collect())
+}
+
+async fn find_involved_crew_ids(
+    interval_id: IntervalId,
+    pool: &SqlitePool,
+) -> Result<Vec<i64>, sqlx::Error> {
+    sqlx::query_scalar!(
+        "SELECT DISTINCT crew_id FROM crew_involvements WHERE interval_id = ?",
+        interval_id.id
+    )
+    .fetch_all(pool)
+    .await
+}
+
+pub async fn find_crew(
+    collective_id: CollectiveId,
+    crew_id: CrewId,
+    pool: &SqlitePool,
+) -> Result<Crew, sqlx::Error> {
     sqlx::query_as!(
         Crew,
-        "SELECT id, name, description, collective_id FROM crews WHERE collective_id = ?",
+        "SELECT id, name, description, collective_id, archived, min_members, max_members, needs_convenor,
+            convenor_strategy as \"convenor_strategy: ConvenorStrategy\", allow_co_convenors,
+            requires_approval, parent_crew_id
+        FROM crews
+        WHERE id = ? AND collective_id = ?",
+        crew_id.id,
         collective_id.id
     )
+    .fetch_one(pool)
+    .await
+}
+
+pub async fn find_crew_with_links(
+    collective_id: CollectiveId,
+    crew_id: CrewId,
+    pool: &SqlitePool,
+) -> Result<CrewWithLinks, sqlx::Error> {
+    let crew = find_crew(collective_id, crew_id.clone(), pool).await?;
+    let links = find_all_links_for_owner(crew_id.id, "crews".to_string(), pool).await?;
+    let roles = find_crew_roles(crew_id.clone(), pool).await?;
+    let sub_crew_ids = find_sub_crew_ids(crew_id, pool).await?;
+
+    Ok(crew_with_links(crew, Some(links), roles, sub_crew_ids))
+}
+
+async fn find_sub_crew_ids(crew_id: CrewId, pool: &SqlitePool) -> Result<Vec<i64>, sqlx::Error> {
+    sqlx::query!("SELECT id FROM crews WHERE parent_crew_id = ?", crew_id.id)
+        .fetch_all(pool)
+        .await
+        .map(|rows| rows.into_iter().map(|row| row.id).collect())
+}
+
+// The crew itself, then its parent, its parent's parent, and so on
+pub async fn find_crew_lineage_ids(
+    crew_id: CrewId,
+    pool: &SqlitePool,
+) -> Result<Vec<i64>, sqlx::Error> {
+    sqlx::query!(
+        "WITH RECURSIVE lineage(id, parent_crew_id, depth) AS (
+            SELECT id, parent_crew_id, 0 FROM crews WHERE id = ?
+            UNION
+            SELECT crews.id, crews.parent_crew_id, lineage.depth + 1
+            FROM crews JOIN lineage ON crews.id = lineage.parent_crew_id
+        )
+        SELECT id as \"id!: i64\" FROM lineage ORDER BY depth",
+        crew_id.id
+    )
     .fetch_all(pool)
     .await
+    .map(|rows| rows.into_iter().map(|row| row.id).collect())
+}
+
+// A parent crew has to be another crew in the collective, and not one of the crew's own
+// sub-crews
+pub async fn validate_parent_crew(
+    collective_id: CollectiveId,
+    crew_id: Option<CrewId>,
+    parent_crew_id: Option<i64>,
+    pool: &SqlitePool,
+) -> Result<(), sqlx::Error> {
+    let Some(parent_crew_id) = parent_crew_id else {
+        return Ok(());
+    };
+
+    match find_crew(collective_id, CrewId::new(parent_crew_id), pool).await {
+        Ok(_) => {}
+        Err(sqlx::Error::RowNotFound) => {
+            return Err(sqlx::Error::InvalidArgument(
+                "Parent crew not found".to_string(),
+            ));
+        }
+        Err(error) => return Err(error),
+    }
+
+    if let Some(crew_id) = crew_id {
+        let lineage = find_crew_lineage_ids(CrewId::new(parent_crew_id), pool).await?;
+        if lineage.contains(&crew_id.id) {
+            return Err(sqlx::Error::InvalidArgument(
+                "A crew can't be its own parent or sit under its own sub-crews".to_string(),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+// Staffing limits can't be negative, and the minimum can't be more than the maximum
+pub fn validate_staffing_limits(
+    min_members: Option<i64>,
+    max_members: Option<i64>,
+) -> Result<(), sqlx::Error> {
+    if min_members.is_some_and(|min| min < 0) || max_members.is_some_and(|max| max < 0) {
+        return Err(sqlx::Error::InvalidArgument(
+            "Minimum and maximum members can't be negative".to_string(),
+        ));
+    }
+
+    if let (Some(min), Some(max)) = (min_members, max_members)
+        && min > max
+    {
+        return Err(sqlx::Error::InvalidArgument(
+            "Minimum members can't be more than the maximum".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+// Whether someone convenes the crew, or any crew it sits under, in the interval
+pub async fn convenes_crew_or_parent(
+    crew_id: CrewId,
+    interval_id: IntervalId,
+    person_id: PersonId,
+    pool: &SqlitePool,
+) -> Result<bool, sqlx::Error> {
+    sqlx::query!(
+        "WITH RECURSIVE lineage(id, parent_crew_id) AS (
+            SELECT id, parent_crew_id FROM crews WHERE id = ?
+            UNION
+            SELECT crews.id, crews.parent_crew_id
+            FROM crews JOIN lineage ON crews.id = lineage.parent_crew_id
+        )
+        SELECT EXISTS (
+            SELECT 1 FROM crew_involvements
+            WHERE crew_id IN (SELECT id FROM lineage)
+            AND interval_id = ? AND person_id = ?
+            AND convenor = TRUE AND membership = 'Member'
+        ) as \"convenes!: bool\"",
+        crew_id.id,
+        interval_id.id,
+        person_id.id
+    )
+    .fetch_one(pool)
+    .await
+    .map(|row| row.convenes)
+}
+
+pub async fn insert_crew_with_links(
+    collective_id: CollectiveId,
+    crew: CrewWithLinks,
+    pool: &SqlitePool,
+) -> Result<CrewWithLinks, sqlx::Error> {
+    validate_staffing_limits(crew.min_members, crew.max_members)?;
+    validate_parent_crew(collective_id.clone(), None, crew.parent_crew_id, pool).await?;
+
+    let result = sqlx::query!(
+        "INSERT INTO crews
+            (name, description, collective_id, min_members, max_members, needs_convenor,
+            convenor_strategy, allow_co_convenors, requires_approval, parent_crew_id)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        crew.name,
+        crew.description,
+        collective_id.id,
+        crew.min_members,
+        crew.max_members,
+        crew.needs_convenor,
+        crew.convenor_strategy,
+        crew.allow_co_convenors,
+        crew.requires_approval,
+        crew.parent_crew_id
+    )
+    .execute(pool)
+    .await?;
+    let crew_id = CrewId::new(result.last_insert_rowid());
+
+    update_links_for_owner(crew_id.id, "crews".to_string(), crew.links, pool).await?;
+
+    find_crew_with_links(collective_id, crew_id, pool).await
+}
+
+pub async fn set_crew_archived(
+    collective_id: CollectiveId,
+    crew_id: CrewId,
+    archived: bool,
+    pool: &SqlitePool,
+) -> Result<CrewWithLinks, sqlx::Error> {
+    sqlx::query!(
+        "UPDATE crews SET archived = ? WHERE id = ? AND collective_id = ?",
+        archived,
+        crew_id.id,
+        collective_id.id
+    )
+    .execute(pool)
+    .await?;
+
+    find_crew_with_links(collective_id, crew_id, pool).await
+}
+
+pub async fn find_archived_crew_ids(pool: &SqlitePool) -> Result<Vec<CrewId>, sqlx::Error> {
+    sqlx::query_as!(CrewId, "SELECT id FROM crews WHERE archived = TRUE")
+        .fetch_all(pool)
+        .await
+}
+
+#[derive(PartialEq, Debug)]
+pub enum CrewDeletion {
+    Deleted,
+    // Crews that anyone has ever been involved in can only be archived
+    HasInvolvements,
+}
+
+pub async fn delete_crew(
+    collective_id: CollectiveId,
+    crew_id: CrewId,
+    pool: &SqlitePool,
+) -> Result<CrewDeletion, sqlx::Error> {
+    let mut transaction = pool.begin().await?;
+
+    // Checked in the same transaction, so nobody can join the crew in between
+    let involvement_count = sqlx::query_scalar!(
+        "SELECT COUNT(*) FROM crew_involvements WHERE crew_id = ?",
+        crew_id.id
+    )
+    .fetch_one(&mut *transaction)
+    .await?;
+    if involvement_count > 0 {
+        return Ok(CrewDeletion::HasInvolvements);
+    }
+
+    sqlx::query!(
+        "DELETE FROM links WHERE owner_id = ? AND owner_type = 'crews'",
+        crew_id.id
+    )
+    .execute(&mut *transaction)
+    .await?;
+
+    sqlx::query!(
+        "DELETE FROM crew_role_assignments
+        WHERE crew_role_id IN (SELECT id FROM crew_roles WHERE crew_id = ?)",
+        crew_id.id
+    )
+    .execute(&mut *transaction)
+    .await?;
+
+    sqlx::query!("DELETE FROM crew_roles WHERE crew_id = ?", crew_id.id)
+        .execute(&mut *transaction)
+        .await?;
+
+    // Without a care crew, only admins can see what's kept for care
+    sqlx::query!(
+        "UPDATE collectives SET care_crew_id = NULL WHERE id = ? AND care_crew_id = ?",
+        collective_id.id,
+        crew_id.id
+    )
+    .execute(&mut *transaction)
+    .await?;
+
+    let result = sqlx::query!(
+        "DELETE FROM crews WHERE id = ? AND collective_id = ?",
+        crew_id.id,
+        collective_id.id
+    )
+    .execute(&mut *transaction)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(sqlx::Error::RowNotFound);
+    }
+
+    transaction.commit().await?;
+
+    Ok(CrewDeletion::Deleted)
 }
 
 pub async fn update_crew(
@@ -50,9 +443,20 @@ pub async fn update_crew(
 ) -> Result<Crew, sqlx::Error> {
     sqlx::query_as!(
         Crew,
-        "UPDATE crews SET name = ?, description = ? WHERE id = ? AND collective_id = ? ",
+        "UPDATE crews
+        SET name = ?, description = ?, min_members = ?, max_members = ?, needs_convenor = ?,
+            convenor_strategy = ?, allow_co_convenors = ?, requires_approval = ?,
+            parent_crew_id = ?
+        WHERE id = ? AND collective_id = ? ",
         crew.name,
         crew.description,
+        crew.min_members,
+        crew.max_members,
+        crew.needs_convenor,
+        crew.convenor_strategy,
+        crew.allow_co_convenors,
+        crew.requires_approval,
+        crew.parent_crew_id,
         crew.id,
         collective_id.id
     )
@@ -67,27 +471,75 @@ pub async fn update_crew_with_links(
     crew: CrewWithLinks,
     pool: &SqlitePool,
 ) -> Result<CrewWithLinks, sqlx::Error> {
-    let crew_result = update_crew(
-        collective_id,
-        Crew {
-            id: crew.id,
-            name: crew.name,
-            description: crew.description,
-            collective_id: crew.collective_id,
-        },
+    let crew_id = CrewId::new(crew.id);
+    let links = crew.links.clone();
+
+    validate_staffing_limits(crew.min_members, crew.max_members)?;
+    validate_parent_crew(
+        collective_id.clone(),
+        Some(crew_id.clone()),
+        crew.parent_crew_id,
         pool,
     )
     .await?;
 
-    let links = update_links_for_owner(crew.id, "crews".to_string(), crew.links, pool).await?;
+    update_crew(collective_id.clone(), crew.into(), pool).await?;
+    let links = update_links_for_owner(crew_id.id, "crews".to_string(), links, pool).await?;
 
-    Ok(CrewWithLinks {
-        id: crew_result.id,
-        name: crew_result.name,
-        description: crew_result.description,
-        collective_id: crew_result.collective_id,
-        links,
-    })
+    // Re-read the crew, as fields like archived aren't changed by an update
+    let crew_result = find_crew(collective_id, crew_id.clone(), pool).await?;
+    let roles = find_crew_roles(crew_id.clone(), pool).await?;
+    let sub_crew_ids = find_sub_crew_ids(crew_id, pool).await?;
+
+    Ok(crew_with_links(crew_result, links, roles, sub_crew_ids))
+}
+
+// Crew involvements as stored, before their roles are added
+pub struct CrewInvolvementRecord {
+    pub id: i64,
+    pub person_id: i64,
+    pub crew_id: i64,
+    pub interval_id: i64,
+    pub convenor: bool,
+    pub volunteered_convenor: bool,
+    pub membership: CrewMembership,
+}
+
+// Adds each involvement's roles. All the records must be for the same interval
+pub async fn with_roles(
+    records: Vec<CrewInvolvementRecord>,
+    interval_id: IntervalId,
+    pool: &SqlitePool,
+) -> Result<Vec<CrewInvolvement>, sqlx::Error> {
+    let assignments = find_role_assignments_for_interval(interval_id, pool).await?;
+
+    Ok(records
+        .into_iter()
+        .map(|record| {
+            let roles = assignments
+                .iter()
+                .filter(|assignment| {
+                    assignment.crew_id == record.crew_id && assignment.person_id == record.person_id
+                })
+                .map(|assignment| CrewRoleInvolvement {
+                    crew_role_id: assignment.crew_role_id,
+                    assigned: assignment.assigned,
+                    volunteered: assignment.volunteered,
+                })
+                .collect();
+
+            CrewInvolvement {
+                id: record.id,
+                person_id: record.person_id,
+                crew_id: record.crew_id,
+                interval_id: record.interval_id,
+                convenor: record.convenor,
+                volunteered_convenor: record.volunteered_convenor,
+                roles,
+                membership: record.membership,
+            }
+        })
+        .collect())
 }
 
 pub async fn find_crew_involvements(
@@ -95,33 +547,39 @@ pub async fn find_crew_involvements(
     interval_id: IntervalId,
     pool: &SqlitePool,
 ) -> Result<Vec<CrewInvolvement>, sqlx::Error> {
-    sqlx::query_as!(
-        CrewInvolvement,
-        "SELECT id, person_id, crew_id, interval_id, convenor, volunteered_convenor
+    let records = sqlx::query_as!(
+        CrewInvolvementRecord,
+        "SELECT id, person_id, crew_id, interval_id, convenor, volunteered_convenor,
+            membership as \"membership: CrewMembership\"
         FROM crew_involvements
         WHERE crew_id = ? AND interval_id = ?",
         crew_id.id,
         interval_id.id
     )
     .fetch_all(pool)
-    .await
+    .await?;
+
+    with_roles(records, interval_id, pool).await
 }
 
-pub async fn set_crew_convenor(
+// Replaces the crew's convenors for the interval. assigned_by is the person who chose
+// them by hand, or None when they were chosen automatically
+pub async fn set_crew_convenors(
     crew_id: CrewId,
     interval_id: IntervalId,
-    person_id: Option<i64>,
+    person_ids: Vec<i64>,
+    assigned_by: Option<i64>,
     pool: &SqlitePool,
 ) -> Result<(), sqlx::Error> {
     println!(
-        "Setting crew {} convenor for interval {} to person {:?}",
-        crew_id.id, interval_id.id, person_id
+        "Setting crew {} convenors for interval {} to people {:?}",
+        crew_id.id, interval_id.id, person_ids
     );
 
     let mut transaction = pool.begin().await?;
 
     sqlx::query!(
-        "UPDATE crew_involvements SET convenor = FALSE
+        "UPDATE crew_involvements SET convenor = FALSE, convenor_assigned_by = NULL
         WHERE crew_id = ? AND interval_id = ?",
         crew_id.id,
         interval_id.id
@@ -129,10 +587,11 @@ pub async fn set_crew_convenor(
     .execute(&mut *transaction)
     .await?;
 
-    if person_id.is_some() {
+    for person_id in person_ids {
         sqlx::query!(
-            "UPDATE crew_involvements SET convenor = TRUE
+            "UPDATE crew_involvements SET convenor = TRUE, convenor_assigned_by = ?
         WHERE crew_id = ? AND interval_id = ? AND person_id = ?",
+            assigned_by,
             crew_id.id,
             interval_id.id,
             person_id
@@ -146,6 +605,70 @@ pub async fn set_crew_convenor(
     Ok(())
 }
 
+pub async fn has_manually_assigned_convenor(
+    crew_id: CrewId,
+    interval_id: IntervalId,
+    pool: &SqlitePool,
+) -> Result<bool, sqlx::Error> {
+    sqlx::query!(
+        "SELECT EXISTS(
+            SELECT 1 FROM crew_involvements
+            WHERE crew_id = ? AND interval_id = ? AND convenor = TRUE
+            AND convenor_assigned_by IS NOT NULL
+        ) as \"assigned!: bool\"",
+        crew_id.id,
+        interval_id.id
+    )
+    .fetch_one(pool)
+    .await
+    .map(|row| row.assigned)
+}
+
+// Stepping down also withdraws the person's offer to convene, so they aren't reselected
+// Leaves any other convenors as they are
+pub async fn add_crew_convenor(
+    crew_id: CrewId,
+    interval_id: IntervalId,
+    person_id: i64,
+    pool: &SqlitePool,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        "UPDATE crew_involvements SET convenor = TRUE, convenor_assigned_by = NULL
+        WHERE crew_id = ? AND interval_id = ? AND person_id = ?",
+        crew_id.id,
+        interval_id.id,
+        person_id
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn step_down_as_convenor(
+    crew_id: CrewId,
+    interval_id: IntervalId,
+    person_id: PersonId,
+    pool: &SqlitePool,
+) -> Result<(), sqlx::Error> {
+    let result = sqlx::query!(
+        "UPDATE crew_involvements
+        SET convenor = FALSE, volunteered_convenor = FALSE, convenor_assigned_by = NULL
+        WHERE crew_id = ? AND interval_id = ? AND person_id = ? AND convenor = TRUE",
+        crew_id.id,
+        interval_id.id,
+        person_id.id
+    )
+    .execute(pool)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(sqlx::Error::RowNotFound);
+    }
+
+    Ok(())
+}
+
 pub async fn intervals_participated_since_last_convened(
     crew_id: CrewId,
     before_interval_id: IntervalId,
@@ -153,22 +676,24 @@ pub async fn intervals_participated_since_last_convened(
 ) -> Result<HashMap<i64, i64>, sqlx::Error> {
     let result = sqlx::query!(
         "
-        SELECT crew_involvements.person_id, COUNT(interval_id) as \"count: i64\"
+        SELECT crew_involvements.person_id, COUNT(crew_involvements.interval_id) as \"count: i64\"
         FROM crew_involvements
+        INNER JOIN intervals ON intervals.id = crew_involvements.interval_id
         LEFT JOIN (
-            SELECT person_id, MAX(interval_id) as last_convened
-            FROM crew_involvements
+            SELECT convened.person_id, MAX(convened_intervals.start_date) as last_convened
+            FROM crew_involvements convened
+            INNER JOIN intervals convened_intervals ON convened_intervals.id = convened.interval_id
             WHERE
-                crew_id = ? AND
-                convenor = TRUE AND
-                interval_id <= ?
-            GROUP BY person_id
+                convened.crew_id = ? AND
+                convened.convenor = TRUE AND
+                convened_intervals.start_date <= (SELECT start_date FROM intervals WHERE id = ?)
+            GROUP BY convened.person_id
         ) i ON crew_involvements.person_id = i.person_id
         WHERE
-            interval_id > last_convened AND
+            intervals.start_date > last_convened AND
             convenor = FALSE AND
             crew_id = ? AND
-            interval_id <= ?
+            intervals.start_date <= (SELECT start_date FROM intervals WHERE id = ?)
         GROUP BY crew_involvements.person_id
         ",
         crew_id.id,
@@ -188,3 +713,491 @@ pub async fn intervals_participated_since_last_convened(
 
     Ok(map)
 }
+
+pub struct CrewMemberRecord {
+    pub crew_id: i64,
+    pub person_id: i64,
+    pub convenor: bool,
+}
+
+// Anyone whose status on the date takes them away from participating is left out. Their
+// involvement's own status holds until the first change part way through the interval
+pub async fn find_crew_members(
+    interval_id: IntervalId,
+    on_date: &str,
+    pool: &SqlitePool,
+) -> Result<Vec<CrewMemberRecord>, sqlx::Error> {
+    sqlx::query_as!(
+        CrewMemberRecord,
+        "WITH statuses_on_date AS (
+            SELECT
+                collective_involvements.person_id,
+                COALESCE(
+                    (
+                        SELECT involvement_status_segments.status
+                        FROM involvement_status_segments
+                        WHERE involvement_status_segments.involvement_id = collective_involvements.id
+                            AND involvement_status_segments.effective_from <= ?
+                        ORDER BY involvement_status_segments.effective_from DESC
+                        LIMIT 1
+                    ),
+                    collective_involvements.status
+                ) as status
+            FROM collective_involvements
+            WHERE collective_involvements.interval_id = ?
+        )
+        SELECT crew_id, person_id, convenor
+        FROM crew_involvements
+        WHERE interval_id = ? AND membership = 'Member'
+            AND person_id NOT IN (
+                SELECT person_id FROM statuses_on_date WHERE status != 'Participating'
+            )",
+        on_date,
+        interval_id.id,
+        interval_id.id
+    )
+    .fetch_all(pool)
+    .await
+}
+
+// The sequence of the last interval, before this one, in which each person convened the crew
+pub async fn find_last_convened_sequences(
+    crew_id: CrewId,
+    before_interval_id: IntervalId,
+    pool: &SqlitePool,
+) -> Result<HashMap<i64, i64>, sqlx::Error> {
+    let result = sqlx::query!(
+        "SELECT crew_involvements.person_id as \"person_id!: i64\", MAX(intervals.sequence) as \"sequence!: i64\"
+        FROM crew_involvements
+        INNER JOIN intervals ON intervals.id = crew_involvements.interval_id
+        WHERE
+            crew_involvements.crew_id = ? AND
+            crew_involvements.convenor = TRUE AND
+            intervals.start_date < (SELECT start_date FROM intervals WHERE id = ?)
+        GROUP BY crew_involvements.person_id",
+        crew_id.id,
+        before_interval_id.id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(result
+        .into_iter()
+        .map(|row| (row.person_id, row.sequence))
+        .collect())
+}
+
+pub struct ConvenorRotationRecord {
+    pub crew_id: i64,
+    pub interval_id: i64,
+    pub person_id: i64,
+    pub convenor: bool,
+    pub volunteered_convenor: bool,
+}
+
+// Crew involvements for every interval that has started, oldest interval first
+pub async fn find_convenor_rotation_records(
+    collective_id: CollectiveId,
+    today: NaiveDate,
+    pool: &SqlitePool,
+) -> Result<Vec<ConvenorRotationRecord>, sqlx::Error> {
+    let today = today.format("%Y-%m-%d").to_string();
+    sqlx::query_as!(
+        ConvenorRotationRecord,
+        "SELECT
+            crew_involvements.crew_id,
+            crew_involvements.interval_id,
+            crew_involvements.person_id,
+            crew_involvements.convenor,
+            crew_involvements.volunteered_convenor
+        FROM crew_involvements
+        INNER JOIN intervals ON intervals.id = crew_involvements.interval_id
+        INNER JOIN crews ON crews.id = crew_involvements.crew_id
+        WHERE
+            crews.collective_id = ? AND
+            intervals.start_date <= ? AND
+            crew_involvements.membership = 'Member'
+        ORDER BY intervals.start_date, crew_involvements.crew_id, crew_involvements.person_id",
+        collective_id.id,
+        today
+    )
+    .fetch_all(pool)
+    .await
+}
+
+pub async fn find_all_crew_roles(
+    collective_id: CollectiveId,
+    pool: &SqlitePool,
+) -> Result<Vec<CrewRole>, sqlx::Error> {
+    sqlx::query_as!(
+        CrewRole,
+        "SELECT crew_roles.id, crew_roles.crew_id, crew_roles.name,
+            crew_roles.strategy as \"strategy: ConvenorStrategy\"
+        FROM crew_roles
+        INNER JOIN crews ON crews.id = crew_roles.crew_id
+        WHERE crews.collective_id = ?
+        ORDER BY crew_roles.id",
+        collective_id.id
+    )
+    .fetch_all(pool)
+    .await
+}
+
+pub async fn find_crew_roles(
+    crew_id: CrewId,
+    pool: &SqlitePool,
+) -> Result<Vec<CrewRole>, sqlx::Error> {
+    sqlx::query_as!(
+        CrewRole,
+        "SELECT id, crew_id, name, strategy as \"strategy: ConvenorStrategy\"
+        FROM crew_roles
+        WHERE crew_id = ?
+        ORDER BY id",
+        crew_id.id
+    )
+    .fetch_all(pool)
+    .await
+}
+
+pub async fn insert_crew_role(
+    crew_id: CrewId,
+    name: String,
+    strategy: ConvenorStrategy,
+    pool: &SqlitePool,
+) -> Result<CrewRole, sqlx::Error> {
+    sqlx::query_as!(
+        CrewRole,
+        "INSERT INTO crew_roles (crew_id, name, strategy) VALUES (?, ?, ?)
+        RETURNING id, crew_id, name, strategy as \"strategy: ConvenorStrategy\"",
+        crew_id.id,
+        name,
+        strategy
+    )
+    .fetch_one(pool)
+    .await
+}
+
+pub async fn update_crew_role(
+    crew_id: CrewId,
+    role_id: i64,
+    name: String,
+    strategy: ConvenorStrategy,
+    pool: &SqlitePool,
+) -> Result<CrewRole, sqlx::Error> {
+    sqlx::query_as!(
+        CrewRole,
+        "UPDATE crew_roles SET name = ?, strategy = ?
+        WHERE id = ? AND crew_id = ?
+        RETURNING id, crew_id, name, strategy as \"strategy: ConvenorStrategy\"",
+        name,
+        strategy,
+        role_id,
+        crew_id.id
+    )
+    .fetch_one(pool)
+    .await
+}
+
+pub async fn delete_crew_role(
+    crew_id: CrewId,
+    role_id: i64,
+    pool: &SqlitePool,
+) -> Result<(), sqlx::Error> {
+    let mut transaction = pool.begin().await?;
+
+    sqlx::query!(
+        "DELETE FROM crew_role_assignments
+        WHERE crew_role_id = (SELECT id FROM crew_roles WHERE id = ? AND crew_id = ?)",
+        role_id,
+        crew_id.id
+    )
+    .execute(&mut *transaction)
+    .await?;
+
+    let result = sqlx::query!(
+        "DELETE FROM crew_roles WHERE id = ? AND crew_id = ?",
+        role_id,
+        crew_id.id
+    )
+    .execute(&mut *transaction)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(sqlx::Error::RowNotFound);
+    }
+
+    transaction.commit().await?;
+
+    Ok(())
+}
+
+pub struct CrewRoleAssignmentRecord {
+    pub crew_role_id: i64,
+    pub crew_id: i64,
+    pub person_id: i64,
+    pub assigned: bool,
+    pub volunteered: bool,
+}
+
+pub async fn find_role_assignments_for_interval(
+    interval_id: IntervalId,
+    pool: &SqlitePool,
+) -> Result<Vec<CrewRoleAssignmentRecord>, sqlx::Error> {
+    sqlx::query_as!(
+        CrewRoleAssignmentRecord,
+        "SELECT crew_role_assignments.crew_role_id, crew_roles.crew_id,
+            crew_role_assignments.person_id, crew_role_assignments.assigned,
+            crew_role_assignments.volunteered
+        FROM crew_role_assignments
+        INNER JOIN crew_roles ON crew_roles.id = crew_role_assignments.crew_role_id
+        WHERE crew_role_assignments.interval_id = ?",
+        interval_id.id
+    )
+    .fetch_all(pool)
+    .await
+}
+
+// Saves which of a crew's roles the person volunteered for. Whether they've been assigned
+// a role is left as it was
+pub async fn update_role_volunteering(
+    person_id: PersonId,
+    crew_id: CrewId,
+    interval_id: IntervalId,
+    roles: Vec<CrewRoleInvolvement>,
+    connection: &mut SqliteConnection,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        "UPDATE crew_role_assignments SET volunteered = FALSE
+        WHERE person_id = ? AND interval_id = ?
+        AND crew_role_id IN (SELECT id FROM crew_roles WHERE crew_id = ?)",
+        person_id.id,
+        interval_id.id,
+        crew_id.id
+    )
+    .execute(&mut *connection)
+    .await?;
+
+    // Roles that don't belong to the crew are ignored
+    for role in roles {
+        sqlx::query!(
+            "INSERT INTO crew_role_assignments (crew_role_id, person_id, interval_id, volunteered)
+            SELECT id, ?, ?, ? FROM crew_roles WHERE id = ? AND crew_id = ?
+            ON CONFLICT (crew_role_id, person_id, interval_id) DO UPDATE SET
+                volunteered = excluded.volunteered",
+            person_id.id,
+            interval_id.id,
+            role.volunteered,
+            role.crew_role_id,
+            crew_id.id
+        )
+        .execute(&mut *connection)
+        .await?;
+    }
+
+    Ok(())
+}
+
+pub async fn delete_role_assignments(
+    person_id: PersonId,
+    crew_id: CrewId,
+    interval_id: IntervalId,
+    connection: &mut SqliteConnection,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        "DELETE FROM crew_role_assignments
+        WHERE person_id = ? AND interval_id = ?
+        AND crew_role_id IN (SELECT id FROM crew_roles WHERE crew_id = ?)",
+        person_id.id,
+        interval_id.id,
+        crew_id.id
+    )
+    .execute(connection)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn set_role_assignees(
+    role_id: i64,
+    interval_id: IntervalId,
+    person_ids: Vec<i64>,
+    pool: &SqlitePool,
+) -> Result<(), sqlx::Error> {
+    println!(
+        "Setting role {} for interval {} to people {:?}",
+        role_id, interval_id.id, person_ids
+    );
+
+    let mut transaction = pool.begin().await?;
+
+    sqlx::query!(
+        "UPDATE crew_role_assignments SET assigned = FALSE
+        WHERE crew_role_id = ? AND interval_id = ?",
+        role_id,
+        interval_id.id
+    )
+    .execute(&mut *transaction)
+    .await?;
+
+    for person_id in person_ids {
+        sqlx::query!(
+            "INSERT INTO crew_role_assignments (crew_role_id, person_id, interval_id, assigned)
+            VALUES (?, ?, ?, TRUE)
+            ON CONFLICT (crew_role_id, person_id, interval_id) DO UPDATE SET assigned = TRUE",
+            role_id,
+            person_id,
+            interval_id.id
+        )
+        .execute(&mut *transaction)
+        .await?;
+    }
+
+    transaction.commit().await?;
+
+    Ok(())
+}
+
+// How many intervals each person has been in the crew since they last held the role, or
+// ever if they never have
+pub async fn intervals_in_crew_since_last_held_role(
+    role: &CrewRole,
+    before_interval_id: IntervalId,
+    pool: &SqlitePool,
+) -> Result<HashMap<i64, i64>, sqlx::Error> {
+    let result = sqlx::query!(
+        "SELECT crew_involvements.person_id as \"person_id!: i64\", COUNT(*) as \"count!: i64\"
+        FROM crew_involvements
+        INNER JOIN intervals ON intervals.id = crew_involvements.interval_id
+        LEFT JOIN (
+            SELECT held.person_id, MAX(held_intervals.start_date) as last_held
+            FROM crew_role_assignments held
+            INNER JOIN intervals held_intervals ON held_intervals.id = held.interval_id
+            WHERE
+                held.crew_role_id = ? AND
+                held.assigned = TRUE AND
+                held_intervals.start_date < (SELECT start_date FROM intervals WHERE id = ?)
+            GROUP BY held.person_id
+        ) i ON crew_involvements.person_id = i.person_id
+        WHERE
+            crew_involvements.crew_id = ? AND
+            intervals.start_date < (SELECT start_date FROM intervals WHERE id = ?) AND
+            (i.last_held IS NULL OR intervals.start_date > i.last_held)
+        GROUP BY crew_involvements.person_id",
+        role.id,
+        before_interval_id.id,
+        role.crew_id,
+        before_interval_id.id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(result
+        .into_iter()
+        .map(|row| (row.person_id, row.count))
+        .collect())
+}
+
+pub async fn find_last_held_role_sequences(
+    role_id: i64,
+    before_interval_id: IntervalId,
+    pool: &SqlitePool,
+) -> Result<HashMap<i64, i64>, sqlx::Error> {
+    let result = sqlx::query!(
+        "SELECT crew_role_assignments.person_id as \"person_id!: i64\",
+            MAX(intervals.sequence) as \"sequence!: i64\"
+        FROM crew_role_assignments
+        INNER JOIN intervals ON intervals.id = crew_role_assignments.interval_id
+        WHERE
+            crew_role_assignments.crew_role_id = ? AND
+            crew_role_assignments.assigned = TRUE AND
+            intervals.start_date < (SELECT start_date FROM intervals WHERE id = ?)
+        GROUP BY crew_role_assignments.person_id",
+        role_id,
+        before_interval_id.id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(result
+        .into_iter()
+        .map(|row| (row.person_id, row.sequence))
+        .collect())
+}
+
+pub async fn find_crew_ids_requiring_approval(
+    pool: &SqlitePool,
+) -> Result<Vec<CrewId>, sqlx::Error> {
+    sqlx::query_as!(
+        CrewId,
+        "SELECT id FROM crews WHERE requires_approval = TRUE"
+    )
+    .fetch_all(pool)
+    .await
+}
+
+// Crews the person has been a full member of in any interval, so they don't need approval
+// to join again
+pub async fn find_member_crew_ids_for_person(
+    person_id: PersonId,
+    pool: &SqlitePool,
+) -> Result<Vec<CrewId>, sqlx::Error> {
+    sqlx::query_as!(
+        CrewId,
+        "SELECT DISTINCT crew_id as id FROM crew_involvements
+        WHERE person_id = ? AND membership = 'Member'",
+        person_id.id
+    )
+    .fetch_all(pool)
+    .await
+}
+
+pub async fn approve_crew_member(
+    crew_id: CrewId,
+    interval_id: IntervalId,
+    person_id: PersonId,
+    pool: &SqlitePool,
+) -> Result<(), sqlx::Error> {
+    let result = sqlx::query!(
+        "UPDATE crew_involvements SET membership = 'Member'
+        WHERE crew_id = ? AND interval_id = ? AND person_id = ? AND membership = 'Pending'",
+        crew_id.id,
+        interval_id.id,
+        person_id.id
+    )
+    .execute(pool)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(sqlx::Error::RowNotFound);
+    }
+
+    Ok(())
+}
+
+pub async fn decline_crew_member(
+    crew_id: CrewId,
+    interval_id: IntervalId,
+    person_id: PersonId,
+    pool: &SqlitePool,
+) -> Result<(), sqlx::Error> {
+    let mut transaction = pool.begin().await?;
+
+    let result = sqlx::query!(
+        "DELETE FROM crew_involvements
+        WHERE crew_id = ? AND interval_id = ? AND person_id = ? AND membership = 'Pending'",
+        crew_id.id,
+        interval_id.id,
+        person_id.id
+    )
+    .execute(&mut *transaction)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(sqlx::Error::RowNotFound);
+    }
+
+    delete_role_assignments(person_id, crew_id, interval_id, &mut transaction).await?;
+    transaction.commit().await?;
+
+    Ok(())
+}
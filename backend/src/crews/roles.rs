@@ -0,0 +1,161 @@
+use sqlx::SqlitePool;
+
+use crate::{
+    crews::{
+        convenor_selection::{ConvenorCandidate, selector_for},
+        events::RoleAssigneeChange,
+        repo::{
+            find_crew_involvements, find_crew_roles, find_last_held_role_sequences,
+            intervals_in_crew_since_last_held_role, set_role_assignees,
+        },
+    },
+    my_collective::involvements_repo::find_all_collective_involvements,
+    shared::entities::{
+        CollectiveId, ConvenorStrategy, CrewId, CrewInvolvement, CrewMembership, CrewRole,
+        IntervalId, IntervalPhase, PersonId,
+    },
+};
+
+// Rotates each of the crew's roles between the people who volunteered for it, using the
+// same strategies as convenors. Manual only roles are left alone
+pub async fn update_role_assignees_if_needed(
+    collective_id: CollectiveId,
+    crew_id: CrewId,
+    interval_id: IntervalId,
+    phase: IntervalPhase,
+    pool: &SqlitePool,
+) -> Result<(), sqlx::Error> {
+    let roles = find_crew_roles(crew_id.clone(), pool).await?;
+    if roles
+        .iter()
+        .all(|role| role.strategy == ConvenorStrategy::ManualOnly)
+    {
+        return Ok(());
+    }
+
+    let crew_involvements = find_crew_involvements(crew_id, interval_id.clone(), pool).await?;
+    let planning_locked = matches!(phase, IntervalPhase::Locked | IntervalPhase::Active);
+
+    for role in roles {
+        let selector = selector_for(role.strategy);
+        let current_assignees = role_assignee_ids(&crew_involvements, role.id);
+
+        if (planning_locked || selector.keeps_existing_convenor()) && !current_assignees.is_empty()
+        {
+            continue;
+        }
+
+        let volunteered: Vec<i64> = crew_involvements
+            .iter()
+            .filter(|involvement| involvement.membership == CrewMembership::Member)
+            .filter(|involvement| {
+                involvement
+                    .roles
+                    .iter()
+                    .any(|r| r.crew_role_id == role.id && r.volunteered)
+            })
+            .map(|involvement| involvement.person_id)
+            .collect();
+        let candidates = find_role_candidates(
+            collective_id.clone(),
+            &role,
+            interval_id.clone(),
+            volunteered,
+            pool,
+        )
+        .await?;
+        let best: Vec<i64> = selector.select(&candidates).into_iter().collect();
+
+        if best != current_assignees {
+            set_role_assignees(role.id, interval_id.clone(), best, pool).await?;
+        }
+    }
+
+    Ok(())
+}
+
+// Assigns a role by hand. Everyone assigned must be in the crew for the interval
+pub async fn assign_role(
+    crew_id: CrewId,
+    role_id: i64,
+    interval_id: IntervalId,
+    person_ids: Vec<i64>,
+    assigned_by: PersonId,
+    pool: &SqlitePool,
+) -> Result<RoleAssigneeChange, sqlx::Error> {
+    let roles = find_crew_roles(crew_id.clone(), pool).await?;
+    if !roles.iter().any(|role| role.id == role_id) {
+        return Err(sqlx::Error::RowNotFound);
+    }
+
+    let crew_involvements =
+        find_crew_involvements(crew_id.clone(), interval_id.clone(), pool).await?;
+    for person_id in &person_ids {
+        if !crew_involvements.iter().any(|involvement| {
+            involvement.person_id == *person_id && involvement.membership == CrewMembership::Member
+        }) {
+            return Err(sqlx::Error::InvalidArgument(format!(
+                "Person {} isn't in crew {} for interval {}",
+                person_id, crew_id.id, interval_id.id
+            )));
+        }
+    }
+
+    set_role_assignees(role_id, interval_id.clone(), person_ids.clone(), pool).await?;
+
+    Ok(RoleAssigneeChange {
+        crew_id: crew_id.id,
+        crew_role_id: role_id,
+        interval_id: interval_id.id,
+        previous_assignee_ids: role_assignee_ids(&crew_involvements, role_id),
+        assignee_ids: person_ids,
+        changed_by: assigned_by.id,
+    })
+}
+
+fn role_assignee_ids(crew_involvements: &[CrewInvolvement], role_id: i64) -> Vec<i64> {
+    crew_involvements
+        .iter()
+        .filter(|involvement| {
+            involvement
+                .roles
+                .iter()
+                .any(|role| role.crew_role_id == role_id && role.assigned)
+        })
+        .map(|involvement| involvement.person_id)
+        .collect()
+}
+
+async fn find_role_candidates(
+    collective_id: CollectiveId,
+    role: &CrewRole,
+    interval_id: IntervalId,
+    volunteered: Vec<i64>,
+    pool: &SqlitePool,
+) -> Result<Vec<ConvenorCandidate>, sqlx::Error> {
+    if volunteered.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let intervals_since_held =
+        intervals_in_crew_since_last_held_role(role, interval_id.clone(), pool).await?;
+    let last_held_sequences =
+        find_last_held_role_sequences(role.id, interval_id.clone(), pool).await?;
+    let collective_involvements =
+        find_all_collective_involvements(collective_id, interval_id, pool).await?;
+
+    let candidates = volunteered
+        .into_iter()
+        .map(|person_id| ConvenorCandidate {
+            person_id,
+            intervals_since_convened: intervals_since_held.get(&person_id).cloned().unwrap_or(0),
+            last_convened_sequence: last_held_sequences.get(&person_id).cloned(),
+            capacity_score: collective_involvements
+                .iter()
+                .find(|collective_involvement| collective_involvement.person_id == person_id)
+                .and_then(|collective_involvement| collective_involvement.capacity_score),
+        })
+        .collect();
+
+    Ok(candidates)
+}
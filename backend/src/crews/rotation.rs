@@ -0,0 +1,129 @@
+use std::collections::BTreeMap;
+
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use utoipa::ToSchema;
+
+use crate::{
+    crews::repo::{ConvenorRotationRecord, find_convenor_rotation_records},
+    shared::entities::CollectiveId,
+};
+
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone)]
+pub struct IntervalConvenors {
+    pub interval_id: i64,
+    pub convenor_ids: Vec<i64>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone, Default)]
+pub struct PersonRotation {
+    pub person_id: i64,
+    pub times_convened: i64,
+    // None if they've never convened
+    pub intervals_since_last_convened: Option<i64>,
+    pub times_volunteered_not_chosen: i64,
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone)]
+pub struct CrewRotation {
+    pub crew_id: i64,
+    // Oldest interval first
+    pub intervals: Vec<IntervalConvenors>,
+    pub people: Vec<PersonRotation>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone)]
+pub struct RotationReport {
+    pub crews: Vec<CrewRotation>,
+    // Totals across every crew, to spot people carrying too much
+    pub people: Vec<PersonRotation>,
+}
+
+pub async fn find_rotation_report(
+    collective_id: CollectiveId,
+    today: NaiveDate,
+    pool: &SqlitePool,
+) -> Result<RotationReport, sqlx::Error> {
+    let records = find_convenor_rotation_records(collective_id, today, pool).await?;
+
+    Ok(build_rotation_report(records))
+}
+
+// Records must be ordered oldest interval first
+fn build_rotation_report(records: Vec<ConvenorRotationRecord>) -> RotationReport {
+    let mut records_by_crew: BTreeMap<i64, Vec<ConvenorRotationRecord>> = BTreeMap::new();
+    for record in records {
+        records_by_crew
+            .entry(record.crew_id)
+            .or_default()
+            .push(record);
+    }
+
+    let crews: Vec<CrewRotation> = records_by_crew
+        .into_iter()
+        .map(|(crew_id, records)| crew_rotation(crew_id, records))
+        .collect();
+
+    let mut totals: BTreeMap<i64, PersonRotation> = BTreeMap::new();
+    for person in crews.iter().flat_map(|crew| crew.people.iter()) {
+        let total = totals.entry(person.person_id).or_insert(PersonRotation {
+            person_id: person.person_id,
+            ..Default::default()
+        });
+        total.times_convened += person.times_convened;
+        total.times_volunteered_not_chosen += person.times_volunteered_not_chosen;
+        total.intervals_since_last_convened = match (
+            total.intervals_since_last_convened,
+            person.intervals_since_last_convened,
+        ) {
+            (Some(total_since), Some(since)) => Some(total_since.min(since)),
+            (total_since, since) => total_since.or(since),
+        };
+    }
+
+    RotationReport {
+        crews,
+        people: totals.into_values().collect(),
+    }
+}
+
+fn crew_rotation(crew_id: i64, records: Vec<ConvenorRotationRecord>) -> CrewRotation {
+    let mut intervals: Vec<IntervalConvenors> = vec![];
+    let mut people: BTreeMap<i64, PersonRotation> = BTreeMap::new();
+
+    for record in records {
+        if intervals.last().map(|interval| interval.interval_id) != Some(record.interval_id) {
+            intervals.push(IntervalConvenors {
+                interval_id: record.interval_id,
+                convenor_ids: vec![],
+            });
+        }
+
+        let person = people.entry(record.person_id).or_insert(PersonRotation {
+            person_id: record.person_id,
+            ..Default::default()
+        });
+
+        if record.convenor {
+            if let Some(interval) = intervals.last_mut() {
+                interval.convenor_ids.push(record.person_id);
+            }
+            person.times_convened += 1;
+            person.intervals_since_last_convened = Some(0);
+        } else {
+            // Only intervals they were in the crew for count towards their turn
+            person.intervals_since_last_convened =
+                person.intervals_since_last_convened.map(|since| since + 1);
+            if record.volunteered_convenor {
+                person.times_volunteered_not_chosen += 1;
+            }
+        }
+    }
+
+    CrewRotation {
+        crew_id,
+        intervals,
+        people: people.into_values().collect(),
+    }
+}
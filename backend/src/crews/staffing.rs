@@ -0,0 +1,139 @@
+use std::collections::HashSet;
+
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use utoipa::ToSchema;
+
+use crate::{
+    crews::repo::{CrewMemberRecord, find_all_crews, find_crew_members},
+    intervals::{
+        clock::{Clock, collective_today},
+        repo::{find_interval, planning_date},
+    },
+    shared::entities::{CollectiveId, Crew, IntervalId},
+};
+
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone)]
+pub struct CrewStaffing {
+    pub crew_id: i64,
+    pub parent_crew_id: Option<i64>,
+    pub member_count: i64,
+    // Everyone in the crew or any of its sub-crews, counting each person once
+    pub rolled_up_member_count: i64,
+    pub min_members: Option<i64>,
+    pub max_members: Option<i64>,
+    pub has_convenor: bool,
+    pub needs_convenor: bool,
+}
+
+impl CrewStaffing {
+    // A parent crew is staffed by the people in its sub-crews too
+    pub fn is_understaffed(&self) -> bool {
+        self.min_members
+            .is_some_and(|min| self.rolled_up_member_count < min)
+    }
+
+    pub fn is_overfull(&self) -> bool {
+        self.max_members
+            .is_some_and(|max| self.rolled_up_member_count > max)
+    }
+
+    pub fn is_missing_convenor(&self) -> bool {
+        self.needs_convenor && !self.has_convenor
+    }
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone)]
+pub struct StaffingReport {
+    pub interval_id: i64,
+    pub crews: Vec<CrewStaffing>,
+    pub understaffed_crew_ids: Vec<i64>,
+    pub overfull_crew_ids: Vec<i64>,
+    pub convenorless_crew_ids: Vec<i64>,
+}
+
+pub async fn find_staffing_report(
+    collective_id: CollectiveId,
+    interval_id: IntervalId,
+    clock: &impl Clock,
+    pool: &SqlitePool,
+) -> Result<StaffingReport, sqlx::Error> {
+    let interval = find_interval(interval_id.clone(), pool).await?;
+    let today = collective_today(collective_id.clone(), clock, pool).await?;
+    let on_date = planning_date(&interval, today);
+
+    let crews = find_all_crews(collective_id, std::slice::from_ref(&interval_id), pool).await?;
+    let members = find_crew_members(interval_id.clone(), &on_date, pool).await?;
+
+    Ok(build_staffing_report(interval_id, crews, members))
+}
+
+// The crew's own ID and those of all the crews under it
+fn crew_and_sub_crew_ids(crew_id: i64, crews: &[Crew]) -> HashSet<i64> {
+    let mut crew_ids = HashSet::from([crew_id]);
+    let mut to_visit = vec![crew_id];
+
+    while let Some(parent_crew_id) = to_visit.pop() {
+        for crew in crews {
+            if crew.parent_crew_id == Some(parent_crew_id) && crew_ids.insert(crew.id) {
+                to_visit.push(crew.id);
+            }
+        }
+    }
+
+    crew_ids
+}
+
+fn build_staffing_report(
+    interval_id: IntervalId,
+    crews: Vec<Crew>,
+    members: Vec<CrewMemberRecord>,
+) -> StaffingReport {
+    let crew_members = |crew_id: i64| {
+        members
+            .iter()
+            .filter(move |member| member.crew_id == crew_id)
+    };
+
+    let rolled_up_member_count = |crew_id: i64| {
+        let crew_ids = crew_and_sub_crew_ids(crew_id, &crews);
+        members
+            .iter()
+            .filter(|member| crew_ids.contains(&member.crew_id))
+            .map(|member| member.person_id)
+            .collect::<HashSet<i64>>()
+            .len() as i64
+    };
+
+    let staffing: Vec<CrewStaffing> = crews
+        .iter()
+        // Archived crews only matter if people are still involved in them this interval
+        .filter(|crew| !crew.archived || crew_members(crew.id).next().is_some())
+        .map(|crew| CrewStaffing {
+            crew_id: crew.id,
+            parent_crew_id: crew.parent_crew_id,
+            member_count: crew_members(crew.id).count() as i64,
+            rolled_up_member_count: rolled_up_member_count(crew.id),
+            min_members: crew.min_members,
+            max_members: crew.max_members,
+            has_convenor: crew_members(crew.id).any(|member| member.convenor),
+            needs_convenor: crew.needs_convenor,
+        })
+        .collect();
+
+    let crew_ids_where = |check: fn(&CrewStaffing) -> bool| {
+        staffing
+            .iter()
+            .filter(|staffing| check(staffing))
+            .map(|staffing| staffing.crew_id)
+            .collect()
+    };
+
+    StaffingReport {
+        interval_id: interval_id.id,
+        understaffed_crew_ids: crew_ids_where(CrewStaffing::is_understaffed),
+        overfull_crew_ids: crew_ids_where(CrewStaffing::is_overfull),
+        convenorless_crew_ids: crew_ids_where(CrewStaffing::is_missing_convenor),
+        crews: staffing,
+    }
+}
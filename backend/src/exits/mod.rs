@@ -0,0 +1,75 @@
+use axum::{Extension, Json, http::StatusCode, response::IntoResponse};
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use utoipa::ToSchema;
+use utoipa_axum::{router::OpenApiRouter, routes};
+
+use crate::{
+    auth::{auth_backend::AuthSession, permissions::require_admin},
+    shared::default_collective_id,
+};
+
+pub mod repo;
+pub mod workflow;
+
+pub fn router() -> OpenApiRouter {
+    OpenApiRouter::new().routes(routes!(get_checklist_template, update_checklist_template))
+}
+
+// Each item becomes a handover task for someone who exits
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone)]
+pub struct ExitChecklistItem {
+    pub title: String,
+    pub description: Option<String>,
+}
+
+#[utoipa::path(get, path = "/checklist",
+    responses(
+        (status = 200, body = Vec<ExitChecklistItem>),
+        (status = UNAUTHORIZED, description = "Not logged in", body = ()),
+        (status = INTERNAL_SERVER_ERROR, description = "Internal server error", body = ()),
+    ),
+)]
+pub async fn get_checklist_template(
+    Extension(pool): Extension<SqlitePool>,
+    auth_session: AuthSession,
+) -> impl IntoResponse {
+    if auth_session.user.is_none() {
+        return (StatusCode::UNAUTHORIZED, ()).into_response();
+    }
+
+    match repo::find_checklist_template(default_collective_id(), &pool).await {
+        Ok(items) => (StatusCode::OK, Json(items)).into_response(),
+        Err(_) => (StatusCode::INTERNAL_SERVER_ERROR, ()).into_response(),
+    }
+}
+
+#[utoipa::path(put, path = "/checklist",
+    request_body(content = Vec<ExitChecklistItem>, content_type = "application/json"),
+    responses(
+        (status = 200, body = Vec<ExitChecklistItem>),
+        (status = BAD_REQUEST, description = "Every item needs a title", body = ()),
+        (status = FORBIDDEN, description = "Only admins can change the exit checklist", body = ()),
+        (status = INTERNAL_SERVER_ERROR, description = "Internal server error", body = ()),
+    ),
+)]
+pub async fn update_checklist_template(
+    Extension(pool): Extension<SqlitePool>,
+    auth_session: AuthSession,
+    Json(input): Json<Vec<ExitChecklistItem>>,
+) -> impl IntoResponse {
+    if let Err(status) = require_admin(&auth_session, default_collective_id(), &pool).await {
+        return (status, ()).into_response();
+    }
+
+    if input.iter().any(|item| item.title.trim().is_empty()) {
+        return (StatusCode::BAD_REQUEST, "Every item needs a title").into_response();
+    }
+
+    println!("Updating exit checklist: {:?}", input);
+
+    match repo::replace_checklist_template(default_collective_id(), input, &pool).await {
+        Ok(items) => (StatusCode::OK, Json(items)).into_response(),
+        Err(_) => (StatusCode::INTERNAL_SERVER_ERROR, ()).into_response(),
+    }
+}
@@ -0,0 +1,239 @@
+use sqlx::SqlitePool;
+
+use crate::shared::entities::{CollectiveId, CrewId, IntervalId, PersonId};
+
+use super::ExitChecklistItem;
+
+pub async fn find_checklist_template(
+    collective_id: CollectiveId,
+    pool: &SqlitePool,
+) -> Result<Vec<ExitChecklistItem>, sqlx::Error> {
+    sqlx::query_as!(
+        ExitChecklistItem,
+        "SELECT title, description
+        FROM exit_checklist_items
+        WHERE collective_id = ?
+        ORDER BY position",
+        collective_id.id
+    )
+    .fetch_all(pool)
+    .await
+}
+
+pub async fn replace_checklist_template(
+    collective_id: CollectiveId,
+    items: Vec<ExitChecklistItem>,
+    pool: &SqlitePool,
+) -> Result<Vec<ExitChecklistItem>, sqlx::Error> {
+    let mut transaction = pool.begin().await?;
+
+    sqlx::query!(
+        "DELETE FROM exit_checklist_items WHERE collective_id = ?",
+        collective_id.id
+    )
+    .execute(&mut *transaction)
+    .await?;
+
+    for (position, item) in items.iter().enumerate() {
+        let position = position as i64;
+        sqlx::query!(
+            "INSERT INTO exit_checklist_items (collective_id, title, description, position)
+            VALUES (?, ?, ?, ?)",
+            collective_id.id,
+            item.title,
+            item.description,
+            position
+        )
+        .execute(&mut *transaction)
+        .await?;
+    }
+
+    transaction.commit().await?;
+
+    find_checklist_template(collective_id, pool).await
+}
+
+// Records the exit, returning false if it had already been started for the interval
+pub async fn insert_exit(
+    collective_id: CollectiveId,
+    person_id: PersonId,
+    interval_id: IntervalId,
+    started_on: String,
+    disable_on: String,
+    pool: &SqlitePool,
+) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query!(
+        "INSERT INTO exits (collective_id, person_id, interval_id, started_on, disable_on)
+        VALUES (?, ?, ?, ?, ?)
+        ON CONFLICT (person_id, interval_id) DO NOTHING",
+        collective_id.id,
+        person_id.id,
+        interval_id.id,
+        started_on,
+        disable_on
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+// Someone who changes their mind before their account is disabled is no longer exiting
+pub async fn delete_pending_exit(
+    person_id: PersonId,
+    pool: &SqlitePool,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        "DELETE FROM exits WHERE person_id = ? AND disabled = FALSE",
+        person_id.id
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+pub struct DueExit {
+    pub id: i64,
+    pub person_id: i64,
+}
+
+// Exits whose grace period is over, but whose account is still enabled
+pub async fn find_due_exits(
+    collective_id: CollectiveId,
+    today: String,
+    pool: &SqlitePool,
+) -> Result<Vec<DueExit>, sqlx::Error> {
+    sqlx::query_as!(
+        DueExit,
+        "SELECT id, person_id
+        FROM exits
+        WHERE collective_id = ? AND disabled = FALSE AND disable_on <= ?",
+        collective_id.id,
+        today
+    )
+    .fetch_all(pool)
+    .await
+}
+
+// Disables the person's account and revokes their calendar feeds, leaving everything they've
+// done in place
+pub async fn disable_exited_account(
+    exit_id: i64,
+    person_id: PersonId,
+    pool: &SqlitePool,
+) -> Result<(), sqlx::Error> {
+    let mut transaction = pool.begin().await?;
+
+    sqlx::query!(
+        "UPDATE users SET disabled = TRUE
+        WHERE id = (SELECT user_id FROM people WHERE id = ?)",
+        person_id.id
+    )
+    .execute(&mut *transaction)
+    .await?;
+
+    sqlx::query!(
+        "UPDATE calendar_feeds SET revoked_at = datetime('now')
+        WHERE person_id = ? AND revoked_at IS NULL",
+        person_id.id
+    )
+    .execute(&mut *transaction)
+    .await?;
+
+    sqlx::query!("UPDATE exits SET disabled = TRUE WHERE id = ?", exit_id)
+        .execute(&mut *transaction)
+        .await?;
+
+    transaction.commit().await?;
+
+    Ok(())
+}
+
+pub struct LaterCrewInvolvement {
+    pub crew_id: i64,
+    pub interval_id: i64,
+    pub convenor: bool,
+}
+
+// The person's crews in every interval starting after the given one
+pub async fn find_later_crew_involvements(
+    person_id: PersonId,
+    interval_id: IntervalId,
+    pool: &SqlitePool,
+) -> Result<Vec<LaterCrewInvolvement>, sqlx::Error> {
+    sqlx::query_as!(
+        LaterCrewInvolvement,
+        "SELECT crew_involvements.crew_id, crew_involvements.interval_id, crew_involvements.convenor
+        FROM crew_involvements
+        INNER JOIN intervals ON intervals.id = crew_involvements.interval_id
+        WHERE
+            crew_involvements.person_id = ? AND
+            intervals.start_date > (SELECT start_date FROM intervals WHERE id = ?)",
+        person_id.id,
+        interval_id.id
+    )
+    .fetch_all(pool)
+    .await
+}
+
+pub async fn delete_crew_involvement(
+    person_id: PersonId,
+    crew_id: CrewId,
+    interval_id: IntervalId,
+    pool: &SqlitePool,
+) -> Result<(), sqlx::Error> {
+    let mut transaction = pool.begin().await?;
+
+    sqlx::query!(
+        "DELETE FROM crew_role_assignments
+        WHERE person_id = ? AND interval_id = ?
+        AND crew_role_id IN (SELECT id FROM crew_roles WHERE crew_id = ?)",
+        person_id.id,
+        interval_id.id,
+        crew_id.id
+    )
+    .execute(&mut *transaction)
+    .await?;
+
+    sqlx::query!(
+        "DELETE FROM crew_involvements WHERE person_id = ? AND crew_id = ? AND interval_id = ?",
+        person_id.id,
+        crew_id.id,
+        interval_id.id
+    )
+    .execute(&mut *transaction)
+    .await?;
+
+    transaction.commit().await?;
+
+    Ok(())
+}
+
+// Stops the person convening any crew in the interval, returning the crews they convened
+pub async fn step_down_from_all_crews(
+    person_id: PersonId,
+    interval_id: IntervalId,
+    pool: &SqlitePool,
+) -> Result<Vec<CrewId>, sqlx::Error> {
+    let crew_ids = sqlx::query_as!(
+        CrewId,
+        "SELECT crew_id as id FROM crew_involvements
+        WHERE person_id = ? AND interval_id = ? AND convenor = TRUE",
+        person_id.id,
+        interval_id.id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    sqlx::query!(
+        "UPDATE crew_involvements SET convenor = FALSE, volunteered_convenor = FALSE
+        WHERE person_id = ? AND interval_id = ?",
+        person_id.id,
+        interval_id.id
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(crew_ids)
+}
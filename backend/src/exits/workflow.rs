@@ -0,0 +1,214 @@
+use chrono::{Days, NaiveDate};
+use resend_rs::Resend;
+use sqlx::SqlitePool;
+
+use crate::{
+    crews::{
+        convenor_selection::{selects_on_update, update_convenor_if_needed},
+        events::ConvenorChange,
+    },
+    exits::repo::{
+        delete_crew_involvement, delete_pending_exit, disable_exited_account,
+        find_checklist_template, find_due_exits, find_later_crew_involvements, insert_exit,
+        step_down_from_all_crews,
+    },
+    intervals::{
+        clock::SystemClock,
+        repo::{find_interval, parse_date_only},
+    },
+    my_collective::repo::find_collective,
+    notifications::{Notification, notify_people, repo::find_admin_person_ids},
+    people::repo::find_person_by_id,
+    shared::entities::{
+        CollectiveId, CrewId, Interval, IntervalId, PersonId, Task, TaskRecurrence, TaskStatus,
+    },
+    tasks::repo::{find_assigned_task_by_title, insert_task},
+};
+
+// What starting someone's exit changed
+pub struct ExitStarted {
+    pub person_id: i64,
+    pub disable_on: String,
+    pub convenor_changes: Vec<ConvenorChange>,
+    pub handover_tasks: Vec<Task>,
+    // Intervals the person was removed from crews in
+    pub later_interval_ids: Vec<i64>,
+}
+
+// Starts someone's exit from the interval: they leave their crews in later intervals, stop
+// convening so new convenors can be chosen, and get a handover checklist to work through
+// before their account is disabled. Returns None if their exit had already been started
+pub async fn start_exit(
+    collective_id: CollectiveId,
+    person_id: PersonId,
+    interval: &Interval,
+    today: NaiveDate,
+    pool: &SqlitePool,
+) -> Result<Option<ExitStarted>, sqlx::Error> {
+    let collective = find_collective(collective_id.clone(), pool).await?;
+    // Someone leaving after a later interval is still taking part until it starts
+    let exit_from = parse_date_only(&interval.start_date)
+        .filter(|start_date| *start_date > today)
+        .unwrap_or(today);
+    let disable_on = exit_from
+        .checked_add_days(Days::new(collective.exit_grace_period_days.max(0) as u64))
+        .unwrap_or(exit_from)
+        .format("%Y-%m-%d")
+        .to_string();
+
+    let started = insert_exit(
+        collective_id.clone(),
+        person_id.clone(),
+        interval.typed_id(),
+        today.format("%Y-%m-%d").to_string(),
+        disable_on.clone(),
+        pool,
+    )
+    .await?;
+    if !started {
+        return Ok(None);
+    }
+
+    println!(
+        "Starting exit for person {} from interval {}",
+        person_id.id, interval.id
+    );
+
+    // Crews that need a new convenor, and the interval they need one in
+    let mut convenorless: Vec<(CrewId, Interval)> =
+        step_down_from_all_crews(person_id.clone(), interval.typed_id(), pool)
+            .await?
+            .into_iter()
+            .map(|crew_id| (crew_id, interval.clone()))
+            .collect();
+
+    let mut later_interval_ids = vec![];
+    for involvement in
+        find_later_crew_involvements(person_id.clone(), interval.typed_id(), pool).await?
+    {
+        let crew_id = CrewId::new(involvement.crew_id);
+        let interval_id = IntervalId::new(involvement.interval_id);
+        delete_crew_involvement(
+            person_id.clone(),
+            crew_id.clone(),
+            interval_id.clone(),
+            pool,
+        )
+        .await?;
+
+        if involvement.convenor {
+            convenorless.push((crew_id, find_interval(interval_id, pool).await?));
+        }
+        if !later_interval_ids.contains(&involvement.interval_id) {
+            later_interval_ids.push(involvement.interval_id);
+        }
+    }
+
+    // Crews in intervals that only choose convenors on lock are left until then
+    let mut convenor_changes = vec![];
+    for (crew_id, crew_interval) in convenorless {
+        if !selects_on_update(collective.convenor_selection_timing, crew_interval.phase) {
+            continue;
+        }
+        let change = update_convenor_if_needed(
+            collective_id.clone(),
+            crew_id,
+            crew_interval.typed_id(),
+            crew_interval.phase,
+            person_id.id,
+            &SystemClock,
+            pool,
+        )
+        .await?;
+        convenor_changes.extend(change);
+    }
+
+    // Someone who changes their mind and exits again keeps the tasks they were first given
+    let mut handover_tasks = vec![];
+    for item in find_checklist_template(collective_id.clone(), pool).await? {
+        let existing = find_assigned_task_by_title(
+            collective_id.clone(),
+            person_id.clone(),
+            interval.typed_id(),
+            &item.title,
+            pool,
+        )
+        .await?;
+        if let Some(existing) = existing {
+            handover_tasks.push(existing);
+            continue;
+        }
+
+        let task = Task {
+            id: -1, // ID will be auto-generated
+            collective_id: collective_id.id,
+            crew_id: None,
+            title: item.title,
+            description: item.description,
+            assignee_id: Some(person_id.id),
+            due_date: Some(disable_on.clone()),
+            status: TaskStatus::Open,
+            recurrence: TaskRecurrence::Once,
+            interval_id: Some(interval.id),
+        };
+        handover_tasks.push(insert_task(collective_id.clone(), task, pool).await?);
+    }
+
+    Ok(Some(ExitStarted {
+        person_id: person_id.id,
+        disable_on,
+        convenor_changes,
+        handover_tasks,
+        later_interval_ids,
+    }))
+}
+
+// Called whenever someone saves a status other than exiting, in case they've changed their mind.
+// Their exit is cancelled whichever interval it was started from
+pub async fn cancel_exit(person_id: PersonId, pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    delete_pending_exit(person_id, pool).await
+}
+
+pub async fn notify_exit_started(
+    collective_id: CollectiveId,
+    exit: &ExitStarted,
+    resend: &Resend,
+    pool: &SqlitePool,
+) -> Result<(), sqlx::Error> {
+    let person =
+        find_person_by_id(PersonId::new(exit.person_id), collective_id.clone(), pool).await?;
+    let admins = find_admin_person_ids(collective_id, pool).await?;
+
+    let notification = Notification {
+        subject: format!("{} is exiting", person.display_name),
+        message: format!(
+            "{} has chosen to exit. They've been taken out of their crews in later intervals, new convenors have been chosen where needed, and they have {} handover tasks to finish. Their account will be disabled on {}.",
+            person.display_name,
+            exit.handover_tasks.len(),
+            exit.disable_on
+        ),
+    };
+    notify_people(admins, &notification, resend, pool).await;
+
+    Ok(())
+}
+
+// Disables the accounts of everyone whose grace period is over, returning who was disabled
+pub async fn disable_exited_accounts(
+    collective_id: CollectiveId,
+    today: NaiveDate,
+    pool: &SqlitePool,
+) -> Result<Vec<PersonId>, sqlx::Error> {
+    let due_exits =
+        find_due_exits(collective_id, today.format("%Y-%m-%d").to_string(), pool).await?;
+
+    let mut disabled = vec![];
+    for exit in due_exits {
+        println!("Disabling account for exited person {}", exit.person_id);
+        let person_id = PersonId::new(exit.person_id);
+        disable_exited_account(exit.id, person_id.clone(), pool).await?;
+        disabled.push(person_id);
+    }
+
+    Ok(disabled)
+}
@@ -0,0 +1,126 @@
+use axum::{Extension, Json, http::StatusCode, response::IntoResponse};
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use utoipa::ToSchema;
+use utoipa_axum::{router::OpenApiRouter, routes};
+
+use crate::{
+    auth::auth_backend::AuthSession,
+    hiatus::repo::{find_hiatuses_in_interval, has_collective_involvement},
+    intervals::{
+        clock::{SystemClock, collective_today},
+        repo::{find_current_interval, parse_date_only},
+    },
+    shared::{
+        default_collective_id,
+        entities::{CollectiveId, PersonId},
+    },
+};
+
+pub mod repo;
+pub mod returns;
+
+pub fn router() -> OpenApiRouter {
+    OpenApiRouter::new().routes(routes!(get_hiatus_report))
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone)]
+pub struct PersonOnHiatus {
+    pub person_id: i64,
+    pub planned_return_date: Option<String>,
+    // The interval containing the return date, once it's been created
+    pub return_interval_id: Option<i64>,
+    // Whether they've been drafted back into the return interval yet
+    pub return_drafted: bool,
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone)]
+pub struct HiatusReport {
+    pub interval_id: i64,
+    // Soonest expected back first, with those who haven't said last
+    pub people: Vec<PersonOnHiatus>,
+}
+
+#[utoipa::path(get, path = "/",
+    responses(
+        (status = 200, body = HiatusReport),
+        (status = UNAUTHORIZED, description = "Not logged in", body = ()),
+        (status = NOT_FOUND, description = "No current interval", body = ()),
+        (status = INTERNAL_SERVER_ERROR, description = "Internal server error", body = ()),
+    ),
+)]
+pub async fn get_hiatus_report(
+    Extension(pool): Extension<SqlitePool>,
+    auth_session: AuthSession,
+) -> impl IntoResponse {
+    if auth_session.user.is_none() {
+        return (StatusCode::UNAUTHORIZED, ()).into_response();
+    }
+
+    match find_hiatus_report(default_collective_id(), &pool).await {
+        Ok(report) => (StatusCode::OK, Json(report)).into_response(),
+        Err(sqlx::Error::RowNotFound) => (StatusCode::NOT_FOUND, ()).into_response(),
+        Err(_) => (StatusCode::INTERNAL_SERVER_ERROR, ()).into_response(),
+    }
+}
+
+// Everyone on hiatus in the current interval, and when they're expected back
+pub async fn find_hiatus_report(
+    collective_id: CollectiveId,
+    pool: &SqlitePool,
+) -> Result<HiatusReport, sqlx::Error> {
+    let today = collective_today(collective_id.clone(), &SystemClock, pool).await?;
+    let interval = find_current_interval(collective_id.clone(), today, pool).await?;
+    let hiatuses =
+        find_hiatuses_in_interval(collective_id.clone(), interval.typed_id(), pool).await?;
+
+    let mut people = vec![];
+    for hiatus in hiatuses {
+        let return_date = hiatus
+            .opt_out_planned_return_date
+            .as_deref()
+            .and_then(parse_date_only);
+        let return_interval = match return_date {
+            Some(return_date) => {
+                match find_current_interval(collective_id.clone(), return_date, pool).await {
+                    Ok(interval) => Some(interval),
+                    Err(sqlx::Error::RowNotFound) => None,
+                    Err(error) => return Err(error),
+                }
+            }
+            None => None,
+        };
+        let return_drafted = match &return_interval {
+            Some(return_interval) => {
+                has_collective_involvement(
+                    PersonId::new(hiatus.person_id),
+                    return_interval.typed_id(),
+                    pool,
+                )
+                .await?
+            }
+            None => false,
+        };
+
+        people.push(PersonOnHiatus {
+            person_id: hiatus.person_id,
+            planned_return_date: hiatus.opt_out_planned_return_date,
+            return_interval_id: return_interval.map(|interval| interval.id),
+            return_drafted,
+        });
+    }
+    // Dates are stored as YYYY-MM-DD, so sort correctly as strings
+    people.sort_by(
+        |a, b| match (&a.planned_return_date, &b.planned_return_date) {
+            (Some(a), Some(b)) => a.cmp(b),
+            (Some(_), None) => std::cmp::Ordering::Less,
+            (None, Some(_)) => std::cmp::Ordering::Greater,
+            (None, None) => std::cmp::Ordering::Equal,
+        },
+    );
+
+    Ok(HiatusReport {
+        interval_id: interval.id,
+        people,
+    })
+}
@@ -0,0 +1,99 @@
+use sqlx::SqlitePool;
+
+use crate::shared::entities::{CollectiveId, IntervalId, PersonId};
+
+pub struct HiatusRecord {
+    pub person_id: i64,
+    pub interval_id: i64,
+    pub opt_out_planned_return_date: Option<String>,
+}
+
+// People whose most recent involvement is a hiatus with a planned return date
+pub async fn find_hiatuses_with_return_dates(
+    collective_id: CollectiveId,
+    pool: &SqlitePool,
+) -> Result<Vec<HiatusRecord>, sqlx::Error> {
+    sqlx::query_as!(
+        HiatusRecord,
+        "SELECT person_id, interval_id, opt_out_planned_return_date
+        FROM collective_involvements
+        INNER JOIN intervals ON intervals.id = collective_involvements.interval_id
+        WHERE
+            collective_involvements.collective_id = ? AND
+            collective_involvements.status = 'OnHiatus' AND
+            collective_involvements.opt_out_planned_return_date IS NOT NULL AND
+            NOT EXISTS (
+                SELECT 1 FROM collective_involvements AS later
+                INNER JOIN intervals AS later_intervals ON later_intervals.id = later.interval_id
+                WHERE
+                    later.person_id = collective_involvements.person_id AND
+                    later.collective_id = collective_involvements.collective_id AND
+                    later_intervals.start_date > intervals.start_date
+            )",
+        collective_id.id
+    )
+    .fetch_all(pool)
+    .await
+}
+
+// Everyone on hiatus in the interval
+pub async fn find_hiatuses_in_interval(
+    collective_id: CollectiveId,
+    interval_id: IntervalId,
+    pool: &SqlitePool,
+) -> Result<Vec<HiatusRecord>, sqlx::Error> {
+    sqlx::query_as!(
+        HiatusRecord,
+        "SELECT person_id, interval_id, opt_out_planned_return_date
+        FROM collective_involvements
+        WHERE collective_id = ? AND interval_id = ? AND status = 'OnHiatus'",
+        collective_id.id,
+        interval_id.id
+    )
+    .fetch_all(pool)
+    .await
+}
+
+// The last interval, before the given one, that the person participated in
+pub async fn find_last_participating_interval_id(
+    collective_id: CollectiveId,
+    person_id: PersonId,
+    before_interval_id: IntervalId,
+    pool: &SqlitePool,
+) -> Result<Option<IntervalId>, sqlx::Error> {
+    sqlx::query_as!(
+        IntervalId,
+        "SELECT collective_involvements.interval_id as id
+        FROM collective_involvements
+        INNER JOIN intervals ON intervals.id = collective_involvements.interval_id
+        WHERE
+            collective_involvements.collective_id = ? AND
+            collective_involvements.person_id = ? AND
+            collective_involvements.status = 'Participating' AND
+            intervals.start_date < (SELECT start_date FROM intervals WHERE id = ?)
+        ORDER BY intervals.start_date DESC
+        LIMIT 1",
+        collective_id.id,
+        person_id.id,
+        before_interval_id.id
+    )
+    .fetch_optional(pool)
+    .await
+}
+
+pub async fn has_collective_involvement(
+    person_id: PersonId,
+    interval_id: IntervalId,
+    pool: &SqlitePool,
+) -> Result<bool, sqlx::Error> {
+    sqlx::query!(
+        "SELECT EXISTS (
+            SELECT 1 FROM collective_involvements WHERE person_id = ? AND interval_id = ?
+        ) as \"exists!: bool\"",
+        person_id.id,
+        interval_id.id
+    )
+    .fetch_one(pool)
+    .await
+    .map(|row| row.exists)
+}
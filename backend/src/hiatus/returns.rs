@@ -0,0 +1,247 @@
+use chrono::NaiveDate;
+use resend_rs::Resend;
+use sqlx::SqlitePool;
+
+use crate::{
+    crews::{
+        convenor_selection::convenor_ids,
+        repo::{find_crew, find_crew_involvements},
+    },
+    hiatus::repo::{
+        find_hiatuses_with_return_dates, find_last_participating_interval_id,
+        has_collective_involvement,
+    },
+    intervals::{
+        clock::{SystemClock, collective_today},
+        phases::can_update_participation,
+        repo::{find_current_interval, parse_date_only},
+    },
+    me::{
+        my_involvement::{
+            InvolvementError, SavedInvolvements, copy_involvements_as_draft, draft_participation,
+        },
+        repo::{PersonIntervalInvolvementData, find_interval_data_for_person},
+    },
+    notifications::{Notification, notify_people, notify_person},
+    people::repo::find_person_by_id,
+    shared::entities::{CollectiveId, CrewId, Interval, IntervalId, PersonId},
+};
+
+// Someone drafted back from hiatus, and what drafting them changed for their crews
+pub struct HiatusReturn {
+    pub interval_data: PersonIntervalInvolvementData,
+    pub saved: SavedInvolvements,
+}
+
+// Drafts everyone coming back from hiatus into the interval containing their planned return
+// date, once that interval exists, using their crews from before the hiatus. Anyone who
+// has already planned that interval is left alone, so this is safe to run repeatedly. The
+// drafts are made as the person would make them, so intervals they can't plan yet are skipped.
+// Anyone who can't be drafted is tried again next time, without holding up everyone else
+pub async fn draft_hiatus_returns(
+    collective_id: CollectiveId,
+    resend: &Resend,
+    pool: &SqlitePool,
+) -> Result<Vec<HiatusReturn>, sqlx::Error> {
+    let hiatuses = find_hiatuses_with_return_dates(collective_id.clone(), pool).await?;
+    let today = collective_today(collective_id.clone(), &SystemClock, pool).await?;
+
+    let mut returns = vec![];
+    for hiatus in hiatuses {
+        let Some(return_date) = hiatus
+            .opt_out_planned_return_date
+            .as_deref()
+            .and_then(parse_date_only)
+        else {
+            continue;
+        };
+
+        let result = return_from_hiatus(
+            collective_id.clone(),
+            PersonId::new(hiatus.person_id),
+            IntervalId::new(hiatus.interval_id),
+            return_date,
+            today,
+            resend,
+            pool,
+        )
+        .await;
+        match result {
+            Ok(Some(hiatus_return)) => returns.push(hiatus_return),
+            Ok(None) => {}
+            Err(error) => eprintln!(
+                "Failed to draft person {} back from hiatus: {:?}",
+                hiatus.person_id, error
+            ),
+        }
+    }
+
+    Ok(returns)
+}
+
+// Returns None if the person isn't ready to be drafted back yet
+async fn return_from_hiatus(
+    collective_id: CollectiveId,
+    person_id: PersonId,
+    hiatus_interval_id: IntervalId,
+    return_date: NaiveDate,
+    today: NaiveDate,
+    resend: &Resend,
+    pool: &SqlitePool,
+) -> Result<Option<HiatusReturn>, InvolvementError> {
+    let return_interval =
+        match find_current_interval(collective_id.clone(), return_date, pool).await {
+            Ok(interval) => interval,
+            // The interval they return in hasn't been created yet
+            Err(sqlx::Error::RowNotFound) => return Ok(None),
+            Err(error) => return Err(error.into()),
+        };
+
+    // The return interval may not be open for planning yet, or may already be locked
+    if !can_update_participation(&return_interval, today, false) {
+        return Ok(None);
+    }
+
+    if has_collective_involvement(person_id.clone(), return_interval.typed_id(), pool).await? {
+        return Ok(None);
+    }
+
+    // Welcomed before drafting, as once they're drafted they won't be tried again
+    if !welcome_back(person_id.clone(), &return_interval, resend, pool).await {
+        return Ok(None);
+    }
+
+    let saved = draft_return(
+        collective_id.clone(),
+        person_id.clone(),
+        hiatus_interval_id.clone(),
+        &return_interval,
+        pool,
+    )
+    .await?;
+
+    let interval_data = find_interval_data_for_person(
+        collective_id.clone(),
+        person_id,
+        return_interval.typed_id(),
+        pool,
+    )
+    .await?;
+    notify_convenors_of_return(
+        collective_id,
+        &interval_data,
+        hiatus_interval_id,
+        &return_interval,
+        resend,
+        pool,
+    )
+    .await?;
+
+    Ok(Some(HiatusReturn {
+        interval_data,
+        saved,
+    }))
+}
+
+async fn draft_return(
+    collective_id: CollectiveId,
+    person_id: PersonId,
+    hiatus_interval_id: IntervalId,
+    return_interval: &Interval,
+    pool: &SqlitePool,
+) -> Result<SavedInvolvements, InvolvementError> {
+    println!(
+        "Drafting person {} back from hiatus in interval {}",
+        person_id.id, return_interval.id
+    );
+
+    let last_participated = find_last_participating_interval_id(
+        collective_id.clone(),
+        person_id.clone(),
+        hiatus_interval_id,
+        pool,
+    )
+    .await?;
+
+    match last_participated {
+        Some(interval_id) => {
+            copy_involvements_as_draft(
+                collective_id,
+                person_id,
+                interval_id,
+                return_interval.typed_id(),
+                None,
+                false,
+                pool,
+            )
+            .await
+        }
+        None => {
+            draft_participation(
+                collective_id,
+                person_id,
+                return_interval.typed_id(),
+                false,
+                pool,
+            )
+            .await
+        }
+    }
+}
+
+// Returns whether the email was sent
+async fn welcome_back(
+    person_id: PersonId,
+    return_interval: &Interval,
+    resend: &Resend,
+    pool: &SqlitePool,
+) -> bool {
+    let welcome = Notification {
+        subject: "Welcome back, confirm your crews".to_string(),
+        message: format!(
+            "Your hiatus is planned to end in the interval starting {}, so we've drafted your participation using your crews from before you left. Please check your crews and confirm your participation.",
+            return_interval.start_date
+        ),
+    };
+    notify_person(person_id, &welcome, resend, pool).await
+}
+
+// Lets the convenors of each crew the person was drafted into know they're coming.
+// Convenors often aren't chosen for the return interval yet, in which case the crew's
+// convenors from the hiatus interval are told instead
+async fn notify_convenors_of_return(
+    collective_id: CollectiveId,
+    interval_data: &PersonIntervalInvolvementData,
+    hiatus_interval_id: IntervalId,
+    return_interval: &Interval,
+    resend: &Resend,
+    pool: &SqlitePool,
+) -> Result<(), sqlx::Error> {
+    let person_id = PersonId::new(interval_data.person_id);
+    let person = find_person_by_id(person_id, collective_id.clone(), pool).await?;
+
+    for crew_involvement in &interval_data.crew_involvements {
+        let crew_id = CrewId::new(crew_involvement.crew_id);
+        let crew = find_crew(collective_id.clone(), crew_id.clone(), pool).await?;
+        let mut convenors = convenor_ids(
+            &find_crew_involvements(crew_id.clone(), return_interval.typed_id(), pool).await?,
+        );
+        if convenors.is_empty() {
+            convenors = convenor_ids(
+                &find_crew_involvements(crew_id, hiatus_interval_id.clone(), pool).await?,
+            );
+        }
+        let convenors: Vec<PersonId> = convenors.into_iter().map(PersonId::new).collect();
+
+        let notification = Notification {
+            subject: format!("{} is coming back to {}", person.display_name, crew.name),
+            message: format!(
+                "{} is returning from hiatus in the interval starting {}, and has been drafted back into {}.",
+                person.display_name, return_interval.start_date, crew.name
+            ),
+        };
+        notify_people(convenors, &notification, resend, pool).await;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,61 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use chrono_tz::Tz;
+use sqlx::SqlitePool;
+
+use crate::shared::entities::CollectiveId;
+
+// Anything that depends on today's date takes a clock, so that a fixed time can be
+// given when checking behaviour around interval boundaries.
+pub trait Clock {
+    fn now(&self) -> DateTime<Utc>;
+}
+
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+pub fn parse_timezone(timezone: &str) -> Option<Tz> {
+    timezone.parse::<Tz>().ok()
+}
+
+// The date it is right now for a collective. All decisions about which interval is
+// current, upcoming or past are made against this date.
+pub fn today_in_timezone(timezone: &str, clock: &impl Clock) -> NaiveDate {
+    let timezone = parse_timezone(timezone).unwrap_or_else(|| {
+        eprintln!("Unknown timezone {}, falling back to UTC", timezone);
+        Tz::UTC
+    });
+
+    clock.now().with_timezone(&timezone).date_naive()
+}
+
+pub async fn collective_today(
+    collective_id: CollectiveId,
+    clock: &impl Clock,
+    pool: &SqlitePool,
+) -> Result<NaiveDate, sqlx::Error> {
+    let timezone = sqlx::query!(
+        "SELECT timezone FROM collectives WHERE id = ?",
+        collective_id.id
+    )
+    .fetch_one(pool)
+    .await?
+    .timezone;
+
+    Ok(today_in_timezone(&timezone, clock))
+}
+
+// Always gives the same time
+#[cfg(test)]
+pub struct FixedClock(pub DateTime<Utc>);
+
+#[cfg(test)]
+impl Clock for FixedClock {
+    fn now(&self) -> DateTime<Utc> {
+        self.0
+    }
+}
@@ -1,25 +1,66 @@
-use axum::{Extension, Json, http::StatusCode, response::IntoResponse};
+use axum::{Extension, Json, extract::Path, http::StatusCode, response::IntoResponse};
+use serde::{Deserialize, Serialize};
 use sqlx::SqlitePool;
+use utoipa::ToSchema;
 use utoipa_axum::{router::OpenApiRouter, routes};
 
 use crate::{
-    auth::auth_backend::AuthSession,
-    intervals::events::IntervalsEvent,
+    auth::{
+        auth_backend::AuthSession,
+        permissions::require_admin,
+        visibility::{Redact, Viewer},
+    },
+    crews::{convenor_selection::select_convenors_for_interval, events::CrewsEvent},
+    intervals::{
+        clock::SystemClock,
+        events::IntervalsEvent,
+        phases::can_move_to_phase,
+        repo::{find_collective_interval, find_interval, find_previous_interval, parse_date_only},
+    },
+    me::{
+        events::MeEvent,
+        my_involvement::{InvolvementError, prepopulate_interval},
+        repo::find_interval_data_for_person,
+    },
     realtime::RealtimeState,
-    shared::{default_collective_id, entities::Interval, events::AppEvent},
+    shared::{
+        default_collective_id,
+        entities::{Interval, IntervalId, IntervalPhase, PersonId, Task},
+        events::AppEvent,
+    },
+    tasks::{events::TasksEvent, recurrence::repeat_recurring_tasks},
 };
 
+pub mod clock;
 pub mod events;
+pub mod phases;
 pub mod repo;
 
 pub fn router() -> OpenApiRouter {
-    OpenApiRouter::new().routes(routes!(create_interval))
+    OpenApiRouter::new()
+        .routes(routes!(create_interval))
+        .routes(routes!(rollover_interval))
+        .routes(routes!(update_interval_phase))
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct IntervalPhaseInput {
+    pub phase: IntervalPhase,
+    // The stored deadline is kept when none is given
+    pub participation_deadline: Option<String>,
+}
+
+// Everyone who couldn't be drafted is listed, so the rollover can be run again for them
+#[derive(Serialize, ToSchema)]
+pub struct RolloverResponse {
+    pub events: Vec<AppEvent>,
+    pub failed_person_ids: Vec<i64>,
 }
 
 #[utoipa::path(post, path = "/",
     request_body(content = Interval, content_type = "application/json"),
     responses(
-        (status = 201, description = "Collective found successfully", body = Vec<AppEvent>),
+        (status = 201, description = "Created the interval, and repeated the previous interval's recurring tasks into it", body = Vec<AppEvent>),
         (status = INTERNAL_SERVER_ERROR, description = "Internal server error", body = ()),
     ),
 )]
@@ -31,14 +72,304 @@ async fn create_interval(
 ) -> impl IntoResponse {
     println!("Creating interval: {:?}", interval);
 
-    match repo::insert_interval(interval, default_collective_id(), &pool).await {
-        Ok(response) => {
-            let event = AppEvent::IntervalsEvent(IntervalsEvent::IntervalCreated(response));
+    let interval = match repo::insert_interval(interval, default_collective_id(), &pool).await {
+        Ok(interval) => interval,
+        Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR, ()).into_response(),
+    };
+
+    // Recurring tasks carry on into each new interval without waiting for a rollover
+    let repeated_tasks = match repeat_tasks_from_previous_interval(&interval, &pool).await {
+        Ok(tasks) => tasks,
+        Err(error) => {
+            eprintln!(
+                "Error repeating tasks into interval {}: {:?}",
+                interval.id, error
+            );
+            return (StatusCode::INTERNAL_SERVER_ERROR, ()).into_response();
+        }
+    };
+
+    let mut events = vec![AppEvent::IntervalsEvent(IntervalsEvent::IntervalCreated(
+        interval,
+    ))];
+    events.extend(
+        repeated_tasks
+            .into_iter()
+            .map(|task| AppEvent::TasksEvent(TasksEvent::TaskCreated(task))),
+    );
+    for event in &events {
+        realtime_state
+            .broadcast_app_event(Some(auth_session.clone()), event.clone())
+            .await;
+    }
+
+    (StatusCode::CREATED, Json(events)).into_response()
+}
+
+async fn repeat_tasks_from_previous_interval(
+    interval: &Interval,
+    pool: &SqlitePool,
+) -> Result<Vec<Task>, sqlx::Error> {
+    match find_previous_interval(default_collective_id(), interval.typed_id(), pool).await? {
+        Some(previous_interval) => {
+            repeat_recurring_tasks(
+                default_collective_id(),
+                previous_interval.typed_id(),
+                interval,
+                pool,
+            )
+            .await
+        }
+        None => Ok(vec![]),
+    }
+}
+
+#[utoipa::path(post, path = "/{interval_id}/rollover",
+    params(
+        ("interval_id" = i64, Path, description = "Interval ID to pre-populate from the previous interval")
+    ),
+    responses(
+        (status = 200, description = "Drafted participation for everyone participating in the previous interval who could be drafted, and repeated its recurring tasks", body = RolloverResponse),
+        (status = FORBIDDEN, description = "Only admins can pre-populate an interval, and only while it can still be changed", body = ()),
+        (status = NOT_FOUND, description = "No previous interval to copy from", body = ()),
+        (status = INTERNAL_SERVER_ERROR, description = "Internal server error", body = ()),
+    ),
+)]
+async fn rollover_interval(
+    Path(interval_id): Path<i64>,
+    Extension(pool): Extension<SqlitePool>,
+    Extension(realtime_state): Extension<RealtimeState>,
+    auth_session: AuthSession,
+) -> impl IntoResponse {
+    let admin = match require_admin(&auth_session, default_collective_id(), &pool).await {
+        Ok(admin) => admin,
+        Err(status) => return (status, ()).into_response(),
+    };
+
+    println!(
+        "Person {} pre-populating interval {}",
+        admin.person_id.id, interval_id
+    );
+
+    let interval_id = IntervalId::new(interval_id);
+    let previous_interval =
+        match find_previous_interval(default_collective_id(), interval_id.clone(), &pool).await {
+            Ok(Some(interval)) => interval,
+            Ok(None) => return (StatusCode::NOT_FOUND, ()).into_response(),
+            Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR, ()).into_response(),
+        };
+
+    let prepopulated = match prepopulate_interval(
+        default_collective_id(),
+        previous_interval.typed_id(),
+        interval_id.clone(),
+        &pool,
+    )
+    .await
+    {
+        Ok(prepopulated) => prepopulated,
+        Err(InvolvementError::IntervalPhase { .. }) => {
+            return (StatusCode::FORBIDDEN, ()).into_response();
+        }
+        Err(error) => {
+            eprintln!(
+                "Error pre-populating interval {}: {:?}",
+                interval_id.id, error
+            );
+            return (StatusCode::INTERNAL_SERVER_ERROR, ()).into_response();
+        }
+    };
+
+    let Ok(interval) = find_interval(interval_id.clone(), &pool).await else {
+        return (StatusCode::INTERNAL_SERVER_ERROR, ()).into_response();
+    };
+    let repeated_tasks = match repeat_recurring_tasks(
+        default_collective_id(),
+        previous_interval.typed_id(),
+        &interval,
+        &pool,
+    )
+    .await
+    {
+        Ok(tasks) => tasks,
+        Err(error) => {
+            eprintln!(
+                "Error repeating tasks into interval {}: {:?}",
+                interval_id.id, error
+            );
+            return (StatusCode::INTERNAL_SERVER_ERROR, ()).into_response();
+        }
+    };
+
+    let mut events = match broadcast_interval_data_changed(
+        prepopulated.drafted,
+        interval_id,
+        &pool,
+        &realtime_state,
+        &auth_session,
+    )
+    .await
+    {
+        Ok(events) => events,
+        Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR, ()).into_response(),
+    };
+
+    for task in repeated_tasks {
+        let event = AppEvent::TasksEvent(TasksEvent::TaskCreated(task));
+        realtime_state
+            .broadcast_app_event(Some(auth_session.clone()), event.clone())
+            .await;
+        events.push(event);
+    }
+
+    let failed_person_ids = prepopulated
+        .failed
+        .into_iter()
+        .map(|person_id| person_id.id)
+        .collect();
+    let response = RolloverResponse {
+        events,
+        failed_person_ids,
+    };
+    (StatusCode::OK, Json(response)).into_response()
+}
+
+// Broadcasts the public version of each person's data for the interval
+async fn broadcast_interval_data_changed(
+    person_ids: Vec<PersonId>,
+    interval_id: IntervalId,
+    pool: &SqlitePool,
+    realtime_state: &RealtimeState,
+    auth_session: &AuthSession,
+) -> Result<Vec<AppEvent>, sqlx::Error> {
+    let mut events = vec![];
+    for person_id in person_ids {
+        let interval_data = find_interval_data_for_person(
+            default_collective_id(),
+            person_id,
+            interval_id.clone(),
+            pool,
+        )
+        .await?;
+        let event = AppEvent::MeEvent(MeEvent::IntervalDataChanged(
+            interval_data.redact_for(&Viewer::everyone()),
+        ));
+        realtime_state
+            .broadcast_app_event(Some(auth_session.clone()), event.clone())
+            .await;
+        events.push(event);
+    }
+
+    Ok(events)
+}
+
+#[utoipa::path(put, path = "/{interval_id}/phase",
+    params(
+        ("interval_id" = i64, Path, description = "Interval ID")
+    ),
+    request_body(content = IntervalPhaseInput, content_type = "application/json"),
+    responses(
+        (status = 200, body = Vec<AppEvent>),
+        (status = BAD_REQUEST, description = "The interval can't move to that phase, or the deadline isn't a date", body = ()),
+        (status = FORBIDDEN, description = "Only admins can change an interval's phase", body = ()),
+        (status = NOT_FOUND, description = "Interval not found", body = ()),
+        (status = INTERNAL_SERVER_ERROR, description = "Internal server error", body = ()),
+    ),
+)]
+async fn update_interval_phase(
+    Path(interval_id): Path<i64>,
+    Extension(pool): Extension<SqlitePool>,
+    Extension(realtime_state): Extension<RealtimeState>,
+    auth_session: AuthSession,
+    Json(input): Json<IntervalPhaseInput>,
+) -> impl IntoResponse {
+    let admin = match require_admin(&auth_session, default_collective_id(), &pool).await {
+        Ok(admin) => admin,
+        Err(status) => return (status, ()).into_response(),
+    };
+
+    let interval_id = IntervalId::new(interval_id);
+    let interval =
+        match find_collective_interval(default_collective_id(), interval_id.clone(), &pool).await {
+            Ok(Some(interval)) => interval,
+            Ok(None) => return (StatusCode::NOT_FOUND, ()).into_response(),
+            Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR, ()).into_response(),
+        };
+
+    if input.phase != interval.phase && !can_move_to_phase(interval.phase, input.phase) {
+        return (StatusCode::BAD_REQUEST, "Interval can't move to that phase").into_response();
+    }
+
+    let deadline = input.participation_deadline.as_deref();
+    if deadline.is_some_and(|deadline| parse_date_only(deadline).is_none()) {
+        return (StatusCode::BAD_REQUEST, "Deadline must be a date").into_response();
+    }
+
+    println!(
+        "Moving interval {} from {:?} to {:?}",
+        interval_id.id, interval.phase, input.phase
+    );
+
+    let Ok(response) = repo::update_interval_phase(
+        interval_id.clone(),
+        default_collective_id(),
+        input.phase,
+        input.participation_deadline,
+        &pool,
+    )
+    .await
+    else {
+        return (StatusCode::INTERNAL_SERVER_ERROR, ()).into_response();
+    };
+
+    let event = AppEvent::IntervalsEvent(IntervalsEvent::IntervalPhaseChanged(response));
+    realtime_state
+        .broadcast_app_event(Some(auth_session.clone()), event.clone())
+        .await;
+    let mut events = vec![event];
+
+    // Locking is the last chance to choose convenors for crews that don't have one yet
+    let locking = interval.phase < IntervalPhase::Locked && input.phase == IntervalPhase::Locked;
+    if locking {
+        let convenor_result = select_convenors_for_interval(
+            default_collective_id(),
+            interval_id.clone(),
+            input.phase,
+            admin.person_id.id,
+            &SystemClock,
+            &pool,
+        )
+        .await;
+        let Ok(changes) = convenor_result else {
+            return (StatusCode::INTERNAL_SERVER_ERROR, ()).into_response();
+        };
+
+        let mut person_ids: Vec<PersonId> = vec![];
+        for change in changes {
+            person_ids.extend(change.affected_person_ids().into_iter().map(PersonId::new));
+
+            let event = AppEvent::CrewsEvent(CrewsEvent::ConvenorsChanged(change));
             realtime_state
-                .broadcast_app_event(Some(auth_session), event.clone())
+                .broadcast_app_event(Some(auth_session.clone()), event.clone())
                 .await;
-            return (StatusCode::CREATED, Json(vec![event])).into_response();
+            events.push(event);
+        }
+        person_ids.sort_unstable_by_key(|person_id| person_id.id);
+        person_ids.dedup();
+
+        match broadcast_interval_data_changed(
+            person_ids,
+            interval_id,
+            &pool,
+            &realtime_state,
+            &auth_session,
+        )
+        .await
+        {
+            Ok(convenor_events) => events.extend(convenor_events),
+            Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR, ()).into_response(),
         }
-        Err(_) => (StatusCode::INTERNAL_SERVER_ERROR, ()).into_response(),
     }
+
+    (StatusCode::OK, Json(events)).into_response()
 }
@@ -0,0 +1,50 @@
+use chrono::NaiveDate;
+
+use crate::{
+    intervals::repo::{IntervalType, get_interval_type, parse_date_only},
+    shared::entities::{Interval, IntervalPhase},
+};
+
+// Phases only move forward, except that a locked interval can be re-opened for planning
+pub fn can_move_to_phase(from: IntervalPhase, to: IntervalPhase) -> bool {
+    if from == IntervalPhase::Locked && to == IntervalPhase::OpenForPlanning {
+        return true;
+    }
+
+    to > from
+}
+
+pub fn participation_deadline_passed(interval: &Interval, today: NaiveDate) -> bool {
+    interval
+        .participation_deadline
+        .as_deref()
+        .and_then(parse_date_only)
+        .is_some_and(|deadline| today > deadline)
+}
+
+// Members can plan while an interval is open and before its deadline, and make changes once
+// it's active. Admins can override the phase and deadline, but nobody can change a closed or
+// past interval.
+pub fn can_update_participation(
+    interval: &Interval,
+    today: NaiveDate,
+    acting_as_admin: bool,
+) -> bool {
+    if interval.phase == IntervalPhase::Closed {
+        return false;
+    }
+
+    if get_interval_type(interval.clone(), today) == IntervalType::Past {
+        return false;
+    }
+
+    if acting_as_admin {
+        return true;
+    }
+
+    match interval.phase {
+        IntervalPhase::OpenForPlanning => !participation_deadline_passed(interval, today),
+        IntervalPhase::Active => true,
+        IntervalPhase::Draft | IntervalPhase::Locked | IntervalPhase::Closed => false,
+    }
+}
@@ -1,6 +1,7 @@
+use chrono::NaiveDate;
 use sqlx::SqlitePool;
 
-use crate::shared::entities::{CollectiveId, Interval, IntervalId};
+use crate::shared::entities::{CollectiveId, Interval, IntervalId, IntervalPhase};
 
 #[derive(PartialEq, Debug, Clone, Copy)]
 pub enum IntervalType {
@@ -14,41 +15,45 @@ pub async fn insert_interval(
     collective_id: CollectiveId,
     pool: &SqlitePool,
 ) -> Result<Interval, sqlx::Error> {
-    let mut transaction = pool.begin().await?;
-
-    let last_id = sqlx::query!(
-        "SELECT MAX(id) AS id
-        FROM intervals
-        WHERE
-            collective_id = ?",
-        collective_id.id
-    )
-    .fetch_one(&mut *transaction)
-    .await
-    .map(|row| row.id)?;
-
-    let next_id = last_id.map_or(1, |id| id + 1);
-
+    // The sequence is worked out in the same statement as the insert, so that concurrent
+    // inserts can't both take the same number
     sqlx::query_as!(
         Interval,
-        "INSERT INTO intervals (id, start_date, end_date, collective_id)
-         VALUES (?, ?, ?, ?)
-         RETURNING id, start_date, end_date",
-        next_id,
+        "INSERT INTO intervals (sequence, start_date, end_date, collective_id, phase, participation_deadline)
+         SELECT COALESCE(MAX(sequence), 0) + 1, ?, ?, ?, ?, ?
+         FROM intervals
+         WHERE collective_id = ?
+         RETURNING id, sequence, start_date, end_date, phase as \"phase: IntervalPhase\", participation_deadline",
         interval.start_date,
         interval.end_date,
+        collective_id.id,
+        interval.phase,
+        interval.participation_deadline,
         collective_id.id
     )
-    .fetch_one(&mut *transaction)
-    .await?;
+    .fetch_one(pool)
+    .await
+}
 
-    transaction.commit().await?;
+pub async fn update_interval_phase(
+    interval_id: IntervalId,
+    collective_id: CollectiveId,
+    phase: IntervalPhase,
+    participation_deadline: Option<String>,
+    pool: &SqlitePool,
+) -> Result<Interval, sqlx::Error> {
+    sqlx::query!(
+        "UPDATE intervals SET phase = ?, participation_deadline = COALESCE(?, participation_deadline)
+        WHERE id = ? AND collective_id = ?",
+        phase,
+        participation_deadline,
+        interval_id.id,
+        collective_id.id
+    )
+    .execute(pool)
+    .await?;
 
-    Ok(Interval {
-        id: next_id,
-        start_date: interval.start_date,
-        end_date: interval.end_date,
-    })
+    find_interval(interval_id, pool).await
 }
 
 pub async fn find_interval(
@@ -57,55 +62,111 @@ pub async fn find_interval(
 ) -> Result<Interval, sqlx::Error> {
     sqlx::query_as!(
         Interval,
-        "SELECT id, start_date, end_date FROM intervals WHERE id = ?",
+        "SELECT id, sequence, start_date, end_date, phase as \"phase: IntervalPhase\", participation_deadline
+        FROM intervals
+        WHERE id = ?",
         interval_id.id
     )
     .fetch_one(pool)
     .await
 }
 
+// Like find_interval, but only finds intervals belonging to the collective
+pub async fn find_collective_interval(
+    collective_id: CollectiveId,
+    interval_id: IntervalId,
+    pool: &SqlitePool,
+) -> Result<Option<Interval>, sqlx::Error> {
+    sqlx::query_as!(
+        Interval,
+        "SELECT id, sequence, start_date, end_date, phase as \"phase: IntervalPhase\", participation_deadline
+        FROM intervals
+        WHERE id = ? AND collective_id = ?",
+        interval_id.id,
+        collective_id.id
+    )
+    .fetch_optional(pool)
+    .await
+}
+
+pub async fn find_all_intervals(
+    collective_id: CollectiveId,
+    pool: &SqlitePool,
+) -> Result<Vec<Interval>, sqlx::Error> {
+    sqlx::query_as!(
+        Interval,
+        "SELECT id, sequence, start_date, end_date, phase as \"phase: IntervalPhase\", participation_deadline
+        FROM intervals
+        WHERE collective_id = ?
+        ORDER BY start_date ASC",
+        collective_id.id
+    )
+    .fetch_all(pool)
+    .await
+}
+
+// Which interval is current is decided by `get_interval_type`, so that it always agrees with
+// the checks made against the same date elsewhere
 pub async fn find_current_interval(
     collective_id: CollectiveId,
+    today: NaiveDate,
     pool: &SqlitePool,
 ) -> Result<Interval, sqlx::Error> {
+    let intervals = find_all_intervals(collective_id, pool).await?;
+
+    current_interval(intervals, today).ok_or(sqlx::Error::RowNotFound)
+}
+
+// The earliest interval running on the date, given intervals in start date order
+fn current_interval(intervals: Vec<Interval>, today: NaiveDate) -> Option<Interval> {
+    intervals
+        .into_iter()
+        .find(|interval| get_interval_type(interval.clone(), today) == IntervalType::Current)
+}
+
+pub async fn find_next_interval(
+    collective_id: CollectiveId,
+    current_interval_id: IntervalId,
+    pool: &SqlitePool,
+) -> Result<Option<Interval>, sqlx::Error> {
     sqlx::query_as!(
         Interval,
-        "SELECT id, start_date, end_date
+        "SELECT id, sequence, start_date, end_date, phase as \"phase: IntervalPhase\", participation_deadline
         FROM intervals
         WHERE
             collective_id = ? AND
-            start_date <= date('now') AND (end_date IS NULL OR end_date >= date('now'))
-        ORDER BY id ASC
+            start_date > (SELECT start_date FROM intervals WHERE id = ?)
+        ORDER BY start_date ASC
         LIMIT 1",
-        collective_id.id
+        collective_id.id,
+        current_interval_id.id
     )
-    .fetch_one(pool)
+    .fetch_optional(pool)
     .await
 }
 
-pub async fn find_next_interval(
+pub async fn find_previous_interval(
     collective_id: CollectiveId,
-    current_interval_id: IntervalId,
+    interval_id: IntervalId,
     pool: &SqlitePool,
 ) -> Result<Option<Interval>, sqlx::Error> {
     sqlx::query_as!(
         Interval,
-        "SELECT id, start_date, end_date
+        "SELECT id, sequence, start_date, end_date, phase as \"phase: IntervalPhase\", participation_deadline
         FROM intervals
         WHERE
             collective_id = ? AND
-            id > ?
-        ORDER BY id ASC
+            start_date < (SELECT start_date FROM intervals WHERE id = ?)
+        ORDER BY start_date DESC
         LIMIT 1",
         collective_id.id,
-        current_interval_id.id
+        interval_id.id
     )
     .fetch_optional(pool)
     .await
 }
 
-pub fn get_interval_type(interval: Interval) -> IntervalType {
-    let today = chrono::Utc::now().date_naive();
+pub fn get_interval_type(interval: Interval, today: NaiveDate) -> IntervalType {
     let start_date = parse_date_only(&interval.start_date).unwrap();
     let end_date = parse_date_only(&interval.end_date);
 
@@ -122,6 +183,156 @@ pub fn get_interval_type(interval: Interval) -> IntervalType {
     return IntervalType::Current;
 }
 
+// The date whose statuses count when planning the interval: today while it's running,
+// otherwise the nearest day of it
+pub fn planning_date(interval: &Interval, today: NaiveDate) -> String {
+    match get_interval_type(interval.clone(), today) {
+        IntervalType::Current => today.format("%Y-%m-%d").to_string(),
+        IntervalType::Upcoming => interval.start_date.clone(),
+        IntervalType::Past => interval.end_date.clone(),
+    }
+}
+
 pub fn parse_date_only(date_str: &str) -> Option<chrono::NaiveDate> {
     chrono::NaiveDate::parse_from_str(date_str, "%Y-%m-%d").ok()
 }
+
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, NaiveDate, Utc};
+
+    use super::*;
+    use crate::{
+        intervals::clock::{FixedClock, collective_today},
+        shared::default_collective_id,
+        test_support::TestApp,
+    };
+
+    fn clock_at(utc_time: &str) -> FixedClock {
+        FixedClock(utc_time.parse::<DateTime<Utc>>().unwrap())
+    }
+
+    fn date(date: &str) -> NaiveDate {
+        parse_date_only(date).unwrap()
+    }
+
+    fn interval(id: i64, start_date: &str, end_date: &str) -> Interval {
+        Interval {
+            id,
+            sequence: id,
+            start_date: start_date.to_string(),
+            end_date: end_date.to_string(),
+            phase: IntervalPhase::default(),
+            participation_deadline: None,
+        }
+    }
+
+    // Two intervals meeting at midnight Melbourne time on the 1st of July. Melbourne is ten hours
+    // ahead of UTC in July, so its days start at 14:00 UTC
+    async fn seed_melbourne() -> TestApp {
+        let app = TestApp::new().await;
+        app.execute("UPDATE collectives SET timezone = 'Australia/Melbourne'")
+            .await;
+        app.execute(
+            "INSERT INTO intervals (id, sequence, start_date, end_date, collective_id)
+            VALUES (1, 1, '2025-06-01', '2025-06-30', 1), (2, 2, '2025-07-01', '2025-07-31', 1)",
+        )
+        .await;
+        app
+    }
+
+    #[test]
+    fn interval_types_include_both_end_dates() {
+        let june = interval(1, "2025-06-01", "2025-06-30");
+
+        assert_eq!(
+            get_interval_type(june.clone(), date("2025-05-31")),
+            IntervalType::Upcoming
+        );
+        assert_eq!(
+            get_interval_type(june.clone(), date("2025-06-01")),
+            IntervalType::Current
+        );
+        assert_eq!(
+            get_interval_type(june.clone(), date("2025-06-30")),
+            IntervalType::Current
+        );
+        assert_eq!(
+            get_interval_type(june, date("2025-07-01")),
+            IntervalType::Past
+        );
+    }
+
+    #[test]
+    fn planning_date_is_the_nearest_day_of_the_interval() {
+        let june = interval(1, "2025-06-01", "2025-06-30");
+
+        assert_eq!(planning_date(&june, date("2025-05-20")), "2025-06-01");
+        assert_eq!(planning_date(&june, date("2025-06-15")), "2025-06-15");
+        assert_eq!(planning_date(&june, date("2025-07-10")), "2025-06-30");
+    }
+
+    #[test]
+    fn current_interval_is_the_earliest_running() {
+        let intervals = vec![
+            interval(1, "2025-06-01", "2025-06-30"),
+            interval(2, "2025-06-30", "2025-07-31"),
+        ];
+
+        let current =
+            |today| current_interval(intervals.clone(), date(today)).map(|interval| interval.id);
+        assert_eq!(current("2025-05-31"), None);
+        assert_eq!(current("2025-06-30"), Some(1));
+        assert_eq!(current("2025-07-01"), Some(2));
+        assert_eq!(current("2025-08-01"), None);
+    }
+
+    #[tokio::test]
+    async fn current_interval_follows_the_collective_timezone_before_midnight() {
+        let app = seed_melbourne().await;
+        // 23:59 on the 30th of June in Melbourne
+        let clock = clock_at("2025-06-30T13:59:00Z");
+
+        let today = collective_today(default_collective_id(), &clock, &app.pool)
+            .await
+            .unwrap();
+        let current = find_current_interval(default_collective_id(), today, &app.pool)
+            .await
+            .unwrap();
+
+        assert_eq!(today, date("2025-06-30"));
+        assert_eq!(current.id, 1);
+    }
+
+    #[tokio::test]
+    async fn current_interval_follows_the_collective_timezone_after_midnight() {
+        let app = seed_melbourne().await;
+        // 00:30 on the 1st of July in Melbourne, while it's still the 30th of June in UTC
+        let clock = clock_at("2025-06-30T14:30:00Z");
+
+        let today = collective_today(default_collective_id(), &clock, &app.pool)
+            .await
+            .unwrap();
+        let current = find_current_interval(default_collective_id(), today, &app.pool)
+            .await
+            .unwrap();
+        let june = find_interval(IntervalId::new(1), &app.pool).await.unwrap();
+
+        assert_eq!(today, date("2025-07-01"));
+        assert_eq!(current.id, 2);
+        assert_eq!(get_interval_type(june, today), IntervalType::Past);
+    }
+
+    #[tokio::test]
+    async fn no_current_interval_is_not_found() {
+        let app = seed_melbourne().await;
+        let clock = clock_at("2025-08-01T00:00:00Z");
+
+        let today = collective_today(default_collective_id(), &clock, &app.pool)
+            .await
+            .unwrap();
+        let result = find_current_interval(default_collective_id(), today, &app.pool).await;
+
+        assert!(matches!(result, Err(sqlx::Error::RowNotFound)));
+    }
+}
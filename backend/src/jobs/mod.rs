@@ -0,0 +1,101 @@
+use std::time::Duration;
+
+use chrono::NaiveDate;
+use resend_rs::Resend;
+use sqlx::SqlitePool;
+
+use crate::{
+    auth::visibility::{Redact, Viewer},
+    crews::{convenor_selection::step_down_unavailable_convenors, events::CrewsEvent},
+    exits::workflow::disable_exited_accounts,
+    hiatus::returns::draft_hiatus_returns,
+    intervals::{
+        clock::{SystemClock, collective_today},
+        repo::find_current_interval,
+    },
+    me::events::MeEvent,
+    realtime::RealtimeState,
+    shared::{default_collective_id, events::AppEvent},
+};
+
+const JOB_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+// Runs the scheduled jobs in the background, once at startup and then every hour
+pub fn spawn_jobs(pool: SqlitePool, resend: Resend, realtime_state: RealtimeState) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(JOB_INTERVAL);
+        loop {
+            ticker.tick().await;
+            run_jobs(&pool, &resend, &realtime_state).await;
+        }
+    });
+}
+
+async fn run_jobs(pool: &SqlitePool, resend: &Resend, realtime_state: &RealtimeState) {
+    match draft_hiatus_returns(default_collective_id(), resend, pool).await {
+        Ok(returns) => {
+            for hiatus_return in returns {
+                let event = AppEvent::MeEvent(MeEvent::IntervalDataChanged(
+                    hiatus_return.interval_data.redact_for(&Viewer::everyone()),
+                ));
+                realtime_state.broadcast_app_event(None, event).await;
+
+                for change in hiatus_return.saved.convenor_changes {
+                    let event = AppEvent::CrewsEvent(CrewsEvent::ConvenorsChanged(change));
+                    realtime_state.broadcast_app_event(None, event).await;
+                }
+                for request in hiatus_return.saved.join_requests {
+                    let event = AppEvent::CrewsEvent(CrewsEvent::JoinRequested(request));
+                    realtime_state.broadcast_app_event(None, event).await;
+                }
+            }
+        }
+        Err(error) => eprintln!("Failed to draft returns from hiatus: {:?}", error),
+    }
+
+    match collective_today(default_collective_id(), &SystemClock, pool).await {
+        Ok(today) => {
+            if let Err(error) = disable_exited_accounts(default_collective_id(), today, pool).await
+            {
+                eprintln!("Failed to disable exited accounts: {:?}", error);
+            }
+            step_down_convenors_on_hiatus(today, pool, realtime_state).await;
+        }
+        Err(error) => eprintln!("Failed to find today for exits: {:?}", error),
+    }
+}
+
+// Status changes can take effect on a later date, so convenors who've started a hiatus since
+// are caught here
+async fn step_down_convenors_on_hiatus(
+    today: NaiveDate,
+    pool: &SqlitePool,
+    realtime_state: &RealtimeState,
+) {
+    let interval = match find_current_interval(default_collective_id(), today, pool).await {
+        Ok(interval) => interval,
+        Err(sqlx::Error::RowNotFound) => return,
+        Err(error) => {
+            eprintln!("Failed to find the current interval: {:?}", error);
+            return;
+        }
+    };
+
+    match step_down_unavailable_convenors(
+        default_collective_id(),
+        &interval,
+        today,
+        &SystemClock,
+        pool,
+    )
+    .await
+    {
+        Ok(changes) => {
+            for change in changes {
+                let event = AppEvent::CrewsEvent(CrewsEvent::ConvenorsChanged(change));
+                realtime_state.broadcast_app_event(None, event).await;
+            }
+        }
+        Err(error) => eprintln!("Failed to step down unavailable convenors: {:?}", error),
+    }
+}
@@ -1,5 +1,5 @@
 use axum::{
-    Extension,
+    Extension, Router,
     http::{Method, header},
     routing::get,
 };
@@ -8,6 +8,7 @@ use axum_login::{
     tower_sessions::{Expiry, SessionManagerLayer},
 };
 use resend_rs::Resend;
+use sqlx::SqlitePool;
 use std::env;
 use time::Duration;
 use tower_http::cors::CorsLayer;
@@ -24,29 +25,70 @@ use crate::{
     static_server::frontend_handler,
 };
 
+mod analytics;
 mod api;
 mod auth;
+mod calendar;
+mod check_ins;
 mod crews;
 mod database;
 mod entry_pathways;
+mod exits;
+mod hiatus;
 mod intervals;
+mod jobs;
 mod me;
 mod my_collective;
+mod notifications;
 mod people;
 mod public;
 mod realtime;
 mod shared;
 mod static_server;
+mod tasks;
+#[cfg(test)]
+mod test_support;
 
 #[macro_use]
 extern crate lazy_static;
 
+#[derive(OpenApi)]
+#[openapi()]
+struct ApiDoc;
+
+// Everything but CORS, so tests can make requests against the same routes, sessions and auth
+fn app(pool: SqlitePool, resend: Resend, realtime_state: RealtimeState) -> Router {
+    // SESSION MANAGEMENT
+    let session_store = MemoryStore::default();
+    let session_layer = SessionManagerLayer::new(session_store)
+        .with_secure(false)
+        .with_expiry(Expiry::OnInactivity(Duration::days(30)));
+    let backend = AppAuthBackend::new(pool.clone());
+    let auth_layer = AuthManagerLayerBuilder::new(backend, session_layer).build();
+
+    // ROUTES
+    let (router, api) = OpenApiRouter::with_openapi(ApiDoc::openapi())
+        .nest(
+            "/api",
+            OpenApiRouter::new()
+                .nest("/auth", crate::auth::router())
+                .merge(public_api_router())
+                .merge(private_api_router().route_layer(login_required!(AppAuthBackend))),
+        )
+        .split_for_parts();
+
+    router
+        .merge(SwaggerUi::new("/swagger-ui").url("/api-docs/openapi.json", api.clone()))
+        .route("/ws", get(realtime::handler))
+        .fallback_service(get(frontend_handler))
+        .layer(Extension(pool))
+        .layer(Extension(resend))
+        .layer(auth_layer)
+        .layer(Extension(realtime_state))
+}
+
 #[tokio::main]
 async fn main() {
-    #[derive(OpenApi)]
-    #[openapi()]
-    struct ApiDoc;
-
     // CONFIG PARAMS
     let base_url =
         std::env::var("BASE_URL").unwrap_or_else(|_| "http://localhost:5173".to_string());
@@ -69,14 +111,6 @@ async fn main() {
         .await
         .expect("Failed to prepare database");
 
-    // SESSION MANAGEMENT
-    let session_store = MemoryStore::default();
-    let session_layer = SessionManagerLayer::new(session_store)
-        .with_secure(false)
-        .with_expiry(Expiry::OnInactivity(Duration::days(30)));
-    let backend = AppAuthBackend::new(pool.clone());
-    let auth_layer = AuthManagerLayerBuilder::new(backend, session_layer).build();
-
     // REALTIME COMMS
     let realtime_state = RealtimeState::new();
 
@@ -92,26 +126,11 @@ async fn main() {
 
     let resend = Resend::with_client(&resend_key, client);
 
-    // ROUTES
-    let (router, api) = OpenApiRouter::with_openapi(ApiDoc::openapi())
-        .nest(
-            "/api",
-            OpenApiRouter::new()
-                .nest("/auth", crate::auth::router())
-                .merge(public_api_router())
-                .merge(private_api_router().route_layer(login_required!(AppAuthBackend))),
-        )
-        .split_for_parts();
+    // BACKGROUND JOBS
+    jobs::spawn_jobs(pool.clone(), resend.clone(), realtime_state.clone());
 
-    let router = router
-        .merge(SwaggerUi::new("/swagger-ui").url("/api-docs/openapi.json", api.clone()))
-        .route("/ws", get(realtime::handler))
-        .fallback_service(get(frontend_handler))
-        .layer(cors)
-        .layer(Extension(pool))
-        .layer(Extension(resend))
-        .layer(auth_layer)
-        .layer(Extension(realtime_state));
+    // ROUTES
+    let router = app(pool, resend, realtime_state).layer(cors);
 
     // SERVICE
     let app = router.into_make_service();
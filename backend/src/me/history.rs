@@ -0,0 +1,43 @@
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use utoipa::ToSchema;
+
+use crate::{
+    intervals::repo::find_all_intervals,
+    me::repo::{PersonIntervalInvolvementData, find_interval_data_for_person},
+    shared::entities::{CollectiveId, PersonId},
+};
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct ParticipationHistory {
+    pub person_id: i64,
+    // Every interval, oldest first, including those the person wasn't involved in
+    pub intervals: Vec<PersonIntervalInvolvementData>,
+}
+
+// Unredacted, so callers need to redact it for whoever it's going to
+pub async fn find_participation_history(
+    collective_id: CollectiveId,
+    person_id: PersonId,
+    pool: &SqlitePool,
+) -> Result<ParticipationHistory, sqlx::Error> {
+    let intervals = find_all_intervals(collective_id.clone(), pool).await?;
+
+    let mut history = vec![];
+    for interval in intervals {
+        let interval_data = find_interval_data_for_person(
+            collective_id.clone(),
+            person_id.clone(),
+            interval.typed_id(),
+            pool,
+        )
+        .await?;
+
+        history.push(interval_data);
+    }
+
+    Ok(ParticipationHistory {
+        person_id: person_id.id,
+        intervals: history,
+    })
+}
@@ -1,32 +1,66 @@
-use axum::{Extension, Json, extract::Path, http::StatusCode, response::IntoResponse};
+use axum::{
+    Extension, Json,
+    extract::Path,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use resend_rs::Resend;
 use sqlx::SqlitePool;
 use utoipa_axum::{router::OpenApiRouter, routes};
 
 use crate::{
-    auth::auth_backend::AuthSession,
+    auth::{
+        auth_backend::AuthSession,
+        visibility::{Redact, Viewer},
+    },
+    check_ins::{repo::find_question_set, validate_answers},
+    crews::{
+        events::CrewsEvent, membership::notify_join_requested, staffing::find_staffing_report,
+    },
+    exits::workflow::{ExitStarted, notify_exit_started},
+    intervals::{
+        clock::{SystemClock, collective_today},
+        repo::{find_interval, find_previous_interval},
+    },
     me::{
-        events::{MeEvent, strip_private_data},
-        my_involvement::{MyParticipationInput, update_my_involvements},
-        repo::{MyInitialData, find_person_id_for_user},
+        events::{MeEvent, ProxyEdit},
+        history::{ParticipationHistory, find_participation_history},
+        my_involvement::{
+            InvolvementError, MyParticipationInput, SavedInvolvements, StatusChangeInput,
+            calculate_status, change_status_from, copy_involvements_as_draft,
+            update_my_involvements, validate_status_change,
+        },
+        repo::{
+            MyInitialData, PersonIntervalInvolvementData, find_person_id_for_user, find_person_role,
+        },
     },
     my_collective::involvements_repo::find_collective_involvement,
     realtime::RealtimeState,
     shared::{
         default_collective_id,
-        entities::{CollectiveInvolvement, IntervalId, UserId},
+        entities::{
+            CollectiveInvolvement, IntervalId, InvolvementStatus, PersonId, PersonRole, Task,
+            UserId,
+        },
         events::AppEvent,
     },
+    tasks::{events::TasksEvent, repo::find_open_tasks_for_assignee},
 };
 
 pub mod events;
-mod my_involvement;
-mod repo;
+pub mod history;
+pub mod my_involvement;
+pub mod repo;
 
 pub fn router() -> OpenApiRouter {
     OpenApiRouter::new()
         .routes(routes!(get_my_state))
         .routes(routes!(my_participation))
         .routes(routes!(update_my_participation))
+        .routes(routes!(rollover_my_participation))
+        .routes(routes!(change_my_status))
+        .routes(routes!(get_my_tasks))
+        .routes(routes!(get_my_history))
 }
 
 #[utoipa::path(get, path = "/", responses(
@@ -40,9 +74,15 @@ async fn get_my_state(
 ) -> impl IntoResponse {
     match auth_session.user {
         Some(user) => {
+            let Ok(today) = collective_today(default_collective_id(), &SystemClock, &pool).await
+            else {
+                return (StatusCode::INTERNAL_SERVER_ERROR, ()).into_response();
+            };
+
             let result = repo::find_initial_data_for_user(
                 default_collective_id(),
                 UserId::new(user.id),
+                today,
                 &pool,
             )
             .await;
@@ -56,6 +96,57 @@ async fn get_my_state(
     }
 }
 
+#[utoipa::path(get, path = "/history", responses(
+        (status = 200, body = ParticipationHistory),
+        (status = NOT_FOUND, description = "Not a member of the collective", body = ()),
+        (status = INTERNAL_SERVER_ERROR, description = "Internal server error", body = ()),
+    ),)]
+async fn get_my_history(
+    Extension(pool): Extension<SqlitePool>,
+    auth_session: AuthSession,
+) -> impl IntoResponse {
+    let Some(user) = auth_session.user else {
+        return (StatusCode::UNAUTHORIZED, ()).into_response();
+    };
+
+    let Ok(person_id) =
+        find_person_id_for_user(default_collective_id(), UserId::new(user.id), &pool).await
+    else {
+        return (StatusCode::NOT_FOUND, ()).into_response();
+    };
+
+    match find_participation_history(default_collective_id(), person_id, &pool).await {
+        Ok(history) => (StatusCode::OK, Json(history)).into_response(),
+        Err(_) => (StatusCode::INTERNAL_SERVER_ERROR, ()).into_response(),
+    }
+}
+
+// Tasks assigned to me that aren't done yet
+#[utoipa::path(get, path = "/tasks", responses(
+        (status = 200, body = Vec<Task>),
+        (status = NOT_FOUND, description = "Not a member of the collective", body = ()),
+        (status = INTERNAL_SERVER_ERROR, description = "Internal server error", body = ()),
+    ),)]
+async fn get_my_tasks(
+    Extension(pool): Extension<SqlitePool>,
+    auth_session: AuthSession,
+) -> impl IntoResponse {
+    let Some(user) = auth_session.user else {
+        return (StatusCode::UNAUTHORIZED, ()).into_response();
+    };
+
+    let Ok(person_id) =
+        find_person_id_for_user(default_collective_id(), UserId::new(user.id), &pool).await
+    else {
+        return (StatusCode::NOT_FOUND, ()).into_response();
+    };
+
+    match find_open_tasks_for_assignee(default_collective_id(), person_id, &pool).await {
+        Ok(tasks) => (StatusCode::OK, Json(tasks)).into_response(),
+        Err(_) => (StatusCode::INTERNAL_SERVER_ERROR, ()).into_response(),
+    }
+}
+
 #[utoipa::path(
     get,
     path = "/participation/interval/{interval_id}",
@@ -108,6 +199,8 @@ async fn my_participation(
     ),
     responses(
         (status = 200, description = "Updated my participation successfully", body = Vec<AppEvent>),
+        (status = BAD_REQUEST, description = "The check-in answers don't fit the interval's questions", body = ()),
+        (status = FORBIDDEN, description = "The interval's phase doesn't allow changes", body = ()),
         (status = NOT_FOUND, description = "Not found", body = ())
     ),
 )]
@@ -115,6 +208,7 @@ async fn update_my_participation(
     Path(interval_id): Path<i64>,
     Extension(pool): Extension<SqlitePool>,
     Extension(realtime_state): Extension<RealtimeState>,
+    Extension(resend): Extension<Resend>,
     auth_session: AuthSession,
     axum::extract::Json(input): axum::extract::Json<MyParticipationInput>,
 ) -> impl IntoResponse {
@@ -128,38 +222,418 @@ async fn update_my_participation(
             let person_id = person_id.unwrap();
             let interval_id = IntervalId::new(interval_id);
 
-            let update_result =
-                update_my_involvements(person_id.clone(), interval_id.clone(), input, &pool).await;
-
-            if update_result.is_err() {
-                eprintln!("Error updating my involvements: {:?}", update_result.err());
+            let Ok(role) = find_person_role(person_id.clone(), &pool).await else {
                 return (StatusCode::INTERNAL_SERVER_ERROR, ()).into_response();
+            };
+
+            if let Err(error) = validate_check_in(interval_id.clone(), &input, &pool).await {
+                return error.into_response();
             }
 
-            // Fetch the updated involvement to return
-            let output_result = repo::find_interval_data_for_person(
-                default_collective_id(),
+            let update_result = update_my_involvements(
+                person_id.clone(),
+                interval_id.clone(),
+                input,
+                person_id.clone(),
+                role == PersonRole::Admin,
+                &pool,
+            )
+            .await;
+
+            let saved = match update_result {
+                Ok(saved) => saved,
+                Err(error) => {
+                    eprintln!("Error updating my involvements: {:?}", error);
+                    return involvement_error_response(error);
+                }
+            };
+
+            interval_data_changed_response(
+                ParticipationEditor::Themselves { user_id: user.id },
                 person_id,
                 interval_id,
+                saved,
                 &pool,
+                &realtime_state,
+                &resend,
             )
-            .await;
-            match output_result {
-                Ok(interval_data) => {
-                    let public_interval_data = strip_private_data(&interval_data);
-                    let public_event =
-                        AppEvent::MeEvent(MeEvent::IntervalDataChanged(public_interval_data));
-                    realtime_state
-                        .broadcast_app_event_for_user(Some(user.id), public_event.clone())
-                        .await;
-
-                    let my_event = AppEvent::MeEvent(MeEvent::IntervalDataChanged(interval_data));
-                    return (StatusCode::OK, Json(vec![my_event])).into_response();
+            .await
+        }
+
+        None => return (StatusCode::UNAUTHORIZED, ()).into_response(),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/interval/{interval_id}/rollover",
+    params(
+        ("interval_id" = i64, Path, description = "Interval ID to copy my previous participation into")
+    ),
+    responses(
+        (status = 200, description = "Copied my previous participation as a draft", body = Vec<AppEvent>),
+        (status = FORBIDDEN, description = "The interval's phase doesn't allow changes", body = ()),
+        (status = NOT_FOUND, description = "No previous participation to copy", body = ()),
+        (status = CONFLICT, description = "I've already confirmed my participation in the interval", body = ())
+    ),
+)]
+async fn rollover_my_participation(
+    Path(interval_id): Path<i64>,
+    Extension(pool): Extension<SqlitePool>,
+    Extension(realtime_state): Extension<RealtimeState>,
+    Extension(resend): Extension<Resend>,
+    auth_session: AuthSession,
+) -> impl IntoResponse {
+    let Some(user) = auth_session.user else {
+        return (StatusCode::UNAUTHORIZED, ()).into_response();
+    };
+
+    let Ok(person_id) =
+        find_person_id_for_user(default_collective_id(), UserId::new(user.id), &pool).await
+    else {
+        return (StatusCode::NOT_FOUND, ()).into_response();
+    };
+    let interval_id = IntervalId::new(interval_id);
+
+    let Ok(role) = find_person_role(person_id.clone(), &pool).await else {
+        return (StatusCode::INTERNAL_SERVER_ERROR, ()).into_response();
+    };
+
+    // Rolling over would replace what's already been confirmed
+    let existing = find_collective_involvement(
+        default_collective_id(),
+        person_id.clone(),
+        interval_id.clone(),
+        &pool,
+    )
+    .await;
+    match existing {
+        Ok(Some(involvement)) if !involvement.draft => {
+            return (StatusCode::CONFLICT, "Participation already confirmed").into_response();
+        }
+        Ok(_) => {}
+        Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR, ()).into_response(),
+    }
+
+    let previous_interval =
+        match find_previous_interval(default_collective_id(), interval_id.clone(), &pool).await {
+            Ok(Some(interval)) => interval,
+            Ok(None) => return (StatusCode::NOT_FOUND, ()).into_response(),
+            Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR, ()).into_response(),
+        };
+
+    let copy_result = copy_involvements_as_draft(
+        default_collective_id(),
+        person_id.clone(),
+        previous_interval.typed_id(),
+        interval_id.clone(),
+        Some(person_id.clone()),
+        role == PersonRole::Admin,
+        &pool,
+    )
+    .await;
+
+    let saved = match copy_result {
+        Ok(saved) => saved,
+        Err(error) => {
+            eprintln!("Error copying my involvements: {:?}", error);
+            return involvement_error_response(error);
+        }
+    };
+
+    interval_data_changed_response(
+        ParticipationEditor::Themselves { user_id: user.id },
+        person_id,
+        interval_id,
+        saved,
+        &pool,
+        &realtime_state,
+        &resend,
+    )
+    .await
+}
+
+#[utoipa::path(
+    post,
+    path = "/interval/{interval_id}/status",
+    params(
+        ("interval_id" = i64, Path, description = "Interval ID")
+    ),
+    request_body(
+        content = StatusChangeInput,
+        content_type = "application/json"
+    ),
+    responses(
+        (status = 200, description = "Changed my status from the date", body = Vec<AppEvent>),
+        (status = BAD_REQUEST, description = "The status or date can't be used for a change in the interval", body = ()),
+        (status = FORBIDDEN, description = "Only the current interval can have its status changed part way through", body = ()),
+        (status = NOT_FOUND, description = "Not found", body = ())
+    ),
+)]
+async fn change_my_status(
+    Path(interval_id): Path<i64>,
+    Extension(pool): Extension<SqlitePool>,
+    Extension(realtime_state): Extension<RealtimeState>,
+    Extension(resend): Extension<Resend>,
+    auth_session: AuthSession,
+    Json(input): Json<StatusChangeInput>,
+) -> impl IntoResponse {
+    let Some(user) = auth_session.user else {
+        return (StatusCode::UNAUTHORIZED, ()).into_response();
+    };
+
+    let Ok(person_id) =
+        find_person_id_for_user(default_collective_id(), UserId::new(user.id), &pool).await
+    else {
+        return (StatusCode::NOT_FOUND, ()).into_response();
+    };
+    let interval_id = IntervalId::new(interval_id);
+
+    let interval = match find_interval(interval_id.clone(), &pool).await {
+        Ok(interval) => interval,
+        Err(sqlx::Error::RowNotFound) => return (StatusCode::NOT_FOUND, ()).into_response(),
+        Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR, ()).into_response(),
+    };
+    let Ok(today) = collective_today(default_collective_id(), &SystemClock, &pool).await else {
+        return (StatusCode::INTERNAL_SERVER_ERROR, ()).into_response();
+    };
+
+    if let Err(message) = validate_status_change(&input, &interval, today) {
+        return (StatusCode::BAD_REQUEST, message).into_response();
+    }
+
+    let change_result = change_status_from(
+        default_collective_id(),
+        person_id.clone(),
+        &interval,
+        input,
+        today,
+        &pool,
+    )
+    .await;
+
+    let saved = match change_result {
+        Ok(saved) => saved,
+        Err(error) => {
+            eprintln!("Error changing my status: {:?}", error);
+            return involvement_error_response(error);
+        }
+    };
+
+    interval_data_changed_response(
+        ParticipationEditor::Themselves { user_id: user.id },
+        person_id,
+        interval_id,
+        saved,
+        &pool,
+        &realtime_state,
+        &resend,
+    )
+    .await
+}
+
+// Checks the check-in answers against the interval's questions, returning the status and
+// reason to respond with if they don't fit. There's nothing to check when the answers aren't
+// being changed
+pub async fn validate_check_in(
+    interval_id: IntervalId,
+    input: &MyParticipationInput,
+    pool: &SqlitePool,
+) -> Result<(), (StatusCode, &'static str)> {
+    let Some(check_in_answers) = &input.check_in_answers else {
+        return Ok(());
+    };
+    let Ok(question_set) = find_question_set(default_collective_id(), interval_id, pool).await
+    else {
+        return Err((StatusCode::INTERNAL_SERVER_ERROR, "Internal server error"));
+    };
+
+    let status = calculate_status(
+        input.participation_intention.clone(),
+        input.opt_out_type.clone(),
+    );
+    validate_answers(
+        &question_set,
+        check_in_answers,
+        status == InvolvementStatus::Participating,
+    )
+    .map_err(|message| (StatusCode::BAD_REQUEST, message))
+}
+
+pub fn involvement_error_response(error: InvolvementError) -> Response {
+    match error {
+        InvolvementError::IntervalPhase { .. } => (StatusCode::FORBIDDEN, ()).into_response(),
+        InvolvementError::Database(sqlx::Error::RowNotFound) => {
+            (StatusCode::NOT_FOUND, ()).into_response()
+        }
+        InvolvementError::Database(sqlx::Error::InvalidArgument(message)) => {
+            (StatusCode::BAD_REQUEST, message).into_response()
+        }
+        InvolvementError::Database(_) => (StatusCode::INTERNAL_SERVER_ERROR, ()).into_response(),
+    }
+}
+
+// Who is changing a person's participation. Admins can make changes on someone's behalf, and
+// only get back what they're allowed to see of them
+pub enum ParticipationEditor {
+    Themselves {
+        user_id: i64,
+    },
+    Proxy {
+        user_id: i64,
+        author_id: PersonId,
+        viewer: Viewer,
+    },
+}
+
+impl ParticipationEditor {
+    fn user_id(&self) -> i64 {
+        match self {
+            ParticipationEditor::Themselves { user_id } => *user_id,
+            ParticipationEditor::Proxy { user_id, .. } => *user_id,
+        }
+    }
+
+    // The version of the change to broadcast to everyone, and the version to respond with
+    fn interval_data_events(
+        &self,
+        interval_data: PersonIntervalInvolvementData,
+    ) -> (AppEvent, AppEvent) {
+        let public_interval_data = interval_data.clone().redact_for(&Viewer::everyone());
+        match self {
+            ParticipationEditor::Themselves { .. } => (
+                AppEvent::MeEvent(MeEvent::IntervalDataChanged(public_interval_data)),
+                AppEvent::MeEvent(MeEvent::IntervalDataChanged(interval_data)),
+            ),
+            ParticipationEditor::Proxy {
+                author_id, viewer, ..
+            } => {
+                let proxy_event = |interval_data| {
+                    AppEvent::MeEvent(MeEvent::IntervalDataChangedByProxy(ProxyEdit {
+                        author_id: author_id.id,
+                        interval_data,
+                    }))
+                };
+                (
+                    proxy_event(public_interval_data),
+                    proxy_event(interval_data.redact_for(viewer)),
+                )
+            }
+        }
+    }
+}
+
+// Broadcasts the public version of a person's interval data, and responds with the version the
+// editor can see. Crew staffing, convenor changes and requests to join crews are pushed to
+// everyone as well
+pub async fn interval_data_changed_response(
+    editor: ParticipationEditor,
+    person_id: PersonId,
+    interval_id: IntervalId,
+    saved: SavedInvolvements,
+    pool: &SqlitePool,
+    realtime_state: &RealtimeState,
+    resend: &Resend,
+) -> Response {
+    let output_result = repo::find_interval_data_for_person(
+        default_collective_id(),
+        person_id.clone(),
+        interval_id.clone(),
+        pool,
+    )
+    .await;
+    let staffing_result =
+        find_staffing_report(default_collective_id(), interval_id, &SystemClock, pool).await;
+
+    match (output_result, staffing_result) {
+        (Ok(interval_data), Ok(staffing_report)) => {
+            let user_id = editor.user_id();
+            let (public_event, interval_data_event) = editor.interval_data_events(interval_data);
+            realtime_state
+                .broadcast_app_event_for_user(Some(user_id), public_event.clone())
+                .await;
+
+            let staffing_event = AppEvent::CrewsEvent(CrewsEvent::StaffingChanged(staffing_report));
+            realtime_state
+                .broadcast_app_event_for_user(Some(user_id), staffing_event.clone())
+                .await;
+
+            let mut events = vec![interval_data_event, staffing_event];
+            for change in saved.convenor_changes {
+                let event = AppEvent::CrewsEvent(CrewsEvent::ConvenorsChanged(change));
+                realtime_state
+                    .broadcast_app_event_for_user(Some(user_id), event.clone())
+                    .await;
+                events.push(event);
+            }
+
+            for request in saved.join_requests {
+                if let Err(error) =
+                    notify_join_requested(default_collective_id(), &request, resend, pool).await
+                {
+                    eprintln!("Failed to notify about join request: {:?}", error);
+                }
+
+                let event = AppEvent::CrewsEvent(CrewsEvent::JoinRequested(request));
+                realtime_state
+                    .broadcast_app_event_for_user(Some(user_id), event.clone())
+                    .await;
+                events.push(event);
+            }
+
+            if let Some(exit) = saved.exit {
+                match exit_started_events(&editor, person_id, exit, pool, realtime_state, resend)
+                    .await
+                {
+                    Ok(exit_events) => events.extend(exit_events),
+                    Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR, ()).into_response(),
                 }
-                Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR, ()).into_response(),
             }
+
+            (StatusCode::OK, Json(events)).into_response()
         }
+        _ => (StatusCode::INTERNAL_SERVER_ERROR, ()).into_response(),
+    }
+}
 
-        None => return (StatusCode::UNAUTHORIZED, ()).into_response(),
+// Lets admins know about the exit, and pushes out the handover tasks and the person's
+// crews being cleared from later intervals
+async fn exit_started_events(
+    editor: &ParticipationEditor,
+    person_id: PersonId,
+    exit: ExitStarted,
+    pool: &SqlitePool,
+    realtime_state: &RealtimeState,
+    resend: &Resend,
+) -> Result<Vec<AppEvent>, sqlx::Error> {
+    if let Err(error) = notify_exit_started(default_collective_id(), &exit, resend, pool).await {
+        eprintln!("Failed to notify admins about exit: {:?}", error);
+    }
+
+    let user_id = editor.user_id();
+    let mut events = vec![];
+    for task in exit.handover_tasks {
+        let event = AppEvent::TasksEvent(TasksEvent::TaskCreated(task));
+        realtime_state
+            .broadcast_app_event_for_user(Some(user_id), event.clone())
+            .await;
+        events.push(event);
     }
+
+    for interval_id in exit.later_interval_ids {
+        let interval_data = repo::find_interval_data_for_person(
+            default_collective_id(),
+            person_id.clone(),
+            IntervalId::new(interval_id),
+            pool,
+        )
+        .await?;
+        let (public_event, interval_data_event) = editor.interval_data_events(interval_data);
+        realtime_state
+            .broadcast_app_event_for_user(Some(user_id), public_event)
+            .await;
+        events.push(interval_data_event);
+    }
+
+    Ok(events)
 }
@@ -1,34 +1,57 @@
+use chrono::NaiveDate;
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 
 use crate::{
-    crews::repo::{
-        find_crew_involvements, intervals_participated_since_last_convened, set_crew_convenor,
+    check_ins::{answers_for_question_set, is_answered, repo::find_question_set},
+    crews::{
+        convenor_selection::{
+            selects_on_update, step_down_unavailable_convenors, update_convenor_if_needed,
+        },
+        events::{ConvenorChange, CrewJoinRequest},
+        roles::update_role_assignees_if_needed,
+    },
+    exits::workflow::{ExitStarted, cancel_exit, start_exit},
+    intervals::{
+        clock::{SystemClock, collective_today},
+        phases::can_update_participation,
+        repo::{IntervalType, find_interval, get_interval_type, parse_date_only},
     },
-    intervals::repo::{IntervalType, find_interval, get_interval_type},
     me::repo::{self},
-    my_collective::involvements_repo::{
-        CollectiveInvolvementRecord, upsert_collective_involvement,
+    my_collective::{
+        involvements_repo::{
+            CollectiveInvolvementRecord, find_all_collective_involvements,
+            find_collective_involvement, upsert_collective_involvement, upsert_status_segment,
+        },
+        repo::find_collective,
     },
     shared::entities::{
-        CrewId, CrewInvolvement, IntervalId, InvolvementStatus, OptOutType, ParticipationIntention,
-        PersonId,
+        CheckInAnswer, CollectiveId, CrewInvolvement, CrewRoleInvolvement, Interval, IntervalId,
+        IntervalPhase, InvolvementStatus, OptOutType, ParticipationIntention, PersonId,
+        StatusSegment,
     },
 };
 
+// What saving someone's participation changed for the rest of their crews
+#[derive(Default)]
+pub struct SavedInvolvements {
+    pub convenor_changes: Vec<ConvenorChange>,
+    pub join_requests: Vec<CrewJoinRequest>,
+    pub exit: Option<ExitStarted>,
+}
+
 #[derive(Serialize, Deserialize, ToSchema)]
 pub struct MyParticipationInput {
     pub collective_id: i64,
-    pub private_capacity_planning: bool,
-    pub wellbeing: Option<String>,
-    pub focus: Option<String>,
     pub capacity_score: Option<i64>,
-    pub capacity: Option<String>,
     pub participation_intention: Option<ParticipationIntention>,
     pub opt_out_type: Option<OptOutType>,
     pub opt_out_planned_return_date: Option<String>,
     pub crew_involvements: Option<Vec<CrewInvolvement>>,
     pub intention_context: Option<String>,
+    // Checked against the interval's check-in questions before saving. Leaving them out keeps
+    // whatever answers were saved before
+    pub check_in_answers: Option<Vec<CheckInAnswer>>,
 }
 
 pub fn calculate_status(
@@ -46,23 +69,214 @@ pub fn calculate_status(
     }
 }
 
+// The author is whoever made the change, which is an admin when it's made on someone's behalf
 pub async fn update_my_involvements(
     person_id: PersonId,
     interval_id: IntervalId,
     input: MyParticipationInput,
+    author_id: PersonId,
+    acting_as_admin: bool,
     pool: &sqlx::SqlitePool,
-) -> Result<(), sqlx::Error> {
+) -> Result<SavedInvolvements, InvolvementError> {
+    save_involvements(
+        person_id,
+        interval_id,
+        input,
+        false,
+        Some(author_id),
+        acting_as_admin,
+        pool,
+    )
+    .await
+}
+
+// Copies a person's crews and capacity planning from one interval into another as a
+// draft, which they can then confirm or edit. There's no author when it's done automatically
+pub async fn copy_involvements_as_draft(
+    collective_id: CollectiveId,
+    person_id: PersonId,
+    from_interval_id: IntervalId,
+    to_interval_id: IntervalId,
+    author_id: Option<PersonId>,
+    acting_as_admin: bool,
+    pool: &sqlx::SqlitePool,
+) -> Result<SavedInvolvements, InvolvementError> {
+    let source = repo::find_interval_data_for_person(
+        collective_id.clone(),
+        person_id.clone(),
+        from_interval_id,
+        pool,
+    )
+    .await?;
+
+    let Some(involvement) = source.collective_involvement else {
+        return Err(sqlx::Error::RowNotFound.into());
+    };
+    // The question set may have changed between the intervals
+    let question_set =
+        find_question_set(collective_id.clone(), to_interval_id.clone(), pool).await?;
+    let check_in_answers = answers_for_question_set(&question_set, involvement.check_in_answers);
+
+    let crew_involvements: Vec<CrewInvolvement> = source
+        .crew_involvements
+        .into_iter()
+        .map(|crew_involvement| CrewInvolvement {
+            id: -1, // ID will be auto-generated
+            interval_id: to_interval_id.id,
+            convenor: false,
+            roles: crew_involvement
+                .roles
+                .into_iter()
+                .filter(|role| role.volunteered)
+                .map(|role| CrewRoleInvolvement {
+                    assigned: false,
+                    ..role
+                })
+                .collect(),
+            ..crew_involvement
+        })
+        .collect();
+
+    let input = MyParticipationInput {
+        collective_id: collective_id.id,
+        capacity_score: involvement.capacity_score,
+        participation_intention: Some(ParticipationIntention::OptIn),
+        opt_out_type: None,
+        opt_out_planned_return_date: None,
+        crew_involvements: Some(crew_involvements),
+        intention_context: None,
+        check_in_answers: Some(check_in_answers),
+    };
+
+    save_involvements(
+        person_id,
+        to_interval_id,
+        input,
+        true,
+        author_id,
+        acting_as_admin,
+        pool,
+    )
+    .await
+}
+
+// Drafts someone as participating in an interval without any crews, which they can then
+// confirm or edit
+pub async fn draft_participation(
+    collective_id: CollectiveId,
+    person_id: PersonId,
+    interval_id: IntervalId,
+    acting_as_admin: bool,
+    pool: &sqlx::SqlitePool,
+) -> Result<SavedInvolvements, InvolvementError> {
+    let input = MyParticipationInput {
+        collective_id: collective_id.id,
+        capacity_score: None,
+        participation_intention: Some(ParticipationIntention::OptIn),
+        opt_out_type: None,
+        opt_out_planned_return_date: None,
+        crew_involvements: None,
+        intention_context: None,
+        check_in_answers: None,
+    };
+
+    save_involvements(
+        person_id,
+        interval_id,
+        input,
+        true,
+        None,
+        acting_as_admin,
+        pool,
+    )
+    .await
+}
+
+// Who was drafted into the next interval, and who couldn't be
+pub struct Prepopulated {
+    pub drafted: Vec<PersonId>,
+    pub failed: Vec<PersonId>,
+}
+
+// Drafts the next interval for everyone participating in the previous one. Anyone who
+// has already planned the next interval is left untouched, so running it again retries
+// anyone who failed.
+pub async fn prepopulate_interval(
+    collective_id: CollectiveId,
+    from_interval_id: IntervalId,
+    to_interval_id: IntervalId,
+    pool: &sqlx::SqlitePool,
+) -> Result<Prepopulated, InvolvementError> {
+    let already_planned: Vec<i64> =
+        find_all_collective_involvements(collective_id.clone(), to_interval_id.clone(), pool)
+            .await?
+            .into_iter()
+            .map(|involvement| involvement.person_id)
+            .collect();
+
+    let participating: Vec<PersonId> =
+        find_all_collective_involvements(collective_id.clone(), from_interval_id.clone(), pool)
+            .await?
+            .into_iter()
+            .filter(|involvement| involvement.status == InvolvementStatus::Participating)
+            .filter(|involvement| !already_planned.contains(&involvement.person_id))
+            .map(|involvement| PersonId::new(involvement.person_id))
+            .collect();
+
+    let mut prepopulated = Prepopulated {
+        drafted: vec![],
+        failed: vec![],
+    };
+    for person_id in participating {
+        let result = copy_involvements_as_draft(
+            collective_id.clone(),
+            person_id.clone(),
+            from_interval_id.clone(),
+            to_interval_id.clone(),
+            None,
+            true,
+            pool,
+        )
+        .await;
+        match result {
+            Ok(_) => prepopulated.drafted.push(person_id),
+            // The interval's phase stops everyone being drafted, not just this person
+            Err(error @ InvolvementError::IntervalPhase { .. }) => return Err(error),
+            Err(error) => {
+                eprintln!(
+                    "Error drafting person {} into interval {}: {:?}",
+                    person_id.id, to_interval_id.id, error
+                );
+                prepopulated.failed.push(person_id);
+            }
+        }
+    }
+
+    Ok(prepopulated)
+}
+
+async fn save_involvements(
+    person_id: PersonId,
+    interval_id: IntervalId,
+    input: MyParticipationInput,
+    draft: bool,
+    author_id: Option<PersonId>,
+    acting_as_admin: bool,
+    pool: &sqlx::SqlitePool,
+) -> Result<SavedInvolvements, InvolvementError> {
     let status: InvolvementStatus = calculate_status(
         input.participation_intention.clone(),
         input.opt_out_type.clone(),
     );
 
-    let interval = find_interval(interval_id.clone(), &pool).await?;
-    let interval_type = get_interval_type(interval);
+    let interval = find_interval(interval_id.clone(), pool).await?;
+    let today =
+        collective_today(CollectiveId::new(input.collective_id), &SystemClock, pool).await?;
 
-    if interval_type == IntervalType::Past {
-        return Err(past_interval_error());
+    if !can_update_participation(&interval, today, acting_as_admin) {
+        return Err(InvolvementError::interval_phase(&interval));
     }
+    let exiting = status == InvolvementStatus::Exiting;
 
     upsert_collective_involvement(
         CollectiveInvolvementRecord {
@@ -71,181 +285,187 @@ pub async fn update_my_involvements(
             collective_id: input.collective_id,
             interval_id: interval_id.id,
             status,
-            private_capacity_planning: input.private_capacity_planning,
-            wellbeing: input.wellbeing,
-            focus: input.focus,
             capacity_score: input.capacity_score,
-            capacity: input.capacity,
             participation_intention: input.participation_intention,
             opt_out_type: input.opt_out_type,
             opt_out_planned_return_date: input.opt_out_planned_return_date,
             intention_context: input.intention_context,
+            draft,
+            updated_by: author_id.map(|author_id| author_id.id),
         },
+        input
+            .check_in_answers
+            .map(|answers| answers.into_iter().filter(is_answered).collect()),
         pool,
     )
     .await?;
 
+    let mut saved = SavedInvolvements::default();
     if let Some(crew_involvements) = input.crew_involvements {
         // Update crew involvements
-        let impacted_crew_ids = repo::update_crew_involvements(
-            person_id,
+        let update = repo::update_crew_involvements(
+            person_id.clone(),
             interval_id.clone(),
             crew_involvements,
             &pool,
         )
         .await?;
 
-        for crew_id in impacted_crew_ids {
-            update_convenor_if_needed(crew_id, interval_id.clone(), interval_type, &pool).await?;
+        saved.join_requests = update
+            .requested_crew_ids
+            .into_iter()
+            .map(|crew_id| CrewJoinRequest {
+                crew_id: crew_id.id,
+                interval_id: interval_id.id,
+                person_id: person_id.id,
+            })
+            .collect();
+
+        let collective_id = CollectiveId::new(input.collective_id);
+        let collective = find_collective(collective_id.clone(), pool).await?;
+        if selects_on_update(collective.convenor_selection_timing, interval.phase) {
+            for crew_id in update.impacted_crew_ids {
+                let change = update_convenor_if_needed(
+                    collective_id.clone(),
+                    crew_id.clone(),
+                    interval_id.clone(),
+                    interval.phase,
+                    person_id.id,
+                    &SystemClock,
+                    pool,
+                )
+                .await?;
+                saved.convenor_changes.extend(change);
+
+                update_role_assignees_if_needed(
+                    collective_id.clone(),
+                    crew_id,
+                    interval_id.clone(),
+                    interval.phase,
+                    pool,
+                )
+                .await?;
+            }
         }
     }
-    Ok(())
-}
 
-async fn update_convenor_if_needed(
-    crew_id: CrewId,
-    interval_id: IntervalId,
-    interval_type: IntervalType,
-    pool: &sqlx::SqlitePool,
-) -> Result<(), sqlx::Error> {
-    if interval_type == IntervalType::Past {
-        return Err(past_interval_error());
+    if exiting {
+        saved.exit = start_exit(
+            CollectiveId::new(input.collective_id),
+            person_id,
+            &interval,
+            today,
+            pool,
+        )
+        .await?;
+        if let Some(exit) = &saved.exit {
+            saved
+                .convenor_changes
+                .extend(exit.convenor_changes.iter().cloned());
+        }
+    } else {
+        cancel_exit(person_id, pool).await?;
     }
 
-    let crew_involvements =
-        find_crew_involvements(crew_id.clone(), interval_id.clone(), pool).await?;
-    let convenor_involvements: Vec<&CrewInvolvement> = crew_involvements
-        .iter()
-        .filter(|involvement| involvement.convenor)
-        .collect();
+    Ok(saved)
+}
 
-    let volunteered_convenor_involvements: Vec<&CrewInvolvement> = crew_involvements
-        .iter()
-        .filter(|involvement| involvement.volunteered_convenor)
-        .collect();
+// A change of status part way through the current interval, such as starting a hiatus
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct StatusChangeInput {
+    pub status: InvolvementStatus,
+    pub effective_from: String,
+}
 
-    // If this is the current interval, and there's already a convenor, do nothing
-    if interval_type == IntervalType::Current && !convenor_involvements.is_empty() {
-        println!(
-            "Crew {} already has a convenor for interval {} of type {:?}",
-            crew_id.id, interval_id.id, interval_type
-        );
-        return Ok(());
+// Checks the change can take effect in what's left of the interval
+pub fn validate_status_change(
+    input: &StatusChangeInput,
+    interval: &Interval,
+    today: NaiveDate,
+) -> Result<(), &'static str> {
+    if !matches!(
+        input.status,
+        InvolvementStatus::Participating | InvolvementStatus::OnHiatus
+    ) {
+        return Err("Only participating and hiatus can change part way through an interval");
     }
 
-    let person_ids = volunteered_convenor_involvements
-        .iter()
-        .map(|involvement| involvement.person_id)
-        .collect::<Vec<i64>>();
-    let best_convenor =
-        get_best_convenor_person_id(crew_id.clone(), interval_id.clone(), person_ids, pool).await?;
-
-    set_crew_convenor(crew_id, interval_id, best_convenor, pool).await?;
+    let Some(effective_from) = parse_date_only(&input.effective_from) else {
+        return Err("The effective date must be a YYYY-MM-DD date");
+    };
+    let ends_after = parse_date_only(&interval.end_date).is_some_and(|end| effective_from > end);
+    if effective_from < today || ends_after {
+        return Err("The effective date must be between today and the end of the interval");
+    }
 
     Ok(())
 }
 
-async fn get_best_convenor_person_id(
-    crew_id: CrewId,
-    interval_id: IntervalId,
-    mut person_ids: Vec<i64>,
+// Changes the person's status from a date in the current interval. Past intervals can't be
+// changed, and upcoming ones are planned with `update_my_involvements` instead
+pub async fn change_status_from(
+    collective_id: CollectiveId,
+    person_id: PersonId,
+    interval: &Interval,
+    input: StatusChangeInput,
+    today: NaiveDate,
     pool: &sqlx::SqlitePool,
-) -> Result<Option<i64>, sqlx::Error> {
-    // If there are no volunteered convenors, return None
-    if person_ids.is_empty() {
-        return Ok(None);
-    }
-
-    // If there is only one volunteered convenor, return them
-    if person_ids.len() == 1 {
-        return Ok(Some(person_ids[0]));
-    }
-
-    person_ids =
-        filter_by_longest_since_convened_this_crew(person_ids, crew_id, interval_id, pool).await?;
-    if person_ids.len() == 1 {
-        return Ok(Some(person_ids[0]));
+) -> Result<SavedInvolvements, InvolvementError> {
+    let current = get_interval_type(interval.clone(), today) == IntervalType::Current;
+    if !current || !can_update_participation(interval, today, false) {
+        return Err(InvolvementError::interval_phase(interval));
     }
 
-    // If there are still multiple volunteered convenors, sort them by id and return the first one
-    person_ids.sort_unstable();
-
-    Ok(Some(person_ids[0]))
-}
-
-fn past_interval_error() -> sqlx::Error {
-    let result =
-        sqlx::Error::InvalidArgument("Cannot update involvements for a past interval".to_string());
-    eprintln!("error: {}", result);
-    result
-}
+    let Some(involvement) = find_collective_involvement(
+        collective_id.clone(),
+        person_id.clone(),
+        interval.typed_id(),
+        pool,
+    )
+    .await?
+    else {
+        return Err(sqlx::Error::RowNotFound.into());
+    };
 
-async fn filter_by_longest_since_convened_this_crew(
-    person_ids: Vec<i64>,
-    crew_id: CrewId,
-    current_interval_id: IntervalId,
-    pool: &sqlx::SqlitePool,
-) -> Result<Vec<i64>, sqlx::Error> {
-    let data = intervals_since_last_convened(
-        person_ids.clone(),
-        crew_id.clone(),
-        current_interval_id,
+    upsert_status_segment(
+        &involvement,
+        StatusSegment {
+            status: input.status,
+            effective_from: input.effective_from,
+        },
+        person_id,
         pool,
     )
     .await?;
 
-    let most_intervals: i64 = data
-        .iter()
-        .map(|result| result.intervals_since_convened)
-        .max()
-        .unwrap_or_else(|| {
-            eprintln!(
-                "No intervals since convened found for crew {} with person_ids {:?}",
-                crew_id.id, person_ids
-            );
-            0
-        });
-
-    let filtered_ids: Vec<i64> = data
-        .into_iter()
-        .filter(|record| record.intervals_since_convened == most_intervals)
-        .map(|record| record.person_id)
-        .collect();
+    let convenor_changes =
+        step_down_unavailable_convenors(collective_id, interval, today, &SystemClock, pool).await?;
 
-    if filtered_ids.is_empty() {
-        panic!(
-            "No crew members found for crew {} with intervals since convened {}",
-            crew_id.id, most_intervals
-        );
-    } else {
-        Ok(filtered_ids)
-    }
+    Ok(SavedInvolvements {
+        convenor_changes,
+        ..Default::default()
+    })
 }
 
-struct IntervalLastConvenedResult {
-    person_id: i64,
-    intervals_since_convened: i64,
+#[derive(Debug, thiserror::Error)]
+pub enum InvolvementError {
+    // The interval's phase, or it being in the past, doesn't allow the change
+    #[error("Cannot update involvements for interval {interval_id} in phase {phase:?}")]
+    IntervalPhase {
+        interval_id: i64,
+        phase: IntervalPhase,
+    },
+    #[error(transparent)]
+    Database(#[from] sqlx::Error),
 }
 
-async fn intervals_since_last_convened(
-    person_ids: Vec<i64>,
-    crew_id: CrewId,
-    current_interval_id: IntervalId,
-    pool: &sqlx::SqlitePool,
-) -> Result<Vec<IntervalLastConvenedResult>, sqlx::Error> {
-    let data =
-        intervals_participated_since_last_convened(crew_id, current_interval_id, pool).await?;
-
-    let result: Vec<IntervalLastConvenedResult> = person_ids
-        .into_iter()
-        .map(|person_id| {
-            let intervals_since_convened: i64 = data.get(&person_id).cloned().unwrap_or(0);
-            IntervalLastConvenedResult {
-                person_id,
-                intervals_since_convened,
-            }
-        })
-        .collect();
-
-    Ok(result)
+impl InvolvementError {
+    fn interval_phase(interval: &Interval) -> Self {
+        let error = InvolvementError::IntervalPhase {
+            interval_id: interval.id,
+            phase: interval.phase,
+        };
+        eprintln!("error: {}", error);
+        error
+    }
 }
@@ -1,13 +1,19 @@
+use chrono::NaiveDate;
 use serde::{Deserialize, Serialize};
-use sqlx::{QueryBuilder, Sqlite, SqlitePool};
+use sqlx::{QueryBuilder, Sqlite, SqliteConnection, SqlitePool};
 use utoipa::ToSchema;
 
 use crate::{
+    crews::repo::{
+        CrewInvolvementRecord, delete_role_assignments, find_archived_crew_ids,
+        find_crew_ids_requiring_approval, find_member_crew_ids_for_person,
+        update_role_volunteering, with_roles,
+    },
     intervals::repo::{find_current_interval, find_next_interval},
     my_collective::involvements_repo::find_collective_involvement,
     shared::entities::{
-        CollectiveId, CollectiveInvolvement, CrewId, CrewInvolvement, IntervalId, Person, PersonId,
-        UserId,
+        CollectiveId, CollectiveInvolvement, CrewId, CrewInvolvement, CrewMembership,
+        CrewRoleInvolvement, IntervalId, Person, PersonId, PersonRole, UserId,
     },
 };
 
@@ -22,6 +28,7 @@ pub struct PersonIntervalInvolvementData {
 #[derive(Serialize, Deserialize, ToSchema)]
 pub struct MyInitialData {
     pub person_id: i64,
+    pub role: PersonRole,
     pub current_interval: Option<PersonIntervalInvolvementData>,
     pub next_interval: Option<PersonIntervalInvolvementData>,
 }
@@ -50,13 +57,15 @@ pub async fn find_interval_data_for_person(
 pub async fn find_initial_data_for_user(
     collective_id: CollectiveId,
     user_id: UserId,
+    today: NaiveDate,
     pool: &SqlitePool,
 ) -> Result<MyInitialData, sqlx::Error> {
-    let current_interval = find_current_interval(collective_id.clone(), pool).await?;
+    let current_interval = find_current_interval(collective_id.clone(), today, pool).await?;
     let next_interval =
         find_next_interval(collective_id.clone(), current_interval.typed_id(), pool).await?;
     let person = find_person_for_user(collective_id.clone(), user_id, pool).await?;
     let person_id = person.typed_id();
+    let role = find_person_role(person_id.clone(), pool).await?;
 
     let current_interval_data = find_interval_data_for_person(
         collective_id.clone(),
@@ -82,18 +91,25 @@ pub async fn find_initial_data_for_user(
 
     Ok(MyInitialData {
         person_id: person_id.id,
+        role,
         current_interval: Some(current_interval_data),
         next_interval: next_interval_data,
     })
 }
 
-// Returns the ids of all potentially impacted crews
+pub struct CrewInvolvementsUpdate {
+    // All the potentially impacted crews
+    pub impacted_crew_ids: Vec<CrewId>,
+    // Crews the person has asked to join, which need approval
+    pub requested_crew_ids: Vec<CrewId>,
+}
+
 pub async fn update_crew_involvements(
     person_id: PersonId,
     interval_id: IntervalId,
     involvements: Vec<CrewInvolvement>,
     pool: &SqlitePool,
-) -> Result<Vec<CrewId>, sqlx::Error> {
+) -> Result<CrewInvolvementsUpdate, sqlx::Error> {
     let existing = find_my_crew_involvements(person_id.clone(), interval_id.clone(), pool).await?;
 
     // Ensure all the involvements have the same person_id and interval_id
@@ -109,6 +125,46 @@ pub async fn update_crew_involvements(
         .map(|i| CrewId::new(i.crew_id))
         .collect();
 
+    // Archived crews can't be joined, but existing involvements in them can be kept
+    let archived_crew_ids = find_archived_crew_ids(pool).await?;
+    for crew_id in &crew_ids {
+        let already_involved = existing.iter().any(|i| i.crew_id == crew_id.id);
+        if archived_crew_ids.contains(crew_id) && !already_involved {
+            return Err(sqlx::Error::InvalidArgument(
+                "Archived crews can't be joined".to_string(),
+            ));
+        }
+    }
+
+    // Joining a crew that requires approval makes the person a pending member, unless
+    // they've been a member before. Existing involvements keep their membership
+    let approval_crew_ids = find_crew_ids_requiring_approval(pool).await?;
+    let member_crew_ids = find_member_crew_ids_for_person(person_id.clone(), pool).await?;
+    let mut requested_crew_ids = vec![];
+    let involvements: Vec<CrewInvolvement> = involvements
+        .into_iter()
+        .map(|involvement| {
+            let crew_id = CrewId::new(involvement.crew_id);
+            let membership = match existing.iter().find(|i| i.crew_id == crew_id.id) {
+                Some(existing_involvement) => existing_involvement.membership,
+                None if approval_crew_ids.contains(&crew_id)
+                    && !member_crew_ids.contains(&crew_id) =>
+                {
+                    requested_crew_ids.push(crew_id);
+                    CrewMembership::Pending
+                }
+                None => CrewMembership::Member,
+            };
+            let convenor = involvement.convenor && membership == CrewMembership::Member;
+
+            CrewInvolvement {
+                membership,
+                convenor,
+                ..involvement
+            }
+        })
+        .collect();
+
     // Involvements to remove
     let to_remove: Vec<CrewInvolvement> = existing
         .iter()
@@ -116,17 +172,49 @@ pub async fn update_crew_involvements(
         .cloned()
         .collect();
 
-    let removed_crew_ids: Vec<CrewId> = to_remove.iter().map(|i| CrewId::new(i.id)).collect();
+    let removed_crew_ids: Vec<CrewId> = to_remove.iter().map(|i| CrewId::new(i.crew_id)).collect();
+
+    // Either every change is saved or none are
+    let mut transaction = pool.begin().await?;
 
-    println!("Deleting crew participations {:?}", to_remove);
-    delete_crew_involvements(to_remove, pool).await?;
+    for crew_id in &removed_crew_ids {
+        delete_role_assignments(
+            person_id.clone(),
+            crew_id.clone(),
+            interval_id.clone(),
+            &mut transaction,
+        )
+        .await?;
+    }
+
+    delete_crew_involvements(to_remove, &mut transaction).await?;
+
+    let role_volunteering: Vec<(CrewId, Vec<CrewRoleInvolvement>)> = involvements
+        .iter()
+        .map(|i| (CrewId::new(i.crew_id), i.roles.clone()))
+        .collect();
+
+    upsert_crew_involvements(involvements, &mut transaction).await?;
+
+    for (crew_id, roles) in role_volunteering {
+        update_role_volunteering(
+            person_id.clone(),
+            crew_id,
+            interval_id.clone(),
+            roles,
+            &mut transaction,
+        )
+        .await?;
+    }
 
-    println!("Upserting crew participations {:?}", involvements);
-    upsert_crew_involvements(involvements, pool).await?;
+    transaction.commit().await?;
 
     let impacted_crew_ids: Vec<CrewId> = crew_ids.into_iter().chain(removed_crew_ids).collect();
 
-    Ok(impacted_crew_ids)
+    Ok(CrewInvolvementsUpdate {
+        impacted_crew_ids,
+        requested_crew_ids,
+    })
 }
 
 pub async fn find_my_crew_involvements(
@@ -134,24 +222,26 @@ pub async fn find_my_crew_involvements(
     interval_id: IntervalId,
     pool: &SqlitePool,
 ) -> Result<Vec<CrewInvolvement>, sqlx::Error> {
-    sqlx::query_as!(
-        CrewInvolvement,
-        "SELECT id, person_id, crew_id, interval_id, convenor, volunteered_convenor
+    let records = sqlx::query_as!(
+        CrewInvolvementRecord,
+        "SELECT id, person_id, crew_id, interval_id, convenor, volunteered_convenor,
+            membership as \"membership: CrewMembership\"
         FROM crew_involvements
         WHERE person_id = ? AND interval_id = ?",
         person_id.id,
         interval_id.id
     )
     .fetch_all(pool)
-    .await
+    .await?;
+
+    with_roles(records, interval_id, pool).await
 }
 
 pub async fn delete_crew_involvements(
     involvements: Vec<CrewInvolvement>,
-    pool: &SqlitePool,
+    connection: &mut SqliteConnection,
 ) -> Result<(), sqlx::Error> {
     if involvements.is_empty() {
-        println!("No crew involvements to delete");
         return Ok(()); // Nothing to delete
     }
 
@@ -163,25 +253,24 @@ pub async fn delete_crew_involvements(
     }
     separated.push_unseparated(") ");
 
-    query_builder.build().execute(pool).await?;
+    query_builder.build().execute(connection).await?;
 
     Ok(())
 }
 
 pub async fn upsert_crew_involvements(
     involvements: Vec<CrewInvolvement>,
-    pool: &SqlitePool,
+    connection: &mut SqliteConnection,
 ) -> Result<(), sqlx::Error> {
     if involvements.is_empty() {
         return Ok(()); // Nothing to add
     }
 
-    let mut transaction = pool.begin().await?;
-
     for involvement in involvements {
         sqlx::query!(
-            "INSERT INTO crew_involvements (person_id, crew_id, interval_id, convenor, volunteered_convenor)
-            VALUES (?, ?, ?, ?, ?)
+            "INSERT INTO crew_involvements
+                (person_id, crew_id, interval_id, convenor, volunteered_convenor, membership)
+            VALUES (?, ?, ?, ?, ?, ?)
             ON CONFLICT (person_id, crew_id, interval_id) DO UPDATE SET
                 convenor = excluded.convenor,
                 volunteered_convenor = excluded.volunteered_convenor",
@@ -189,14 +278,13 @@ pub async fn upsert_crew_involvements(
             involvement.crew_id,
             involvement.interval_id,
             involvement.convenor,
-            involvement.volunteered_convenor
+            involvement.volunteered_convenor,
+            involvement.membership
         )
-        .execute(&mut *transaction)
+        .execute(&mut *connection)
         .await?;
     }
 
-    transaction.commit().await?;
-
     Ok(())
 }
 
@@ -237,3 +325,16 @@ pub async fn find_person_id_for_user(
     .await
     .map(|person| PersonId::new(person.id))
 }
+
+pub async fn find_person_role(
+    person_id: PersonId,
+    pool: &SqlitePool,
+) -> Result<PersonRole, sqlx::Error> {
+    sqlx::query!(
+        "SELECT role as \"role: PersonRole\" FROM people WHERE id = ?",
+        person_id.id
+    )
+    .fetch_one(pool)
+    .await
+    .map(|row| row.role)
+}
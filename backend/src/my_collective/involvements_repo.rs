@@ -1,8 +1,13 @@
+use std::collections::HashMap;
+
 use sqlx::SqlitePool;
 
-use crate::shared::entities::{
-    CollectiveId, CollectiveInvolvement, IntervalId, InvolvementStatus, OptOutType,
-    ParticipationIntention, PersonId,
+use crate::{
+    check_ins::repo::{find_answers, find_answers_for_interval, replace_answers},
+    shared::entities::{
+        CheckInAnswer, CollectiveId, CollectiveInvolvement, IntervalId, InvolvementStatus,
+        InvolvementUpdate, OptOutType, ParticipationIntention, PersonId, StatusSegment,
+    },
 };
 
 #[derive(Debug, Clone)]
@@ -12,15 +17,13 @@ pub struct CollectiveInvolvementRecord {
     pub collective_id: i64,
     pub interval_id: i64,
     pub status: InvolvementStatus,
-    pub private_capacity_planning: bool,
-    pub wellbeing: Option<String>,
-    pub focus: Option<String>,
-    pub capacity: Option<String>,
     pub capacity_score: Option<i64>,
     pub participation_intention: Option<ParticipationIntention>,
     pub opt_out_type: Option<OptOutType>,
     pub opt_out_planned_return_date: Option<String>,
     pub intention_context: Option<String>,
+    pub draft: bool,
+    pub updated_by: Option<i64>,
 }
 
 impl From<CollectiveInvolvementRecord> for CollectiveInvolvement {
@@ -31,17 +34,15 @@ impl From<CollectiveInvolvementRecord> for CollectiveInvolvement {
             collective_id: record.collective_id,
             interval_id: record.interval_id,
             status: record.status,
-            private_capacity_planning: record.private_capacity_planning,
-            capacity_planning: Some(crate::shared::entities::CapacityPlanning {
-                wellbeing: record.wellbeing,
-                focus: record.focus,
-                capacity: record.capacity,
-            }),
+            check_in_answers: vec![], // Answers and segments are loaded separately
             capacity_score: record.capacity_score,
             participation_intention: record.participation_intention,
             opt_out_type: record.opt_out_type,
             opt_out_planned_return_date: record.opt_out_planned_return_date,
             intention_context: record.intention_context,
+            draft: record.draft,
+            updated_by: record.updated_by,
+            status_segments: vec![],
         }
     }
 }
@@ -54,24 +55,13 @@ impl From<CollectiveInvolvement> for CollectiveInvolvementRecord {
             collective_id: involvement.collective_id,
             interval_id: involvement.interval_id,
             status: involvement.status,
-            private_capacity_planning: involvement.private_capacity_planning,
-            wellbeing: involvement
-                .capacity_planning
-                .as_ref()
-                .and_then(|cp| cp.wellbeing.clone()),
-            focus: involvement
-                .capacity_planning
-                .as_ref()
-                .and_then(|cp| cp.focus.clone()),
-            capacity: involvement
-                .capacity_planning
-                .as_ref()
-                .and_then(|cp| cp.capacity.clone()),
             capacity_score: involvement.capacity_score,
             participation_intention: involvement.participation_intention,
             opt_out_type: involvement.opt_out_type,
             opt_out_planned_return_date: involvement.opt_out_planned_return_date,
             intention_context: involvement.intention_context,
+            draft: involvement.draft,
+            updated_by: involvement.updated_by,
         }
     }
 }
@@ -85,11 +75,10 @@ pub async fn find_collective_involvement(
     let record: Option<CollectiveInvolvementRecord> = sqlx::query_as!(
         CollectiveInvolvementRecord,
         "SELECT id, person_id, collective_id, interval_id,
-        status as \"status: InvolvementStatus\", private_capacity_planning,
-        wellbeing, focus, capacity_score, capacity,
+        status as \"status: InvolvementStatus\", capacity_score,
         participation_intention as \"participation_intention: ParticipationIntention\",
         opt_out_type as \"opt_out_type: OptOutType\", opt_out_planned_return_date,
-        intention_context
+        intention_context, draft, updated_by
         FROM collective_involvements
         WHERE
             collective_id = ? AND
@@ -102,7 +91,17 @@ pub async fn find_collective_involvement(
     .fetch_optional(pool)
     .await?;
 
-    Ok(record.map(Into::into))
+    let Some(record) = record else {
+        return Ok(None);
+    };
+    let check_in_answers = find_answers(record.id, pool).await?;
+    let status_segments = find_status_segments(record.id, pool).await?;
+
+    Ok(Some(CollectiveInvolvement {
+        check_in_answers,
+        status_segments,
+        ..record.into()
+    }))
 }
 
 pub async fn find_all_collective_involvements(
@@ -113,11 +112,10 @@ pub async fn find_all_collective_involvements(
     let records = sqlx::query_as!(
         CollectiveInvolvementRecord,
         "SELECT id, person_id, collective_id, interval_id,
-        status as \"status: InvolvementStatus\", private_capacity_planning,
-        wellbeing, focus, capacity_score, capacity,
+        status as \"status: InvolvementStatus\", capacity_score,
         participation_intention as \"participation_intention: ParticipationIntention\",
         opt_out_type as \"opt_out_type: OptOutType\", opt_out_planned_return_date,
-        intention_context
+        intention_context, draft, updated_by
         FROM collective_involvements
         WHERE
             collective_id = ? AND
@@ -128,48 +126,202 @@ pub async fn find_all_collective_involvements(
     .fetch_all(pool)
     .await?;
 
-    Ok(records.into_iter().map(Into::into).collect())
+    let mut answers =
+        find_answers_for_interval(collective_id.clone(), interval_id.clone(), pool).await?;
+    let mut segments = find_status_segments_for_interval(collective_id, interval_id, pool).await?;
+    Ok(records
+        .into_iter()
+        .map(|record| CollectiveInvolvement {
+            check_in_answers: answers.remove(&record.id).unwrap_or_default(),
+            status_segments: segments.remove(&record.id).unwrap_or_default(),
+            ..record.into()
+        })
+        .collect())
 }
 
+// Saves the involvement and its check-in answers, recording the change in its history. The
+// saved answers are left as they are when there are none to replace them with
 pub async fn upsert_collective_involvement(
     involvement: CollectiveInvolvementRecord,
+    check_in_answers: Option<Vec<CheckInAnswer>>,
     pool: &SqlitePool,
 ) -> Result<(), sqlx::Error> {
-    let result = sqlx::query!(
-        "INSERT INTO collective_involvements (person_id, collective_id, interval_id, status, private_capacity_planning, wellbeing, focus, capacity_score, capacity, participation_intention, opt_out_type, opt_out_planned_return_date,
-        intention_context)
-        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+    let old_status = sqlx::query_scalar!(
+        "SELECT status as \"status: InvolvementStatus\"
+        FROM collective_involvements
+        WHERE person_id = ? AND collective_id = ? AND interval_id = ?",
+        involvement.person_id,
+        involvement.collective_id,
+        involvement.interval_id
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    let collective_involvement_id = sqlx::query_scalar!(
+        "INSERT INTO collective_involvements (person_id, collective_id, interval_id, status, capacity_score, participation_intention, opt_out_type, opt_out_planned_return_date,
+        intention_context, draft, updated_by)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
         ON CONFLICT(person_id, collective_id, interval_id) DO UPDATE SET
             status = excluded.status,
-            private_capacity_planning = excluded.private_capacity_planning,
-            wellbeing = excluded.wellbeing,
-            focus = excluded.focus,
             capacity_score = excluded.capacity_score,
-            capacity = excluded.capacity,
             participation_intention = excluded.participation_intention,
             opt_out_type = excluded.opt_out_type,
             opt_out_planned_return_date = excluded.opt_out_planned_return_date,
-            intention_context = excluded.intention_context",
+            intention_context = excluded.intention_context,
+            draft = excluded.draft,
+            updated_by = excluded.updated_by
+        RETURNING id",
         involvement.person_id,
         involvement.collective_id,
         involvement.interval_id,
         involvement.status,
-        involvement.private_capacity_planning,
-        involvement.wellbeing,
-        involvement.focus,
         involvement.capacity_score,
-        involvement.capacity,
         involvement.participation_intention,
         involvement.opt_out_type,
         involvement.opt_out_planned_return_date,
-        involvement.intention_context
+        involvement.intention_context,
+        involvement.draft,
+        involvement.updated_by
+    )
+    .fetch_one(pool)
+    .await?;
+
+    sqlx::query!(
+        "INSERT INTO involvement_updates (involvement_id, old_status, new_status, context, author_id)
+        VALUES (?, ?, ?, ?, ?)",
+        collective_involvement_id,
+        old_status,
+        involvement.status,
+        involvement.intention_context,
+        involvement.updated_by
     )
     .execute(pool)
     .await?;
 
-    if result.rows_affected() == 0 {
-        return Err(sqlx::Error::RowNotFound);
-    } else {
-        Ok(())
+    match check_in_answers {
+        Some(check_in_answers) => {
+            replace_answers(collective_involvement_id, check_in_answers, pool).await
+        }
+        None => Ok(()),
     }
 }
+
+// Every change to the person's involvement in the interval, oldest first
+pub async fn find_involvement_updates(
+    collective_id: CollectiveId,
+    person_id: PersonId,
+    interval_id: IntervalId,
+    pool: &SqlitePool,
+) -> Result<Vec<InvolvementUpdate>, sqlx::Error> {
+    sqlx::query_as!(
+        InvolvementUpdate,
+        "SELECT involvement_updates.id, collective_involvements.interval_id,
+            involvement_updates.occurred_at,
+            involvement_updates.old_status as \"old_status: InvolvementStatus\",
+            involvement_updates.new_status as \"new_status: InvolvementStatus\",
+            involvement_updates.context, involvement_updates.author_id
+        FROM involvement_updates
+        INNER JOIN collective_involvements
+            ON collective_involvements.id = involvement_updates.involvement_id
+        WHERE collective_involvements.collective_id = ?
+            AND collective_involvements.person_id = ?
+            AND collective_involvements.interval_id = ?
+        ORDER BY involvement_updates.id",
+        collective_id.id,
+        person_id.id,
+        interval_id.id
+    )
+    .fetch_all(pool)
+    .await
+}
+
+async fn find_status_segments(
+    collective_involvement_id: i64,
+    pool: &SqlitePool,
+) -> Result<Vec<StatusSegment>, sqlx::Error> {
+    sqlx::query_as!(
+        StatusSegment,
+        "SELECT status as \"status: InvolvementStatus\", effective_from
+        FROM involvement_status_segments
+        WHERE involvement_id = ?
+        ORDER BY effective_from",
+        collective_involvement_id
+    )
+    .fetch_all(pool)
+    .await
+}
+
+// Everyone's status segments for the interval, by collective involvement
+async fn find_status_segments_for_interval(
+    collective_id: CollectiveId,
+    interval_id: IntervalId,
+    pool: &SqlitePool,
+) -> Result<HashMap<i64, Vec<StatusSegment>>, sqlx::Error> {
+    let rows = sqlx::query!(
+        "SELECT involvement_status_segments.involvement_id,
+            involvement_status_segments.status as \"status: InvolvementStatus\",
+            involvement_status_segments.effective_from
+        FROM involvement_status_segments
+        INNER JOIN collective_involvements
+            ON collective_involvements.id = involvement_status_segments.involvement_id
+        WHERE collective_involvements.collective_id = ?
+            AND collective_involvements.interval_id = ?
+        ORDER BY involvement_status_segments.effective_from",
+        collective_id.id,
+        interval_id.id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let mut segments: HashMap<i64, Vec<StatusSegment>> = HashMap::new();
+    for row in rows {
+        segments
+            .entry(row.involvement_id)
+            .or_default()
+            .push(StatusSegment {
+                status: row.status,
+                effective_from: row.effective_from,
+            });
+    }
+
+    Ok(segments)
+}
+
+// Replaces any change already made from the same date, recording the change in the
+// involvement's history
+pub async fn upsert_status_segment(
+    involvement: &CollectiveInvolvement,
+    segment: StatusSegment,
+    author_id: PersonId,
+    pool: &SqlitePool,
+) -> Result<(), sqlx::Error> {
+    let old_status = involvement.status_on(&segment.effective_from);
+    let context = format!("From {}", segment.effective_from);
+
+    let mut transaction = pool.begin().await?;
+
+    sqlx::query!(
+        "INSERT INTO involvement_status_segments (involvement_id, status, effective_from)
+        VALUES (?, ?, ?)
+        ON CONFLICT (involvement_id, effective_from) DO UPDATE SET status = excluded.status",
+        involvement.id,
+        segment.status,
+        segment.effective_from
+    )
+    .execute(&mut *transaction)
+    .await?;
+
+    sqlx::query!(
+        "INSERT INTO involvement_updates (involvement_id, old_status, new_status, context, author_id)
+        VALUES (?, ?, ?, ?, ?)",
+        involvement.id,
+        old_status,
+        segment.status,
+        context,
+        author_id.id
+    )
+    .execute(&mut *transaction)
+    .await?;
+
+    transaction.commit().await
+}
@@ -3,7 +3,13 @@ use sqlx::SqlitePool;
 use utoipa_axum::{router::OpenApiRouter, routes};
 
 use crate::{
-    auth::auth_backend::AuthSession,
+    auth::{
+        auth_backend::AuthSession,
+        permissions::require_admin,
+        visibility::{Redact, find_viewer},
+    },
+    crews::repo::find_crew,
+    intervals::clock::{SystemClock, parse_timezone, today_in_timezone},
     my_collective::{
         events::CollectiveEvent,
         involvements_repo::find_all_collective_involvements,
@@ -12,7 +18,7 @@ use crate::{
     realtime::RealtimeState,
     shared::{
         default_collective_id,
-        entities::{Collective, IntervalId},
+        entities::{Collective, CrewId, IntervalId},
         events::AppEvent,
     },
 };
@@ -33,15 +39,26 @@ pub fn router() -> OpenApiRouter {
         (status = NOT_FOUND, description = "Collective was not found", body = ()),
         (status = INTERNAL_SERVER_ERROR, description = "Internal server error", body = ()),
     ),)]
-async fn get_collective_state(Extension(pool): Extension<SqlitePool>) -> impl IntoResponse {
+async fn get_collective_state(
+    Extension(pool): Extension<SqlitePool>,
+    auth_session: AuthSession,
+) -> impl IntoResponse {
+    let viewer = match find_viewer(&auth_session, default_collective_id(), &pool).await {
+        Ok(viewer) => viewer,
+        Err(status) => return (status, ()).into_response(),
+    };
+
     let collective_result = repo::find_collective_with_links(default_collective_id(), &pool).await;
 
     match collective_result {
         Ok(collective) => {
+            let today = today_in_timezone(&collective.timezone, &SystemClock);
             let initial_data_result =
-                repo::find_initial_data_for_collective(collective, &pool).await;
+                repo::find_initial_data_for_collective(collective, today, &pool).await;
             match initial_data_result {
-                Ok(initial_data) => (StatusCode::OK, Json(initial_data)).into_response(),
+                Ok(initial_data) => {
+                    (StatusCode::OK, Json(initial_data.redact_for(&viewer))).into_response()
+                }
                 Err(_) => (StatusCode::INTERNAL_SERVER_ERROR, ()).into_response(),
             }
         }
@@ -58,7 +75,13 @@ async fn get_collective_state(Extension(pool): Extension<SqlitePool>) -> impl In
 async fn get_involvements(
     Path(interval_id): Path<i64>,
     Extension(pool): Extension<SqlitePool>,
+    auth_session: AuthSession,
 ) -> impl IntoResponse {
+    let viewer = match find_viewer(&auth_session, default_collective_id(), &pool).await {
+        Ok(viewer) => viewer,
+        Err(status) => return (status, ()).into_response(),
+    };
+
     let interval_id = IntervalId::new(interval_id);
 
     let collective_involvements_result =
@@ -78,13 +101,15 @@ async fn get_involvements(
         crew_involvements,
     };
 
-    return (StatusCode::OK, Json(result)).into_response();
+    return (StatusCode::OK, Json(result.redact_for(&viewer))).into_response();
 }
 
 #[utoipa::path(put, path = "/",
     request_body(content = Collective, content_type = "application/json"),
     responses(
         (status = 200, body = Vec<AppEvent>),
+        (status = BAD_REQUEST, description = "Unknown timezone, or care crew", body = ()),
+        (status = FORBIDDEN, description = "Only admins can change the collective", body = ()),
         (status = INTERNAL_SERVER_ERROR, description = "Internal server error", body = ()),
     ),
 )]
@@ -94,8 +119,28 @@ pub async fn update_collective(
     Extension(realtime_state): Extension<RealtimeState>,
     Json(input): Json<Collective>,
 ) -> impl IntoResponse {
+    if let Err(status) = require_admin(&auth_session, default_collective_id(), &pool).await {
+        return (status, ()).into_response();
+    }
+
     println!("Updating collective: {:?}", input);
 
+    if parse_timezone(&input.timezone).is_none() {
+        return (StatusCode::BAD_REQUEST, "Unknown timezone").into_response();
+    }
+
+    // The care crew can see what people share for care, so it has to be one of this
+    // collective's own crews
+    if let Some(care_crew_id) = input.care_crew_id {
+        match find_crew(default_collective_id(), CrewId::new(care_crew_id), &pool).await {
+            Ok(crew) if crew.collective_id == default_collective_id().id && !crew.archived => {}
+            Ok(_) | Err(sqlx::Error::RowNotFound) => {
+                return (StatusCode::BAD_REQUEST, "Care crew not found").into_response();
+            }
+            Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR, ()).into_response(),
+        }
+    }
+
     match repo::update_collective_with_links(input, default_collective_id(), &pool).await {
         Ok(response) => {
             let event = AppEvent::CollectiveEvent(CollectiveEvent::CollectiveUpdated(response));
@@ -1,18 +1,20 @@
+use chrono::NaiveDate;
 use serde::{Deserialize, Serialize};
 use sqlx::SqlitePool;
 use utoipa::ToSchema;
 
 use crate::{
-    crews::repo::find_all_crews_with_links,
+    crews::repo::{CrewInvolvementRecord, find_all_crews_with_links, with_roles},
     entry_pathways::repo::find_all_entry_pathways_for_collective,
-    intervals::repo::{find_current_interval, find_next_interval},
+    intervals::repo::{find_all_intervals, find_current_interval, find_next_interval},
     my_collective::involvements_repo::find_all_collective_involvements,
     people::repo::find_all_people,
     shared::{
         default_collective_id,
         entities::{
-            Collective, CollectiveId, CollectiveInvolvement, CrewInvolvement, CrewWithLinks,
-            EntryPathway, Interval, IntervalId, Person,
+            Collective, CollectiveId, CollectiveInvolvement, ConvenorSelectionTiming,
+            CrewInvolvement, CrewMembership, CrewWithLinks, EntryPathway, Interval, IntervalId,
+            Person,
         },
         links_repo::{find_all_links_for_owner, update_links_for_owner},
     },
@@ -47,7 +49,9 @@ pub async fn find_collective(
     pool: &SqlitePool,
 ) -> Result<Collective, sqlx::Error> {
     sqlx::query!(
-        "SELECT id, name, noun_name, description, slug, feature_eoi, eoi_description
+        "SELECT id, name, noun_name, description, slug, feature_eoi, eoi_description, timezone,
+            convenor_selection_timing as \"convenor_selection_timing: ConvenorSelectionTiming\",
+            exit_grace_period_days, care_crew_id
         FROM collectives WHERE id = ?",
         collective_id.id
     )
@@ -62,6 +66,10 @@ pub async fn find_collective(
         slug: row.slug,
         feature_eoi: row.feature_eoi,
         eoi_description: row.eoi_description,
+        timezone: row.timezone,
+        convenor_selection_timing: row.convenor_selection_timing,
+        exit_grace_period_days: row.exit_grace_period_days,
+        care_crew_id: row.care_crew_id,
     })
 }
 
@@ -70,7 +78,9 @@ pub async fn find_collective_by_slug(
     pool: &SqlitePool,
 ) -> Result<Collective, sqlx::Error> {
     sqlx::query!(
-        "SELECT id, name, noun_name, description, slug, feature_eoi, eoi_description
+        "SELECT id, name, noun_name, description, slug, feature_eoi, eoi_description, timezone,
+            convenor_selection_timing as \"convenor_selection_timing: ConvenorSelectionTiming\",
+            exit_grace_period_days, care_crew_id
         FROM collectives WHERE slug = ?",
         collective_slug
     )
@@ -85,6 +95,10 @@ pub async fn find_collective_by_slug(
         slug: row.slug,
         feature_eoi: row.feature_eoi,
         eoi_description: row.eoi_description,
+        timezone: row.timezone,
+        convenor_selection_timing: row.convenor_selection_timing,
+        exit_grace_period_days: row.exit_grace_period_days,
+        care_crew_id: row.care_crew_id,
     })
 }
 
@@ -104,6 +118,10 @@ pub async fn find_collective_with_links(
         slug: collective.slug,
         feature_eoi: collective.feature_eoi,
         eoi_description: collective.eoi_description,
+        timezone: collective.timezone,
+        convenor_selection_timing: collective.convenor_selection_timing,
+        exit_grace_period_days: collective.exit_grace_period_days,
+        care_crew_id: collective.care_crew_id,
     })
 }
 
@@ -111,16 +129,19 @@ pub async fn find_all_crew_involvements(
     interval_id: IntervalId,
     pool: &SqlitePool,
 ) -> Result<Vec<CrewInvolvement>, sqlx::Error> {
-    sqlx::query_as!(
-        CrewInvolvement,
-        "SELECT crew_involvements.id, person_id, crew_id, interval_id, convenor, volunteered_convenor
+    let records = sqlx::query_as!(
+        CrewInvolvementRecord,
+        "SELECT crew_involvements.id, person_id, crew_id, interval_id, convenor, volunteered_convenor,
+            membership as \"membership: CrewMembership\"
         FROM crew_involvements
         WHERE
           interval_id = ?",
         interval_id.id
     )
     .fetch_all(pool)
-    .await
+    .await?;
+
+    with_roles(records, interval_id, pool).await
 }
 
 async fn find_interval_involvement_data(
@@ -141,25 +162,23 @@ async fn find_interval_involvement_data(
 
 pub async fn find_initial_data_for_collective(
     collective: Collective,
+    today: NaiveDate,
     pool: &SqlitePool,
 ) -> Result<InitialData, sqlx::Error> {
     let people = find_all_people(collective.typed_id(), pool).await?;
 
-    let crews = find_all_crews_with_links(collective.typed_id(), pool).await?;
-
-    let intervals = sqlx::query_as!(
-        Interval,
-        "SELECT id, start_date, end_date FROM intervals WHERE collective_id = ?",
-        collective.id
-    )
-    .fetch_all(pool)
-    .await?;
+    let intervals = find_all_intervals(collective.typed_id(), pool).await?;
 
-    let current_interval = find_current_interval(collective.typed_id(), pool).await?;
+    let current_interval = find_current_interval(collective.typed_id(), today, pool).await?;
     let current_interval_id = current_interval.typed_id().clone();
     let next_interval =
         find_next_interval(collective.typed_id(), current_interval_id.clone(), pool).await?;
 
+    let mut viewed_interval_ids = vec![current_interval_id.clone()];
+    viewed_interval_ids.extend(next_interval.as_ref().map(Interval::typed_id));
+    let crews =
+        find_all_crews_with_links(collective.typed_id(), &viewed_interval_ids, pool).await?;
+
     let current_interval_data =
         find_interval_involvement_data(current_interval_id.clone(), pool).await?;
     let next_interval_data = if let Some(interval) = next_interval {
@@ -192,7 +211,8 @@ pub async fn update_collective(
 ) -> Result<Collective, sqlx::Error> {
     sqlx::query!(
         "UPDATE collectives
-         SET name = ?, noun_name = ?, description = ?, slug = ?, feature_eoi = ?, eoi_description = ?
+         SET name = ?, noun_name = ?, description = ?, slug = ?, feature_eoi = ?, eoi_description = ?, timezone = ?,
+             convenor_selection_timing = ?, exit_grace_period_days = ?, care_crew_id = ?
          WHERE id = ?",
         input.name,
         input.noun_name,
@@ -200,6 +220,10 @@ pub async fn update_collective(
         input.slug,
         input.feature_eoi,
         input.eoi_description,
+        input.timezone,
+        input.convenor_selection_timing,
+        input.exit_grace_period_days,
+        input.care_crew_id,
         collective_id.id
     )
     .execute(pool)
@@ -231,5 +255,9 @@ pub async fn update_collective_with_links(
         slug: collective.slug,
         feature_eoi: collective.feature_eoi,
         eoi_description: collective.eoi_description,
+        timezone: collective.timezone,
+        convenor_selection_timing: collective.convenor_selection_timing,
+        exit_grace_period_days: collective.exit_grace_period_days,
+        care_crew_id: collective.care_crew_id,
     })
 }
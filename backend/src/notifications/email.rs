@@ -0,0 +1,34 @@
+use resend_rs::{
+    Resend,
+    types::{CreateEmailBaseOptions, CreateEmailResponse},
+};
+
+pub async fn notification_email(
+    resend: &Resend,
+    email: String,
+    subject: &str,
+    message: &str,
+) -> Result<CreateEmailResponse, resend_rs::Error> {
+    let from = "RADicalise <noreply@radicalise.radhousing.org>";
+    let to = [email];
+
+    let base_url =
+        std::env::var("BASE_URL").unwrap_or_else(|_| "http://localhost:5173".to_string());
+
+    let html_content = format!(
+        "<p>{}</p><p><a href=\"{}\">Open RADicalise</a></p>",
+        html_escape(message),
+        base_url
+    );
+
+    let email = CreateEmailBaseOptions::new(from, to, subject).with_html(&html_content);
+
+    resend.emails.send(email).await
+}
+
+fn html_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,59 @@
+use resend_rs::Resend;
+use sqlx::SqlitePool;
+
+use crate::{notifications::repo::find_email_for_person, shared::entities::PersonId};
+
+pub mod email;
+pub mod repo;
+
+pub struct Notification {
+    pub subject: String,
+    pub message: String,
+}
+
+// Emails everyone about something that needs their attention. Failures are logged rather
+// than returned, so a bad address never stops the change that caused the notification
+pub async fn notify_people(
+    person_ids: Vec<PersonId>,
+    notification: &Notification,
+    resend: &Resend,
+    pool: &SqlitePool,
+) {
+    for person_id in person_ids {
+        notify_person(person_id, notification, resend, pool).await;
+    }
+}
+
+// Returns false if the email couldn't be sent, so it can be tried again. Anyone without an
+// email has nothing to retry
+pub async fn notify_person(
+    person_id: PersonId,
+    notification: &Notification,
+    resend: &Resend,
+    pool: &SqlitePool,
+) -> bool {
+    let email = match find_email_for_person(person_id.clone(), pool).await {
+        Ok(Some(email)) => email,
+        Ok(None) => {
+            println!("No email to notify person {}", person_id.id);
+            return true;
+        }
+        Err(error) => {
+            eprintln!(
+                "Failed to find email for person {}: {:?}",
+                person_id.id, error
+            );
+            return false;
+        }
+    };
+
+    match email::notification_email(resend, email, &notification.subject, &notification.message)
+        .await
+    {
+        Ok(_) => true,
+        Err(error) => {
+            eprintln!("Failed to notify person {}: {}", person_id.id, error);
+            false
+        }
+    }
+}
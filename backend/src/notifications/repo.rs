@@ -0,0 +1,31 @@
+use sqlx::SqlitePool;
+
+use crate::shared::entities::{CollectiveId, PersonId};
+
+pub async fn find_email_for_person(
+    person_id: PersonId,
+    pool: &SqlitePool,
+) -> Result<Option<String>, sqlx::Error> {
+    sqlx::query!(
+        "SELECT users.email FROM people
+        INNER JOIN users ON users.id = people.user_id
+        WHERE people.id = ?",
+        person_id.id
+    )
+    .fetch_optional(pool)
+    .await
+    .map(|row| row.and_then(|row| row.email))
+}
+
+pub async fn find_admin_person_ids(
+    collective_id: CollectiveId,
+    pool: &SqlitePool,
+) -> Result<Vec<PersonId>, sqlx::Error> {
+    sqlx::query_as!(
+        PersonId,
+        "SELECT id FROM people WHERE collective_id = ? AND role = 'Admin'",
+        collective_id.id
+    )
+    .fetch_all(pool)
+    .await
+}
@@ -1,19 +1,44 @@
 use axum::{Extension, Json, extract::Path, http::StatusCode, response::IntoResponse};
+use resend_rs::Resend;
 use sqlx::SqlitePool;
 use utoipa_axum::{router::OpenApiRouter, routes};
 
 use crate::{
-    auth::auth_backend::AuthSession,
-    people::events::PeopleEvent,
+    auth::{
+        auth_backend::AuthSession,
+        permissions::{find_acting_person, require_admin},
+        visibility::{Redact, Viewer},
+    },
+    me::{
+        ParticipationEditor,
+        history::{ParticipationHistory, find_participation_history},
+        interval_data_changed_response, involvement_error_response,
+        my_involvement::{MyParticipationInput, update_my_involvements},
+        validate_check_in,
+    },
+    my_collective::involvements_repo::find_involvement_updates,
+    people::{
+        events::PeopleEvent,
+        proxy::{notify_proxy_edit, with_hidden_answers},
+    },
     realtime::RealtimeState,
-    shared::{default_collective_id, entities::Person, events::AppEvent},
+    shared::{
+        default_collective_id,
+        entities::{IntervalId, InvolvementUpdate, Person, PersonId},
+        events::AppEvent,
+    },
 };
 
 pub mod events;
+pub mod proxy;
 pub mod repo;
 
 pub fn router() -> OpenApiRouter {
-    OpenApiRouter::new().routes(routes!(update_person))
+    OpenApiRouter::new()
+        .routes(routes!(update_person))
+        .routes(routes!(get_person_history))
+        .routes(routes!(update_participation_for_person))
+        .routes(routes!(get_person_timeline))
 }
 
 #[utoipa::path(put, path = "/{person_id}",
@@ -48,3 +73,199 @@ pub async fn update_person(
         Err(_) => (StatusCode::INTERNAL_SERVER_ERROR, ()).into_response(),
     }
 }
+
+// Admins see someone's full history, while other members don't see capacity planning
+// they've chosen to keep private
+#[utoipa::path(get, path = "/{person_id}/history",
+    params(
+        ("person_id" = i64, Path, description = "Person ID")
+    ),
+    responses(
+        (status = 200, body = ParticipationHistory),
+        (status = NOT_FOUND, description = "Person not found", body = ()),
+        (status = INTERNAL_SERVER_ERROR, description = "Internal server error", body = ()),
+    ),
+)]
+pub async fn get_person_history(
+    Path(person_id): Path<i64>,
+    Extension(pool): Extension<SqlitePool>,
+    auth_session: AuthSession,
+) -> impl IntoResponse {
+    let acting_person =
+        match find_acting_person(&auth_session, default_collective_id(), &pool).await {
+            Ok(acting_person) => acting_person,
+            Err(status) => return (status, ()).into_response(),
+        };
+
+    let person_id = PersonId::new(person_id);
+    match repo::find_person_by_id(person_id.clone(), default_collective_id(), &pool).await {
+        Ok(_) => {}
+        Err(sqlx::Error::RowNotFound) => return (StatusCode::NOT_FOUND, ()).into_response(),
+        Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR, ()).into_response(),
+    }
+
+    let Ok(viewer) = Viewer::for_person(&acting_person, default_collective_id(), &pool).await
+    else {
+        return (StatusCode::INTERNAL_SERVER_ERROR, ()).into_response();
+    };
+
+    match find_participation_history(default_collective_id(), person_id, &pool).await {
+        Ok(history) => (StatusCode::OK, Json(history.redact_for(&viewer))).into_response(),
+        Err(_) => (StatusCode::INTERNAL_SERVER_ERROR, ()).into_response(),
+    }
+}
+
+// How someone's involvement in an interval changed while it was being planned
+#[utoipa::path(get, path = "/{person_id}/timeline/interval/{interval_id}",
+    params(
+        ("person_id" = i64, Path, description = "Person ID"),
+        ("interval_id" = i64, Path, description = "Interval ID")
+    ),
+    responses(
+        (status = 200, body = Vec<InvolvementUpdate>),
+        (status = NOT_FOUND, description = "Person not found", body = ()),
+        (status = INTERNAL_SERVER_ERROR, description = "Internal server error", body = ()),
+    ),
+)]
+pub async fn get_person_timeline(
+    Path((person_id, interval_id)): Path<(i64, i64)>,
+    Extension(pool): Extension<SqlitePool>,
+    auth_session: AuthSession,
+) -> impl IntoResponse {
+    if let Err(status) = find_acting_person(&auth_session, default_collective_id(), &pool).await {
+        return (status, ()).into_response();
+    }
+
+    let person_id = PersonId::new(person_id);
+    match repo::find_person_by_id(person_id.clone(), default_collective_id(), &pool).await {
+        Ok(_) => {}
+        Err(sqlx::Error::RowNotFound) => return (StatusCode::NOT_FOUND, ()).into_response(),
+        Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR, ()).into_response(),
+    }
+
+    match find_involvement_updates(
+        default_collective_id(),
+        person_id,
+        IntervalId::new(interval_id),
+        &pool,
+    )
+    .await
+    {
+        Ok(updates) => (StatusCode::OK, Json(updates)).into_response(),
+        Err(_) => (StatusCode::INTERNAL_SERVER_ERROR, ()).into_response(),
+    }
+}
+
+// Lets admins record someone's participation for them, like when they've shared their plans
+// in person. The person is told about the change.
+#[utoipa::path(put, path = "/{person_id}/participation/interval/{interval_id}",
+    params(
+        ("person_id" = i64, Path, description = "Person ID"),
+        ("interval_id" = i64, Path, description = "Interval ID")
+    ),
+    request_body(content = MyParticipationInput, content_type = "application/json"),
+    responses(
+        (status = 200, body = Vec<AppEvent>),
+        (status = BAD_REQUEST, description = "The check-in answers don't fit the interval's questions", body = ()),
+        (status = FORBIDDEN, description = "Only admins can change someone else's participation", body = ()),
+        (status = NOT_FOUND, description = "Person or interval not found", body = ()),
+        (status = INTERNAL_SERVER_ERROR, description = "Internal server error", body = ()),
+    ),
+)]
+pub async fn update_participation_for_person(
+    Path((person_id, interval_id)): Path<(i64, i64)>,
+    Extension(pool): Extension<SqlitePool>,
+    Extension(realtime_state): Extension<RealtimeState>,
+    Extension(resend): Extension<Resend>,
+    auth_session: AuthSession,
+    Json(mut input): Json<MyParticipationInput>,
+) -> impl IntoResponse {
+    let admin = match require_admin(&auth_session, default_collective_id(), &pool).await {
+        Ok(admin) => admin,
+        Err(status) => return (status, ()).into_response(),
+    };
+    let Some(user) = auth_session.user else {
+        return (StatusCode::UNAUTHORIZED, ()).into_response();
+    };
+
+    let person_id = PersonId::new(person_id);
+    let interval_id = IntervalId::new(interval_id);
+    match repo::find_person_by_id(person_id.clone(), default_collective_id(), &pool).await {
+        Ok(_) => {}
+        Err(sqlx::Error::RowNotFound) => return (StatusCode::NOT_FOUND, ()).into_response(),
+        Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR, ()).into_response(),
+    }
+
+    let Ok(viewer) = Viewer::for_person(&admin, default_collective_id(), &pool).await else {
+        return (StatusCode::INTERNAL_SERVER_ERROR, ()).into_response();
+    };
+    if let Some(answers) = input.check_in_answers.take() {
+        input.check_in_answers = match with_hidden_answers(
+            default_collective_id(),
+            person_id.clone(),
+            interval_id.clone(),
+            answers,
+            &viewer,
+            &pool,
+        )
+        .await
+        {
+            Ok(answers) => Some(answers),
+            Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR, ()).into_response(),
+        };
+    }
+
+    if let Err(error) = validate_check_in(interval_id.clone(), &input, &pool).await {
+        return error.into_response();
+    }
+
+    println!(
+        "Admin {} updating participation for person {} in interval {}",
+        admin.person_id.id, person_id.id, interval_id.id
+    );
+
+    let saved = match update_my_involvements(
+        person_id.clone(),
+        interval_id.clone(),
+        input,
+        admin.person_id.clone(),
+        true,
+        &pool,
+    )
+    .await
+    {
+        Ok(saved) => saved,
+        Err(error) => {
+            eprintln!("Error updating involvements for person: {:?}", error);
+            return involvement_error_response(error);
+        }
+    };
+
+    if let Err(error) = notify_proxy_edit(
+        default_collective_id(),
+        person_id.clone(),
+        admin.person_id.clone(),
+        interval_id.clone(),
+        &resend,
+        &pool,
+    )
+    .await
+    {
+        eprintln!("Failed to notify person about proxy edit: {:?}", error);
+    }
+
+    interval_data_changed_response(
+        ParticipationEditor::Proxy {
+            user_id: user.id,
+            author_id: admin.person_id,
+            viewer,
+        },
+        person_id,
+        interval_id,
+        saved,
+        &pool,
+        &realtime_state,
+        &resend,
+    )
+    .await
+}
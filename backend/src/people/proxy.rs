@@ -0,0 +1,65 @@
+use resend_rs::Resend;
+use sqlx::SqlitePool;
+
+use crate::{
+    auth::visibility::Viewer,
+    intervals::repo::find_interval,
+    my_collective::involvements_repo::find_collective_involvement,
+    notifications::{Notification, notify_people},
+    people::repo::find_person_by_id,
+    shared::entities::{CheckInAnswer, CollectiveId, IntervalId, PersonId},
+};
+
+// Adds back any of the person's answers the admin isn't allowed to see, so an edit on their
+// behalf doesn't clear them. Anything the admin answered themselves takes precedence.
+pub async fn with_hidden_answers(
+    collective_id: CollectiveId,
+    person_id: PersonId,
+    interval_id: IntervalId,
+    answers: Vec<CheckInAnswer>,
+    viewer: &Viewer,
+    pool: &SqlitePool,
+) -> Result<Vec<CheckInAnswer>, sqlx::Error> {
+    let Some(involvement) =
+        find_collective_involvement(collective_id, person_id.clone(), interval_id, pool).await?
+    else {
+        return Ok(answers);
+    };
+
+    let hidden_answers: Vec<CheckInAnswer> = involvement
+        .check_in_answers
+        .into_iter()
+        .filter(|hidden| {
+            !viewer.can_see(hidden.visibility, person_id.id, involvement.interval_id)
+                && !answers
+                    .iter()
+                    .any(|answer| answer.question_key == hidden.question_key)
+        })
+        .collect();
+
+    Ok(answers.into_iter().chain(hidden_answers).collect())
+}
+
+// Lets the person know an admin changed their participation, so they can check it's right
+pub async fn notify_proxy_edit(
+    collective_id: CollectiveId,
+    person_id: PersonId,
+    author_id: PersonId,
+    interval_id: IntervalId,
+    resend: &Resend,
+    pool: &SqlitePool,
+) -> Result<(), sqlx::Error> {
+    let author = find_person_by_id(author_id, collective_id, pool).await?;
+    let interval = find_interval(interval_id, pool).await?;
+
+    let notification = Notification {
+        subject: format!("{} updated your participation", author.display_name),
+        message: format!(
+            "{} updated your participation for the interval starting {} on your behalf. Have a look to check it's right.",
+            author.display_name, interval.start_date
+        ),
+    };
+    notify_people(vec![person_id], &notification, resend, pool).await;
+
+    Ok(())
+}
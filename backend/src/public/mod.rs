@@ -10,6 +10,7 @@ pub fn router() -> OpenApiRouter {
         .routes(routes!(crate::entry_pathways::create_eoi))
         .routes(routes!(crate::entry_pathways::update_eoi))
         .routes(routes!(crate::entry_pathways::get_eoi_by_auth_token))
+        .routes(routes!(crate::calendar::get_calendar_feed))
 }
 
 #[utoipa::path(get, path = "/collective/by_slug/{collective_slug}", responses(
@@ -53,6 +53,12 @@ impl RealtimeState {
         }
     }
 
+    // Lets tests see what's broadcast
+    #[cfg(test)]
+    pub async fn subscribe(&self) -> Receiver<AuthoredAppEvent> {
+        self.broadcast_tx.lock().await.subscribe()
+    }
+
     fn get_user_id_from_session(&self, session: Option<AuthSession>) -> Option<i64> {
         session.map(|s| s.user.map(|u| u.id)).flatten()
     }
@@ -23,6 +23,19 @@ pub struct Collective {
     pub slug: Option<String>,
     pub feature_eoi: bool,
     pub eoi_description: Option<String>,
+    pub timezone: String,
+    #[serde(default)]
+    pub convenor_selection_timing: ConvenorSelectionTiming,
+    // How long someone who exits keeps their account, to finish handing over
+    #[serde(default = "default_exit_grace_period_days")]
+    pub exit_grace_period_days: i64,
+    // The crew trusted with people's capacity planning when they share it for care
+    #[serde(default)]
+    pub care_crew_id: Option<i64>,
+}
+
+fn default_exit_grace_period_days() -> i64 {
+    30
 }
 
 impl Collective {
@@ -68,6 +81,32 @@ impl Person {
     }
 }
 
+#[derive(Serialize, Deserialize, ToSchema, sqlx::Type, Clone, Debug, PartialEq)]
+pub enum PersonRole {
+    Member,
+    Admin,
+}
+
+impl FromStr for PersonRole {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "Member" => Ok(PersonRole::Member),
+            "Admin" => Ok(PersonRole::Admin),
+            _ => Err(()),
+        }
+    }
+}
+
+impl TryFrom<String> for PersonRole {
+    type Error = ();
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        PersonRole::from_str(&value)
+    }
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub struct IntervalId {
     pub id: i64,
@@ -82,8 +121,13 @@ impl IntervalId {
 #[derive(Serialize, Deserialize, ToSchema, Debug, Clone)]
 pub struct Interval {
     pub id: i64,
+    #[serde(default)]
+    pub sequence: i64,
     pub start_date: String,
     pub end_date: String,
+    #[serde(default)]
+    pub phase: IntervalPhase,
+    pub participation_deadline: Option<String>,
 }
 
 impl Interval {
@@ -92,7 +136,102 @@ impl Interval {
     }
 }
 
-#[derive(Serialize, Deserialize, ToSchema, sqlx::Type, Clone, Debug)]
+// Phases are declared in the order an interval moves through them
+#[derive(
+    Serialize, Deserialize, ToSchema, sqlx::Type, Clone, Copy, Debug, PartialEq, PartialOrd, Default,
+)]
+pub enum IntervalPhase {
+    #[default]
+    Draft,
+    OpenForPlanning,
+    Locked,
+    Active,
+    Closed,
+}
+
+impl FromStr for IntervalPhase {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "Draft" => Ok(IntervalPhase::Draft),
+            "OpenForPlanning" => Ok(IntervalPhase::OpenForPlanning),
+            "Locked" => Ok(IntervalPhase::Locked),
+            "Active" => Ok(IntervalPhase::Active),
+            "Closed" => Ok(IntervalPhase::Closed),
+            _ => Err(()),
+        }
+    }
+}
+
+impl TryFrom<String> for IntervalPhase {
+    type Error = ();
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        IntervalPhase::from_str(&value)
+    }
+}
+
+// When convenors are automatically chosen for an interval's crews
+#[derive(Serialize, Deserialize, ToSchema, sqlx::Type, Clone, Copy, Debug, PartialEq, Default)]
+pub enum ConvenorSelectionTiming {
+    #[default]
+    OnEveryUpdate,
+    OnLock,
+}
+
+impl FromStr for ConvenorSelectionTiming {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "OnEveryUpdate" => Ok(ConvenorSelectionTiming::OnEveryUpdate),
+            "OnLock" => Ok(ConvenorSelectionTiming::OnLock),
+            _ => Err(()),
+        }
+    }
+}
+
+impl TryFrom<String> for ConvenorSelectionTiming {
+    type Error = ();
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        ConvenorSelectionTiming::from_str(&value)
+    }
+}
+
+#[derive(Serialize, Deserialize, ToSchema, sqlx::Type, Clone, Copy, Debug, PartialEq, Default)]
+pub enum ConvenorStrategy {
+    #[default]
+    LongestSinceConvened,
+    RoundRobin,
+    CapacityWeighted,
+    ManualOnly,
+}
+
+impl FromStr for ConvenorStrategy {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "LongestSinceConvened" => Ok(ConvenorStrategy::LongestSinceConvened),
+            "RoundRobin" => Ok(ConvenorStrategy::RoundRobin),
+            "CapacityWeighted" => Ok(ConvenorStrategy::CapacityWeighted),
+            "ManualOnly" => Ok(ConvenorStrategy::ManualOnly),
+            _ => Err(()),
+        }
+    }
+}
+
+impl TryFrom<String> for ConvenorStrategy {
+    type Error = ();
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        ConvenorStrategy::from_str(&value)
+    }
+}
+
+#[derive(Serialize, Deserialize, ToSchema, sqlx::Type, Clone, Debug, PartialEq)]
 pub enum InvolvementStatus {
     Participating,
     OnHiatus,
@@ -191,13 +330,173 @@ pub struct CrewInvolvement {
     pub interval_id: i64,
     pub convenor: bool,
     pub volunteered_convenor: bool,
+    #[serde(default)]
+    pub roles: Vec<CrewRoleInvolvement>,
+    // Only ever set by the server, as joining some crews needs approval
+    #[serde(default)]
+    pub membership: CrewMembership,
 }
 
-#[derive(Serialize, Deserialize, ToSchema, Clone, Debug, sqlx::Type, sqlx::Decode)]
-pub struct CapacityPlanning {
-    pub wellbeing: Option<String>,
-    pub focus: Option<String>,
-    pub capacity: Option<String>,
+#[derive(Serialize, Deserialize, ToSchema, sqlx::Type, Clone, Copy, Debug, PartialEq, Default)]
+pub enum CrewMembership {
+    #[default]
+    Member,
+    // Waiting for a convenor or admin to approve their request to join
+    Pending,
+}
+
+impl FromStr for CrewMembership {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "Member" => Ok(CrewMembership::Member),
+            "Pending" => Ok(CrewMembership::Pending),
+            _ => Err(()),
+        }
+    }
+}
+
+impl TryFrom<String> for CrewMembership {
+    type Error = ();
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        CrewMembership::from_str(&value)
+    }
+}
+
+// A person's part in one of a crew's roles for an interval
+#[derive(Serialize, Deserialize, ToSchema, Clone, Debug)]
+pub struct CrewRoleInvolvement {
+    pub crew_role_id: i64,
+    pub assigned: bool,
+    pub volunteered: bool,
+}
+
+// A responsibility within a crew, like note-taker or treasurer. Roles with a strategy other
+// than ManualOnly rotate automatically between the people who volunteer, like convenors do
+#[derive(Serialize, Deserialize, ToSchema, Clone, Debug)]
+pub struct CrewRole {
+    pub id: i64,
+    pub crew_id: i64,
+    pub name: String,
+    pub strategy: ConvenorStrategy,
+}
+
+// Who, other than the person themselves, can see one of their check-in answers. Anything
+// saved without saying defaults to private, so nothing is shared by accident
+#[derive(Serialize, Deserialize, ToSchema, sqlx::Type, Clone, Copy, Debug, PartialEq, Default)]
+pub enum VisibilityLevel {
+    #[default]
+    OnlyMe,
+    // Convenors of the crews the person is in for the interval
+    CrewConvenors,
+    // Admins, and the collective's care crew
+    Care,
+    Collective,
+}
+
+impl FromStr for VisibilityLevel {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "OnlyMe" => Ok(VisibilityLevel::OnlyMe),
+            "CrewConvenors" => Ok(VisibilityLevel::CrewConvenors),
+            "Care" => Ok(VisibilityLevel::Care),
+            "Collective" => Ok(VisibilityLevel::Collective),
+            _ => Err(()),
+        }
+    }
+}
+
+impl TryFrom<String> for VisibilityLevel {
+    type Error = ();
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        VisibilityLevel::from_str(&value)
+    }
+}
+
+#[derive(Serialize, Deserialize, ToSchema, sqlx::Type, Clone, Copy, Debug, PartialEq)]
+pub enum CheckInQuestionKind {
+    FreeText,
+    // A whole number from 1 to 5
+    Scale,
+    // One of the question's options
+    MultipleChoice,
+}
+
+impl FromStr for CheckInQuestionKind {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "FreeText" => Ok(CheckInQuestionKind::FreeText),
+            "Scale" => Ok(CheckInQuestionKind::Scale),
+            "MultipleChoice" => Ok(CheckInQuestionKind::MultipleChoice),
+            _ => Err(()),
+        }
+    }
+}
+
+impl TryFrom<String> for CheckInQuestionKind {
+    type Error = ();
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        CheckInQuestionKind::from_str(&value)
+    }
+}
+
+// One of the questions a collective asks people when they plan their participation
+#[derive(Serialize, Deserialize, ToSchema, Clone, Debug)]
+pub struct CheckInQuestion {
+    pub key: String,
+    pub prompt: String,
+    pub kind: CheckInQuestionKind,
+    #[serde(default)]
+    pub options: Vec<String>,
+    #[serde(default)]
+    pub required: bool,
+}
+
+// The questions asked for an interval. Each version of the set applies from the interval it
+// was defined for until a later interval has its own
+#[derive(Serialize, Deserialize, ToSchema, Clone, Debug)]
+pub struct CheckInQuestionSet {
+    // The interval this version was defined for, if there is one yet
+    pub defined_for_interval_id: Option<i64>,
+    pub questions: Vec<CheckInQuestion>,
+}
+
+// Someone's answer to one of the collective's check-in questions for an interval. Answers
+// are matched to questions by key, so they carry over between versions of the question set
+#[derive(Serialize, Deserialize, ToSchema, Clone, Debug)]
+pub struct CheckInAnswer {
+    pub question_key: String,
+    pub answer: String,
+    #[serde(default)]
+    pub visibility: VisibilityLevel,
+}
+
+// A change to someone's status part way through an interval, from the given date onwards
+#[derive(Serialize, Deserialize, ToSchema, Clone, Debug)]
+pub struct StatusSegment {
+    pub status: InvolvementStatus,
+    pub effective_from: String,
+}
+
+// A change to someone's involvement in an interval, recorded every time it's saved
+#[derive(Serialize, Deserialize, ToSchema, Clone, Debug)]
+pub struct InvolvementUpdate {
+    pub id: i64,
+    pub interval_id: i64,
+    pub occurred_at: String,
+    pub old_status: Option<InvolvementStatus>,
+    pub new_status: InvolvementStatus,
+    // The intention context given with the change
+    pub context: Option<String>,
+    pub author_id: Option<i64>,
 }
 
 #[derive(Serialize, Deserialize, ToSchema, Clone, Debug)]
@@ -207,13 +506,31 @@ pub struct CollectiveInvolvement {
     pub collective_id: i64,
     pub interval_id: i64,
     pub status: InvolvementStatus,
-    pub private_capacity_planning: bool,
-    pub capacity_planning: Option<CapacityPlanning>,
+    #[serde(default)]
+    pub check_in_answers: Vec<CheckInAnswer>,
     pub capacity_score: Option<i64>,
     pub participation_intention: Option<ParticipationIntention>,
     pub opt_out_type: Option<OptOutType>,
     pub opt_out_planned_return_date: Option<String>,
     pub intention_context: Option<String>,
+    pub draft: bool,
+    // Who last saved it, when that wasn't done automatically
+    #[serde(default)]
+    pub updated_by: Option<i64>,
+    // Changes during the interval, in date order. The status above holds until the first
+    #[serde(default)]
+    pub status_segments: Vec<StatusSegment>,
+}
+
+impl CollectiveInvolvement {
+    // The status on a date within the interval, given as YYYY-MM-DD
+    pub fn status_on(&self, date: &str) -> InvolvementStatus {
+        self.status_segments
+            .iter()
+            .rev()
+            .find(|segment| segment.effective_from.as_str() <= date)
+            .map_or(self.status.clone(), |segment| segment.status.clone())
+    }
 }
 
 #[derive(Serialize, Deserialize, ToSchema, Debug)]
@@ -222,6 +539,14 @@ pub struct Crew {
     pub name: String,
     pub description: Option<String>,
     pub collective_id: i64,
+    pub archived: bool,
+    pub min_members: Option<i64>,
+    pub max_members: Option<i64>,
+    pub needs_convenor: bool,
+    pub convenor_strategy: ConvenorStrategy,
+    pub allow_co_convenors: bool,
+    pub requires_approval: bool,
+    pub parent_crew_id: Option<i64>,
 }
 
 #[derive(Serialize, Deserialize, ToSchema, Debug, Clone)]
@@ -259,6 +584,123 @@ pub struct CrewWithLinks {
     pub description: Option<String>,
     pub collective_id: i64,
     pub links: Option<Vec<Link>>,
+    // Archived crews are kept for history, but can't be joined in new intervals
+    #[serde(default)]
+    pub archived: bool,
+    #[serde(default)]
+    pub min_members: Option<i64>,
+    #[serde(default)]
+    pub max_members: Option<i64>,
+    #[serde(default = "default_needs_convenor")]
+    pub needs_convenor: bool,
+    #[serde(default)]
+    pub convenor_strategy: ConvenorStrategy,
+    #[serde(default)]
+    pub allow_co_convenors: bool,
+    #[serde(default)]
+    pub roles: Vec<CrewRole>,
+    #[serde(default)]
+    pub requires_approval: bool,
+    // Sub-crews and working groups sit under a parent crew
+    #[serde(default)]
+    pub parent_crew_id: Option<i64>,
+    #[serde(default)]
+    pub sub_crew_ids: Vec<i64>,
+}
+
+fn default_needs_convenor() -> bool {
+    true
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TaskId {
+    pub id: i64,
+}
+
+impl TaskId {
+    pub fn new(id: i64) -> Self {
+        TaskId { id }
+    }
+}
+
+#[derive(Serialize, Deserialize, ToSchema, sqlx::Type, Clone, Copy, Debug, PartialEq, Default)]
+pub enum TaskStatus {
+    #[default]
+    Open,
+    InProgress,
+    Done,
+}
+
+impl FromStr for TaskStatus {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "Open" => Ok(TaskStatus::Open),
+            "InProgress" => Ok(TaskStatus::InProgress),
+            "Done" => Ok(TaskStatus::Done),
+            _ => Err(()),
+        }
+    }
+}
+
+impl TryFrom<String> for TaskStatus {
+    type Error = ();
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        TaskStatus::from_str(&value)
+    }
+}
+
+#[derive(Serialize, Deserialize, ToSchema, sqlx::Type, Clone, Copy, Debug, PartialEq, Default)]
+pub enum TaskRecurrence {
+    #[default]
+    Once,
+    EveryInterval,
+}
+
+impl FromStr for TaskRecurrence {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "Once" => Ok(TaskRecurrence::Once),
+            "EveryInterval" => Ok(TaskRecurrence::EveryInterval),
+            _ => Err(()),
+        }
+    }
+}
+
+impl TryFrom<String> for TaskRecurrence {
+    type Error = ();
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        TaskRecurrence::from_str(&value)
+    }
+}
+
+// A one-off task or standing responsibility, owned by a crew or, without one, the whole
+// collective. Tasks that recur every interval are repeated when the next interval is created,
+// so each interval has its own copy to complete
+#[derive(Serialize, Deserialize, ToSchema, Clone, Debug)]
+pub struct Task {
+    pub id: i64,
+    pub collective_id: i64,
+    #[serde(default)]
+    pub crew_id: Option<i64>,
+    pub title: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub assignee_id: Option<i64>,
+    #[serde(default)]
+    pub due_date: Option<String>,
+    #[serde(default)]
+    pub status: TaskStatus,
+    #[serde(default)]
+    pub recurrence: TaskRecurrence,
+    #[serde(default)]
+    pub interval_id: Option<i64>,
 }
 
 #[derive(ToSchema, Deserialize, Serialize, Debug, Clone)]
@@ -286,4 +728,4 @@ pub struct ExpressionOfInterest {
     pub referral: Option<String>,
     pub conflict_experience: Option<String>,
     pub participant_connections: Option<String>,
-}
\ No newline at end of file
+}
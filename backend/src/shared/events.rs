@@ -9,6 +9,8 @@ pub enum AppEvent {
     CollectiveEvent(crate::my_collective::events::CollectiveEvent),
     PeopleEvent(crate::people::events::PeopleEvent),
     EntryPathwayEvent(crate::entry_pathways::events::EntryPathwayEvent),
+    TasksEvent(crate::tasks::events::TasksEvent),
+    CheckInsEvent(crate::check_ins::events::CheckInsEvent),
 }
 
 #[derive(Serialize, Deserialize, ToSchema, Debug, Clone)]
@@ -0,0 +1,12 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::shared::entities::Task;
+
+#[derive(Serialize, Deserialize, ToSchema, Debug, Clone)]
+#[allow(clippy::enum_variant_names)]
+pub enum TasksEvent {
+    TaskCreated(Task),
+    TaskUpdated(Task),
+    TaskDeleted(i64),
+}
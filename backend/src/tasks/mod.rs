@@ -0,0 +1,327 @@
+use axum::{
+    Extension, Json,
+    extract::Path,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use utoipa::ToSchema;
+use utoipa_axum::{router::OpenApiRouter, routes};
+
+use crate::{
+    auth::{
+        auth_backend::AuthSession,
+        permissions::{ActingPerson, find_acting_person},
+    },
+    crews::{repo::find_crew, require_admin_or_current_convenor},
+    intervals::repo::{find_collective_interval, parse_date_only},
+    people::repo::find_person_by_id,
+    realtime::RealtimeState,
+    shared::{
+        default_collective_id,
+        entities::{CrewId, IntervalId, PersonId, Task, TaskId, TaskRecurrence, TaskStatus},
+        events::AppEvent,
+    },
+    tasks::events::TasksEvent,
+};
+
+pub mod events;
+pub mod recurrence;
+pub mod repo;
+
+pub fn router() -> OpenApiRouter {
+    OpenApiRouter::new()
+        .routes(routes!(get_tasks, create_task))
+        .routes(routes!(update_task, delete_task))
+        .routes(routes!(update_task_status))
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct TaskStatusInput {
+    pub status: TaskStatus,
+}
+
+#[utoipa::path(get, path = "/",
+    responses(
+        (status = 200, body = Vec<Task>),
+        (status = UNAUTHORIZED, description = "Not logged in", body = ()),
+        (status = INTERNAL_SERVER_ERROR, description = "Internal server error", body = ()),
+    ),
+)]
+pub async fn get_tasks(
+    Extension(pool): Extension<SqlitePool>,
+    auth_session: AuthSession,
+) -> impl IntoResponse {
+    if auth_session.user.is_none() {
+        return (StatusCode::UNAUTHORIZED, ()).into_response();
+    }
+
+    match repo::find_all_tasks(default_collective_id(), &pool).await {
+        Ok(tasks) => (StatusCode::OK, Json(tasks)).into_response(),
+        Err(_) => (StatusCode::INTERNAL_SERVER_ERROR, ()).into_response(),
+    }
+}
+
+#[utoipa::path(post, path = "/",
+    request_body(content = Task, content_type = "application/json"),
+    responses(
+        (status = 201, body = Vec<AppEvent>),
+        (status = BAD_REQUEST, description = "The task isn't valid", body = ()),
+        (status = FORBIDDEN, description = "Only admins, or the crew's convenors, can create tasks", body = ()),
+        (status = INTERNAL_SERVER_ERROR, description = "Internal server error", body = ()),
+    ),
+)]
+pub async fn create_task(
+    Extension(pool): Extension<SqlitePool>,
+    Extension(realtime_state): Extension<RealtimeState>,
+    auth_session: AuthSession,
+    Json(input): Json<Task>,
+) -> impl IntoResponse {
+    if let Err(status) =
+        require_admin_or_current_convenor(&auth_session, input.crew_id, &pool).await
+    {
+        return (status, ()).into_response();
+    }
+
+    if let Err(error) = validate_task(&input, &pool).await {
+        return error.into_response();
+    }
+
+    println!("Creating task: {:?}", input);
+
+    let result = repo::insert_task(default_collective_id(), input, &pool).await;
+    task_response(
+        result.map(TasksEvent::TaskCreated),
+        StatusCode::CREATED,
+        realtime_state,
+        auth_session,
+    )
+    .await
+}
+
+#[utoipa::path(put, path = "/{task_id}",
+    params(
+        ("task_id" = i64, Path, description = "Task ID")
+    ),
+    request_body(content = Task, content_type = "application/json"),
+    responses(
+        (status = 200, body = Vec<AppEvent>),
+        (status = BAD_REQUEST, description = "Task ID mismatch, or the task isn't valid", body = ()),
+        (status = FORBIDDEN, description = "Only admins, or the crew's convenors, can update tasks", body = ()),
+        (status = NOT_FOUND, description = "Task not found", body = ()),
+        (status = INTERNAL_SERVER_ERROR, description = "Internal server error", body = ()),
+    ),
+)]
+pub async fn update_task(
+    Path(task_id): Path<i64>,
+    Extension(pool): Extension<SqlitePool>,
+    Extension(realtime_state): Extension<RealtimeState>,
+    auth_session: AuthSession,
+    Json(input): Json<Task>,
+) -> impl IntoResponse {
+    if input.id != task_id {
+        return (StatusCode::BAD_REQUEST, "Task ID mismatch").into_response();
+    }
+
+    let task = match repo::find_task(default_collective_id(), TaskId::new(task_id), &pool).await {
+        Ok(task) => task,
+        Err(sqlx::Error::RowNotFound) => return (StatusCode::NOT_FOUND, ()).into_response(),
+        Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR, ()).into_response(),
+    };
+
+    // Moving a task between crews needs permission for both
+    for crew_id in [task.crew_id, input.crew_id] {
+        if let Err(status) = require_admin_or_current_convenor(&auth_session, crew_id, &pool).await
+        {
+            return (status, ()).into_response();
+        }
+    }
+
+    if let Err(error) = validate_task(&input, &pool).await {
+        return error.into_response();
+    }
+
+    println!("Updating task {}: {:?}", task_id, input);
+
+    let result = repo::update_task(default_collective_id(), input, &pool).await;
+    task_response(
+        result.map(TasksEvent::TaskUpdated),
+        StatusCode::OK,
+        realtime_state,
+        auth_session,
+    )
+    .await
+}
+
+// Whoever the task is assigned to can move it along, as well as those who manage the task
+#[utoipa::path(put, path = "/{task_id}/status",
+    params(
+        ("task_id" = i64, Path, description = "Task ID")
+    ),
+    request_body(content = TaskStatusInput, content_type = "application/json"),
+    responses(
+        (status = 200, body = Vec<AppEvent>),
+        (status = FORBIDDEN, description = "Only the assignee, admins, or the crew's convenors, can update a task's status", body = ()),
+        (status = NOT_FOUND, description = "Task not found", body = ()),
+        (status = INTERNAL_SERVER_ERROR, description = "Internal server error", body = ()),
+    ),
+)]
+pub async fn update_task_status(
+    Path(task_id): Path<i64>,
+    Extension(pool): Extension<SqlitePool>,
+    Extension(realtime_state): Extension<RealtimeState>,
+    auth_session: AuthSession,
+    Json(input): Json<TaskStatusInput>,
+) -> impl IntoResponse {
+    let task_id = TaskId::new(task_id);
+    let task = match repo::find_task(default_collective_id(), task_id.clone(), &pool).await {
+        Ok(task) => task,
+        Err(sqlx::Error::RowNotFound) => return (StatusCode::NOT_FOUND, ()).into_response(),
+        Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR, ()).into_response(),
+    };
+
+    let acting_person = match require_assignee_or_manager(&auth_session, &task, &pool).await {
+        Ok(acting_person) => acting_person,
+        Err(status) => return (status, ()).into_response(),
+    };
+
+    println!(
+        "Person {} setting task {} to {:?}",
+        acting_person.person_id.id, task_id.id, input.status
+    );
+
+    let result =
+        repo::update_task_status(default_collective_id(), task_id, input.status, &pool).await;
+    task_response(
+        result.map(TasksEvent::TaskUpdated),
+        StatusCode::OK,
+        realtime_state,
+        auth_session,
+    )
+    .await
+}
+
+#[utoipa::path(delete, path = "/{task_id}",
+    params(
+        ("task_id" = i64, Path, description = "Task ID")
+    ),
+    responses(
+        (status = 200, body = Vec<AppEvent>),
+        (status = FORBIDDEN, description = "Only admins, or the crew's convenors, can delete tasks", body = ()),
+        (status = NOT_FOUND, description = "Task not found", body = ()),
+        (status = INTERNAL_SERVER_ERROR, description = "Internal server error", body = ()),
+    ),
+)]
+pub async fn delete_task(
+    Path(task_id): Path<i64>,
+    Extension(pool): Extension<SqlitePool>,
+    Extension(realtime_state): Extension<RealtimeState>,
+    auth_session: AuthSession,
+) -> impl IntoResponse {
+    let task_id = TaskId::new(task_id);
+    let task = match repo::find_task(default_collective_id(), task_id.clone(), &pool).await {
+        Ok(task) => task,
+        Err(sqlx::Error::RowNotFound) => return (StatusCode::NOT_FOUND, ()).into_response(),
+        Err(_) => return (StatusCode::INTERNAL_SERVER_ERROR, ()).into_response(),
+    };
+
+    if let Err(status) = require_admin_or_current_convenor(&auth_session, task.crew_id, &pool).await
+    {
+        return (status, ()).into_response();
+    }
+
+    println!("Deleting task {}", task_id.id);
+
+    let result = repo::delete_task(default_collective_id(), task_id.clone(), &pool).await;
+    task_response(
+        result.map(|_| TasksEvent::TaskDeleted(task_id.id)),
+        StatusCode::OK,
+        realtime_state,
+        auth_session,
+    )
+    .await
+}
+
+async fn require_assignee_or_manager(
+    auth_session: &AuthSession,
+    task: &Task,
+    pool: &SqlitePool,
+) -> Result<ActingPerson, StatusCode> {
+    let acting_person = find_acting_person(auth_session, default_collective_id(), pool).await?;
+    if task.assignee_id == Some(acting_person.person_id.id) {
+        return Ok(acting_person);
+    }
+
+    require_admin_or_current_convenor(auth_session, task.crew_id, pool).await
+}
+
+// Checks the task only refers to things in the collective, returning the status and reason
+// to respond with if not
+async fn validate_task(task: &Task, pool: &SqlitePool) -> Result<(), (StatusCode, &'static str)> {
+    let bad_request = |message| Err((StatusCode::BAD_REQUEST, message));
+
+    if task.title.trim().is_empty() {
+        return bad_request("Tasks need a title");
+    }
+
+    if task
+        .due_date
+        .as_deref()
+        .is_some_and(|due_date| parse_date_only(due_date).is_none())
+    {
+        return bad_request("Due date must be a date");
+    }
+
+    // Recurring tasks are repeated from one interval to the next, so need to start in one
+    if task.recurrence == TaskRecurrence::EveryInterval && task.interval_id.is_none() {
+        return bad_request("Recurring tasks need an interval");
+    }
+
+    if let Some(interval_id) = task.interval_id {
+        match find_collective_interval(default_collective_id(), IntervalId::new(interval_id), pool)
+            .await
+        {
+            Ok(Some(_)) => {}
+            Ok(None) => return bad_request("Interval not found"),
+            Err(_) => return Err((StatusCode::INTERNAL_SERVER_ERROR, "Internal server error")),
+        }
+    }
+
+    if let Some(crew_id) = task.crew_id {
+        match find_crew(default_collective_id(), CrewId::new(crew_id), pool).await {
+            Ok(_) => {}
+            Err(sqlx::Error::RowNotFound) => return bad_request("Crew not found"),
+            Err(_) => return Err((StatusCode::INTERNAL_SERVER_ERROR, "Internal server error")),
+        }
+    }
+
+    if let Some(assignee_id) = task.assignee_id {
+        match find_person_by_id(PersonId::new(assignee_id), default_collective_id(), pool).await {
+            Ok(_) => {}
+            Err(sqlx::Error::RowNotFound) => return bad_request("Assignee not found"),
+            Err(_) => return Err((StatusCode::INTERNAL_SERVER_ERROR, "Internal server error")),
+        }
+    }
+
+    Ok(())
+}
+
+async fn task_response(
+    result: Result<TasksEvent, sqlx::Error>,
+    status: StatusCode,
+    realtime_state: RealtimeState,
+    auth_session: AuthSession,
+) -> Response {
+    match result {
+        Ok(tasks_event) => {
+            let event = AppEvent::TasksEvent(tasks_event);
+            realtime_state
+                .broadcast_app_event(Some(auth_session), event.clone())
+                .await;
+            (status, Json(vec![event])).into_response()
+        }
+        Err(sqlx::Error::RowNotFound) => (StatusCode::NOT_FOUND, ()).into_response(),
+        Err(_) => (StatusCode::INTERNAL_SERVER_ERROR, ()).into_response(),
+    }
+}
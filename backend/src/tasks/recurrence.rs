@@ -0,0 +1,35 @@
+use sqlx::SqlitePool;
+
+use crate::{
+    shared::entities::{CollectiveId, Interval, IntervalId, Task},
+    tasks::repo::{find_unrepeated_recurring_tasks, insert_repeated_task},
+};
+
+// Gives the interval its own copy of each task that recurs from the previous interval,
+// open again and with the same assignee. Copies that had a due date are due by the end of
+// the new interval. Safe to run more than once, as tasks are only ever repeated once
+pub async fn repeat_recurring_tasks(
+    collective_id: CollectiveId,
+    previous_interval_id: IntervalId,
+    interval: &Interval,
+    pool: &SqlitePool,
+) -> Result<Vec<Task>, sqlx::Error> {
+    let recurring_tasks =
+        find_unrepeated_recurring_tasks(collective_id.clone(), previous_interval_id, pool).await?;
+
+    let mut repeated_tasks = vec![];
+    for task in recurring_tasks {
+        let due_date = task.due_date.as_ref().map(|_| interval.end_date.clone());
+        let repeated_task = insert_repeated_task(
+            collective_id.clone(),
+            &task,
+            interval.typed_id(),
+            due_date,
+            pool,
+        )
+        .await?;
+        repeated_tasks.push(repeated_task);
+    }
+
+    Ok(repeated_tasks)
+}
@@ -0,0 +1,237 @@
+use sqlx::SqlitePool;
+
+use crate::shared::entities::{
+    CollectiveId, IntervalId, PersonId, Task, TaskId, TaskRecurrence, TaskStatus,
+};
+
+pub async fn find_all_tasks(
+    collective_id: CollectiveId,
+    pool: &SqlitePool,
+) -> Result<Vec<Task>, sqlx::Error> {
+    sqlx::query_as!(
+        Task,
+        "SELECT id, collective_id, crew_id, title, description, assignee_id, due_date,
+            status as \"status: TaskStatus\", recurrence as \"recurrence: TaskRecurrence\",
+            interval_id
+        FROM tasks
+        WHERE collective_id = ?
+        ORDER BY due_date IS NULL, due_date, id",
+        collective_id.id
+    )
+    .fetch_all(pool)
+    .await
+}
+
+pub async fn find_task(
+    collective_id: CollectiveId,
+    task_id: TaskId,
+    pool: &SqlitePool,
+) -> Result<Task, sqlx::Error> {
+    sqlx::query_as!(
+        Task,
+        "SELECT id, collective_id, crew_id, title, description, assignee_id, due_date,
+            status as \"status: TaskStatus\", recurrence as \"recurrence: TaskRecurrence\",
+            interval_id
+        FROM tasks
+        WHERE id = ? AND collective_id = ?",
+        task_id.id,
+        collective_id.id
+    )
+    .fetch_one(pool)
+    .await
+}
+
+// Everything assigned to the person that still needs doing, soonest first
+pub async fn find_open_tasks_for_assignee(
+    collective_id: CollectiveId,
+    person_id: PersonId,
+    pool: &SqlitePool,
+) -> Result<Vec<Task>, sqlx::Error> {
+    sqlx::query_as!(
+        Task,
+        "SELECT id, collective_id, crew_id, title, description, assignee_id, due_date,
+            status as \"status: TaskStatus\", recurrence as \"recurrence: TaskRecurrence\",
+            interval_id
+        FROM tasks
+        WHERE collective_id = ? AND assignee_id = ? AND status != 'Done'
+        ORDER BY due_date IS NULL, due_date, id",
+        collective_id.id,
+        person_id.id
+    )
+    .fetch_all(pool)
+    .await
+}
+
+// A one-off task already given to the person for the interval, whatever its status
+pub async fn find_assigned_task_by_title(
+    collective_id: CollectiveId,
+    person_id: PersonId,
+    interval_id: IntervalId,
+    title: &str,
+    pool: &SqlitePool,
+) -> Result<Option<Task>, sqlx::Error> {
+    sqlx::query_as!(
+        Task,
+        "SELECT id, collective_id, crew_id, title, description, assignee_id, due_date,
+            status as \"status: TaskStatus\", recurrence as \"recurrence: TaskRecurrence\",
+            interval_id
+        FROM tasks
+        WHERE collective_id = ? AND assignee_id = ? AND interval_id = ? AND title = ?
+            AND recurrence = 'Once'
+        ORDER BY id
+        LIMIT 1",
+        collective_id.id,
+        person_id.id,
+        interval_id.id,
+        title
+    )
+    .fetch_optional(pool)
+    .await
+}
+
+pub async fn insert_task(
+    collective_id: CollectiveId,
+    task: Task,
+    pool: &SqlitePool,
+) -> Result<Task, sqlx::Error> {
+    let result = sqlx::query!(
+        "INSERT INTO tasks
+            (collective_id, crew_id, title, description, assignee_id, due_date, status,
+            recurrence, interval_id)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        collective_id.id,
+        task.crew_id,
+        task.title,
+        task.description,
+        task.assignee_id,
+        task.due_date,
+        task.status,
+        task.recurrence,
+        task.interval_id
+    )
+    .execute(pool)
+    .await?;
+
+    find_task(collective_id, TaskId::new(result.last_insert_rowid()), pool).await
+}
+
+pub async fn update_task(
+    collective_id: CollectiveId,
+    task: Task,
+    pool: &SqlitePool,
+) -> Result<Task, sqlx::Error> {
+    let result = sqlx::query!(
+        "UPDATE tasks
+        SET crew_id = ?, title = ?, description = ?, assignee_id = ?, due_date = ?, status = ?,
+            recurrence = ?, interval_id = ?
+        WHERE id = ? AND collective_id = ?",
+        task.crew_id,
+        task.title,
+        task.description,
+        task.assignee_id,
+        task.due_date,
+        task.status,
+        task.recurrence,
+        task.interval_id,
+        task.id,
+        collective_id.id
+    )
+    .execute(pool)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(sqlx::Error::RowNotFound);
+    }
+
+    find_task(collective_id, TaskId::new(task.id), pool).await
+}
+
+pub async fn update_task_status(
+    collective_id: CollectiveId,
+    task_id: TaskId,
+    status: TaskStatus,
+    pool: &SqlitePool,
+) -> Result<Task, sqlx::Error> {
+    let result = sqlx::query!(
+        "UPDATE tasks SET status = ? WHERE id = ? AND collective_id = ?",
+        status,
+        task_id.id,
+        collective_id.id
+    )
+    .execute(pool)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(sqlx::Error::RowNotFound);
+    }
+
+    find_task(collective_id, task_id, pool).await
+}
+
+pub async fn delete_task(
+    collective_id: CollectiveId,
+    task_id: TaskId,
+    pool: &SqlitePool,
+) -> Result<(), sqlx::Error> {
+    let result = sqlx::query!(
+        "DELETE FROM tasks WHERE id = ? AND collective_id = ?",
+        task_id.id,
+        collective_id.id
+    )
+    .execute(pool)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(sqlx::Error::RowNotFound);
+    }
+
+    Ok(())
+}
+
+// Recurring tasks from the interval that haven't been repeated into a later one yet
+pub async fn find_unrepeated_recurring_tasks(
+    collective_id: CollectiveId,
+    interval_id: IntervalId,
+    pool: &SqlitePool,
+) -> Result<Vec<Task>, sqlx::Error> {
+    sqlx::query_as!(
+        Task,
+        "SELECT id, collective_id, crew_id, title, description, assignee_id, due_date,
+            status as \"status: TaskStatus\", recurrence as \"recurrence: TaskRecurrence\",
+            interval_id
+        FROM tasks
+        WHERE collective_id = ? AND interval_id = ? AND recurrence = 'EveryInterval'
+        AND NOT EXISTS (SELECT 1 FROM tasks AS repeats WHERE repeats.previous_task_id = tasks.id)",
+        collective_id.id,
+        interval_id.id
+    )
+    .fetch_all(pool)
+    .await
+}
+
+pub async fn insert_repeated_task(
+    collective_id: CollectiveId,
+    previous_task: &Task,
+    interval_id: IntervalId,
+    due_date: Option<String>,
+    pool: &SqlitePool,
+) -> Result<Task, sqlx::Error> {
+    let result = sqlx::query!(
+        "INSERT INTO tasks
+            (collective_id, crew_id, title, description, assignee_id, due_date, status,
+            recurrence, interval_id, previous_task_id)
+        VALUES (?, ?, ?, ?, ?, ?, 'Open', 'EveryInterval', ?, ?)",
+        collective_id.id,
+        previous_task.crew_id,
+        previous_task.title,
+        previous_task.description,
+        previous_task.assignee_id,
+        due_date,
+        interval_id.id,
+        previous_task.id
+    )
+    .execute(pool)
+    .await?;
+
+    find_task(collective_id, TaskId::new(result.last_insert_rowid()), pool).await
+}
@@ -0,0 +1,156 @@
+use axum::{
+    Router,
+    body::{Body, to_bytes},
+    http::{Request, StatusCode, header},
+};
+use chrono::{Days, NaiveDate, Utc};
+use resend_rs::Resend;
+use serde_json::Value;
+use sqlx::{SqlitePool, sqlite::SqlitePoolOptions};
+use tower::ServiceExt;
+
+use crate::{app, realtime::RealtimeState, shared::COLLECTIVE_ID};
+
+pub const PASSWORD: &str = "password";
+
+// The app against a fresh in-memory database holding just the collective
+pub struct TestApp {
+    pub pool: SqlitePool,
+    pub realtime_state: RealtimeState,
+    router: Router,
+}
+
+impl TestApp {
+    pub async fn new() -> Self {
+        // Every connection to an in-memory database gets a database of its own
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .expect("Failed to open test database");
+        sqlx::migrate!()
+            .run(&pool)
+            .await
+            .expect("Failed to migrate test database");
+
+        sqlx::query("INSERT INTO collectives (id, name) VALUES (?, 'Test collective')")
+            .bind(COLLECTIVE_ID)
+            .execute(&pool)
+            .await
+            .expect("Failed to add collective");
+
+        let realtime_state = RealtimeState::new();
+        let router = app(pool.clone(), Resend::new("test"), realtime_state.clone());
+
+        TestApp {
+            pool,
+            realtime_state,
+            router,
+        }
+    }
+
+    // Adds someone who can log in with their email and `PASSWORD`, returning their person ID
+    pub async fn add_person(&self, email: &str, role: &str) -> i64 {
+        let user_id: i64 = sqlx::query_scalar(
+            "INSERT INTO users (email, hashed_password) VALUES (?, ?) RETURNING id",
+        )
+        .bind(email)
+        .bind(password_auth::generate_hash(PASSWORD))
+        .fetch_one(&self.pool)
+        .await
+        .expect("Failed to add user");
+
+        sqlx::query_scalar(
+            "INSERT INTO people (display_name, user_id, collective_id, role)
+            VALUES (?, ?, ?, ?)
+            RETURNING id",
+        )
+        .bind(email)
+        .bind(user_id)
+        .bind(COLLECTIVE_ID)
+        .bind(role)
+        .fetch_one(&self.pool)
+        .await
+        .expect("Failed to add person")
+    }
+
+    // Adds an interval running from `start_offset` days before today until 30 days after it
+    pub async fn add_interval(&self, start_offset: u64, phase: &str, sequence: i64) -> i64 {
+        let today = Utc::now().date_naive();
+        let start_date = today.checked_sub_days(Days::new(start_offset)).unwrap();
+        let end_date = today.checked_add_days(Days::new(30)).unwrap();
+
+        sqlx::query_scalar(
+            "INSERT INTO intervals (start_date, end_date, collective_id, phase, sequence)
+            VALUES (?, ?, ?, ?, ?)
+            RETURNING id",
+        )
+        .bind(date_string(start_date))
+        .bind(date_string(end_date))
+        .bind(COLLECTIVE_ID)
+        .bind(phase)
+        .bind(sequence)
+        .fetch_one(&self.pool)
+        .await
+        .expect("Failed to add interval")
+    }
+
+    pub async fn execute(&self, sql: &str) {
+        sqlx::query(sql)
+            .execute(&self.pool)
+            .await
+            .unwrap_or_else(|error| panic!("Failed to run {}: {}", sql, error));
+    }
+
+    // Logs in, returning the session cookie to send with later requests
+    pub async fn log_in(&self, email: &str) -> String {
+        let request = Request::post("/api/auth/login")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(
+                serde_json::json!({ "email": email, "password": PASSWORD }).to_string(),
+            ))
+            .unwrap();
+        let response = self.router.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK, "Failed to log in");
+
+        let cookie = response
+            .headers()
+            .get(header::SET_COOKIE)
+            .expect("No session cookie")
+            .to_str()
+            .unwrap();
+        cookie.split(';').next().unwrap().to_string()
+    }
+
+    pub async fn request(
+        &self,
+        method: &str,
+        uri: &str,
+        session: &str,
+        body: Option<Value>,
+    ) -> (StatusCode, Value) {
+        let request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header(header::COOKIE, session)
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(body.map_or_else(Body::empty, |body| Body::from(body.to_string())))
+            .unwrap();
+        let response = self.router.clone().oneshot(request).await.unwrap();
+
+        let status = response.status();
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body = serde_json::from_slice(&bytes).unwrap_or(Value::Null);
+        (status, body)
+    }
+
+    pub async fn get(&self, uri: &str, session: &str) -> Value {
+        let (status, body) = self.request("GET", uri, session, None).await;
+        assert_eq!(status, StatusCode::OK, "GET {} failed", uri);
+        body
+    }
+}
+
+pub fn date_string(date: NaiveDate) -> String {
+    date.format("%Y-%m-%d").to_string()
+}
@@ -26,6 +26,19 @@ export enum InvolvementStatus {
   Exiting = "Exiting",
 }
 
+export enum VisibilityLevel {
+  OnlyMe = "OnlyMe",
+  CrewConvenors = "CrewConvenors",
+  Care = "Care",
+  Collective = "Collective",
+}
+
+export enum CheckInQuestionKind {
+  FreeText = "FreeText",
+  Scale = "Scale",
+  MultipleChoice = "MultipleChoice",
+}
+
 export enum EoiError {
   CollectiveNotFound = "CollectiveNotFound",
   EoiNotFound = "EoiNotFound",
@@ -53,10 +66,24 @@ export type AppEvent =
       EntryPathwayEvent: EntryPathwayEvent;
     };
 
-export interface CapacityPlanning {
-  capacity?: string | null;
-  focus?: string | null;
-  wellbeing?: string | null;
+export interface CheckInAnswer {
+  answer: string;
+  question_key: string;
+  visibility?: VisibilityLevel;
+}
+
+export interface CheckInQuestion {
+  key: string;
+  kind: CheckInQuestionKind;
+  options?: string[];
+  prompt: string;
+  required?: boolean;
+}
+
+export interface CheckInQuestionSet {
+  /** @format int64 */
+  defined_for_interval_id?: number | null;
+  questions: CheckInQuestion[];
 }
 
 export interface Collective {
@@ -76,9 +103,9 @@ export type CollectiveEvent = {
 };
 
 export interface CollectiveInvolvement {
-  capacity_planning?: null | CapacityPlanning;
   /** @format int64 */
   capacity_score?: number | null;
+  check_in_answers?: CheckInAnswer[];
   /** @format int64 */
   collective_id: number;
   /** @format int64 */
@@ -91,7 +118,6 @@ export interface CollectiveInvolvement {
   participation_intention?: null | ParticipationIntention;
   /** @format int64 */
   person_id: number;
-  private_capacity_planning: boolean;
   status: InvolvementStatus;
 }
 
@@ -218,19 +244,16 @@ export interface MyInitialData {
 }
 
 export interface MyParticipationInput {
-  capacity?: string | null;
   /** @format int64 */
   capacity_score?: number | null;
+  check_in_answers?: CheckInAnswer[];
   /** @format int64 */
   collective_id: number;
   crew_involvements?: any[] | null;
-  focus?: string | null;
   intention_context?: string | null;
   opt_out_planned_return_date?: string | null;
   opt_out_type?: null | OptOutType;
   participation_intention?: null | ParticipationIntention;
-  private_capacity_planning: boolean;
-  wellbeing?: string | null;
 }
 
 export type PeopleEvent = {
@@ -491,6 +514,20 @@ export class Api<
         ...params,
       }),
 
+    /**
+     * No description
+     *
+     * @name GetQuestionSet
+     * @request GET:/api/check-ins/interval/{interval_id}
+     */
+    getQuestionSet: (intervalId: number, params: RequestParams = {}) =>
+      this.request<CheckInQuestionSet, any>({
+        path: `/api/check-ins/interval/${intervalId}`,
+        method: "GET",
+        format: "json",
+        ...params,
+      }),
+
     /**
      * No description
      *
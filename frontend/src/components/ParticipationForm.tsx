@@ -1,22 +1,31 @@
-import { Stepper, Group, Button, Stack, Textarea, Select, Title, Text, type SelectProps, Switch, Flex, Alert } from "@mantine/core";
+import { Stepper, Group, Button, Stack, Textarea, Select, Title, Text, type SelectProps, Alert } from "@mantine/core";
 import { DatePickerInput } from "@mantine/dates";
 import { useForm } from "@mantine/form";
 import { useState } from "react";
-import type { CollectiveInvolvement, CrewInvolvement, Interval, OptOutType, ParticipationIntention } from "../api/Api";
+import {
+  CheckInQuestionKind,
+  VisibilityLevel,
+  type CheckInAnswer,
+  type CheckInQuestion,
+  type CheckInQuestionSet,
+  type CollectiveInvolvement,
+  type CrewInvolvement,
+  type Interval,
+  type OptOutType,
+  type ParticipationIntention,
+} from "../api/Api";
 import { IconCheck, IconLock, IconDoorExit } from "@tabler/icons-react";
 import { useAppSelector } from "../store";
 import { forPerson, getMatchingInvolvementInterval } from "../store/involvements";
-import { ComboTextArea, CrewParticipationsInput } from ".";
-import type { ArrayOfStringTuples } from "./forms/ComboTextArea";
+import { CrewParticipationsInput } from ".";
 import CapacityScoreIcon from "./CapacityScoreIcon";
 import type { CrewWithLinks } from "../store/crews";
 
 export interface MyParticipationFormData {
-  private_capacity_planning: boolean;
-  wellbeing: string;
-  focus: string;
+  // Answers and who can see them, by question key
+  check_in_answers: Record<string, string>;
+  check_in_visibilities: Record<string, VisibilityLevel>;
   capacity_score: string | null;
-  capacity: string;
   participation_intention: ParticipationIntention | null;
   opt_out_type: OptOutType | null;
   opt_out_planned_return_date: string | null;
@@ -24,6 +33,28 @@ export interface MyParticipationFormData {
   intention_context: string | null;
 }
 
+const SCALE_OPTIONS = ["1", "2", "3", "4", "5"];
+
+const VISIBILITY_OPTIONS = [
+  { label: "Only me", value: VisibilityLevel.OnlyMe },
+  { label: "My crew convenors", value: VisibilityLevel.CrewConvenors },
+  { label: "Care crew and admins", value: VisibilityLevel.Care },
+  { label: "Everyone", value: VisibilityLevel.Collective },
+];
+
+function answerFor(involvement: CollectiveInvolvement | null, key: string) {
+  return involvement?.check_in_answers?.find((answer) => answer.question_key === key);
+}
+
+// Blank answers are dropped when saving, so every question is always sent
+export function checkInAnswers(values: MyParticipationFormData, questionSet: CheckInQuestionSet): CheckInAnswer[] {
+  return questionSet.questions.map((question) => ({
+    question_key: question.key,
+    answer: values.check_in_answers[question.key] ?? "",
+    visibility: values.check_in_visibilities[question.key] ?? VisibilityLevel.OnlyMe,
+  }));
+}
+
 type StepProps = {
   form: ReturnType<typeof useForm<MyParticipationFormData>>;
   readOnly?: boolean;
@@ -37,61 +68,51 @@ const renderCapacityScoreOption: SelectProps["renderOption"] = ({ option, checke
   </Group>
 );
 
-function CapacityStep({ form, readOnly }: StepProps) {
+function CheckInQuestionField({ form, readOnly, question }: StepProps & { question: CheckInQuestion }) {
+  const path = `check_in_visibilities.${question.key}`;
+
+  return (
+    <Stack gap={4}>
+      <CheckInQuestionInput form={form} readOnly={readOnly} question={question} />
+      <Select aria-label={`Who can see your answer to "${question.prompt}"`} size="xs" w={200} allowDeselect={false} disabled={readOnly} data={VISIBILITY_OPTIONS} key={form.key(path)} {...form.getInputProps(path)} />
+    </Stack>
+  );
+}
+
+function CheckInQuestionInput({ form, readOnly, question }: StepProps & { question: CheckInQuestion }) {
+  const path = `check_in_answers.${question.key}`;
+
+  switch (question.kind) {
+    case CheckInQuestionKind.Scale:
+      return (
+        <Select label={question.prompt} withAsterisk={question.required} placeholder="Pick value" disabled={readOnly} data={SCALE_OPTIONS} key={form.key(path)} {...form.getInputProps(path)} />
+      );
+    case CheckInQuestionKind.MultipleChoice:
+      return (
+        <Select label={question.prompt} withAsterisk={question.required} placeholder="Pick value" disabled={readOnly} data={question.options ?? []} key={form.key(path)} {...form.getInputProps(path)} />
+      );
+    default:
+      return <Textarea label={question.prompt} withAsterisk={question.required} rows={4} disabled={readOnly} key={form.key(path)} {...form.getInputProps(path)} />;
+  }
+}
+
+function CapacityStep({ form, readOnly, questionSet }: StepProps & { questionSet: CheckInQuestionSet }) {
   const collective_noun_name = useAppSelector((state) => state.collective?.noun_name || "the collective");
 
   return (
     <Stack mt="lg" gap="md">
       <Stack gap={0} mb="md">
-        <Flex direction={{ base: "column", sm: "row" }} justify="space-between" align={{ base: "flex-start", sm: "center" }} gap="xs" mb={{ base: "md", sm: 0 }}>
-          <Title order={3} m={0}>
-            Plan your capacity
-          </Title>
-          <Switch label="Keep private" labelPosition="left" {...form.getInputProps("private_capacity_planning", { type: "checkbox" })} />
-        </Flex>
-        <Text c="dimmed">Optional questions to prompt reflection on life before planning your participation, sharing them with the group can help us be more aware of each other's needs.</Text>
+        <Title order={3} m={0}>
+          Plan your capacity
+        </Title>
+        <Text c="dimmed">Optional questions to prompt reflection on life before planning your participation, sharing them with the group can help us be more aware of each other's needs. Choose who can see each answer.</Text>
       </Stack>
 
-      <ComboTextArea
-        disabled={readOnly}
-        rows={4}
-        label="Wellbeing"
-        description="How is your wellbeing and energy?"
-        key={form.key("wellbeing")}
-        hints={
-          [
-            ["Excitable", "I'm feeling excitable"],
-            ["Determined", "I'm feeling determined"],
-            ["Mostly well", "I'm feeling mostly well"],
-            ["Up and down", "I'm feeling up and down"],
-            ["A bit meh", "I'm feeling a bit meh"],
-            ["Overwhelmed", "I'm feeling overwhelmed"],
-            ["Exhausted", "I'm feeling exhausted"],
-          ] as ArrayOfStringTuples
-        }
-        {...form.getInputProps("wellbeing")}
-      />
-      <ComboTextArea
-        disabled={readOnly}
-        label="Focus"
-        rows={4}
-        description="Where are you likely to be directing your time, energy and attention? Do you have any commitments, events or responsibilities coming up?"
-        hints={
-          [
-            ["surviving", "I'm focusing on surviving"],
-            ["care responsibilities", "I'm focusing on care responsibilities"],
-            ["generating income", "I'm focusing on generating income"],
-            ["travel", "I'm focused on travel opportunities"],
-            ["study", "I'm focusing on learning opportunities"],
-            ["community projects", "I'm contributing to community projects"],
-            ["social connection", "I'm nourishing my social connections"],
-          ] as ArrayOfStringTuples
-        }
-        key={form.key("focus")}
-        {...form.getInputProps("focus")}
-      />
+      {questionSet.questions.map((question) => (
+        <CheckInQuestionField key={question.key} form={form} readOnly={readOnly} question={question} />
+      ))}
       <Select
-        label={form.values.private_capacity_planning ? "Capacity (Shared with group)" : "Capacity"}
+        label="Capacity"
         description={`Given the context of your life (above), how would you describe your capacity to participate in ${collective_noun_name} this interval?`}
         placeholder="Pick value"
         disabled={readOnly}
@@ -104,7 +125,6 @@ function CapacityStep({ form, readOnly }: StepProps) {
         key={form.key("capacity_score")}
         {...form.getInputProps("capacity_score")}
       />
-      <Textarea disabled={readOnly} label="Further context" rows={4} description="Do you want to record any further context about your capacity? " key={form.key("capacity")} {...form.getInputProps("capacity")} />
     </Stack>
   );
 }
@@ -236,11 +256,12 @@ interface ParticipationFormProps {
   readOnly?: boolean;
   involvement?: CollectiveInvolvement | null;
   interval: Interval;
+  questionSet: CheckInQuestionSet;
   previousIntervalId?: number | undefined;
   onSubmit: (data: MyParticipationFormData) => void;
 }
 
-export default function ParticipationForm({ personId, interval, previousIntervalId, readOnly = false, involvement = null, onSubmit }: ParticipationFormProps) {
+export default function ParticipationForm({ personId, interval, questionSet, previousIntervalId, readOnly = false, involvement = null, onSubmit }: ParticipationFormProps) {
   const involvements = useAppSelector((state) => state.involvements);
   const involvementInterval = getMatchingInvolvementInterval(involvements, interval.id);
   const crewInvolvements = involvementInterval?.crew_involvements || [];
@@ -255,11 +276,9 @@ export default function ParticipationForm({ personId, interval, previousInterval
   const form = useForm<MyParticipationFormData>({
     mode: "controlled",
     initialValues: {
-      private_capacity_planning: involvement?.private_capacity_planning ?? false,
-      wellbeing: involvement?.capacity_planning?.wellbeing ?? "",
-      focus: involvement?.capacity_planning?.focus ?? "",
+      check_in_answers: Object.fromEntries(questionSet.questions.map((question) => [question.key, answerFor(involvement, question.key)?.answer ?? ""])),
+      check_in_visibilities: Object.fromEntries(questionSet.questions.map((question) => [question.key, answerFor(involvement, question.key)?.visibility ?? VisibilityLevel.OnlyMe])),
       capacity_score: involvement?.capacity_score?.toString() ?? null,
-      capacity: involvement?.capacity_planning?.capacity ?? "",
       participation_intention: involvement?.participation_intention ?? null,
       opt_out_type: involvement?.opt_out_type ?? null,
       opt_out_planned_return_date: involvement?.opt_out_planned_return_date ?? null,
@@ -320,7 +339,7 @@ export default function ParticipationForm({ personId, interval, previousInterval
     <form onSubmit={form.onSubmit(onSubmit, (errors) => console.log("Form submission errors:", errors))}>
       <Stepper active={step} onStepClick={setStepIfValid} iconSize={32} size="lg" mt="xl">
         <Stepper.Step label="Capacity">
-          <CapacityStep form={form} readOnly={readOnly} />
+          <CapacityStep form={form} readOnly={readOnly} questionSet={questionSet} />
         </Stepper.Step>
         <Stepper.Step label="Minimum Participation">
           <MinimumParticipationStep form={form} readOnly={readOnly} />
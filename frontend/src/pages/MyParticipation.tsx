@@ -1,11 +1,11 @@
 import { Container, Stack, Title, Text } from "@mantine/core";
-import ParticipationForm, { type MyParticipationFormData } from "../components/ParticipationForm";
+import ParticipationForm, { checkInAnswers, type MyParticipationFormData } from "../components/ParticipationForm";
 import { handleAppEvents, useAppSelector } from "../store";
 import { useNavigate, useParams } from "react-router-dom";
 import DateText from "../components/DateText";
 import { useEffect, useState } from "react";
 import { getApi } from "../api";
-import type { CollectiveInvolvement, MyParticipationInput } from "../api/Api";
+import type { CheckInQuestionSet, CollectiveInvolvement, MyParticipationInput } from "../api/Api";
 import { findPreviousInterval } from "../store/intervals";
 
 export default function MyParticipation() {
@@ -46,16 +46,33 @@ export default function MyParticipation() {
       });
   }, [interval.id]);
 
+  const [questionSet, setQuestionSet] = useState<CheckInQuestionSet | null>(null);
+  useEffect(() => {
+    api.api
+      .getQuestionSet(interval.id)
+      .then((response) => {
+        setQuestionSet(response.data);
+      })
+      .catch((error) => {
+        console.error("Error fetching check-in questions:", error);
+      });
+  }, [interval.id]);
+
   if (readOnly && !involvement) {
     return <Text>Error: You were not participating in this interval.</Text>;
   }
 
+  if (!questionSet) {
+    return <Text>Loading check-in questions...</Text>;
+  }
+
   const onSubmit = (values: MyParticipationFormData) => {
     const inputData: MyParticipationInput = {
       collective_id: collective.id,
       ...involvement,
       ...values,
       capacity_score: values.capacity_score ? parseInt(values.capacity_score) : null,
+      check_in_answers: checkInAnswers(values, questionSet),
     };
 
     api.api
@@ -87,6 +104,7 @@ export default function MyParticipation() {
         key={involvement?.id || `fresh-${interval.id}`}
         onSubmit={onSubmit}
         interval={interval}
+        questionSet={questionSet}
         previousIntervalId={previousInterval?.id}
       />
     </Container>
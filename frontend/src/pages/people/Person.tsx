@@ -3,7 +3,9 @@ import { useAppSelector } from "../../store";
 import { Anchor } from "../../components";
 import { IconUserEdit } from "@tabler/icons-react";
 import { useParams } from "react-router-dom";
-import type { CapacityPlanning, CollectiveInvolvement, Person } from "../../api/Api";
+import { useEffect, useState } from "react";
+import { getApi } from "../../api";
+import type { CheckInAnswer, CheckInQuestionSet, CollectiveInvolvement, Person } from "../../api/Api";
 import CapacityScoreIcon from "../../components/CapacityScoreIcon";
 import DateText from "../../components/DateText";
 import { oneForPerson } from "../../store/involvements";
@@ -21,9 +23,17 @@ function CapacityQuestion({ question, answer }: { question: string; answer: stri
   );
 }
 
-function CapacityPlanningSection({ capacity_planning, capacity_score }: { capacity_planning: CapacityPlanning; capacity_score: number | null | undefined }) {
-  if (!capacity_planning) return null;
-  if (!capacity_planning.wellbeing && !capacity_planning.focus && !capacity_planning.capacity) return null;
+// Only the answers this person has chosen to share with us are sent
+function CapacityPlanningSection({
+  check_in_answers,
+  capacity_score,
+  questionSet,
+}: {
+  check_in_answers: CheckInAnswer[];
+  capacity_score: number | null | undefined;
+  questionSet: CheckInQuestionSet | null;
+}) {
+  if (check_in_answers.length === 0) return null;
 
   return (
     <Card withBorder>
@@ -34,9 +44,13 @@ function CapacityPlanningSection({ capacity_planning, capacity_score }: { capaci
         <CapacityScoreIcon score={capacity_score} />
       </Group>
       <Stack gap="xs">
-        <CapacityQuestion question="Wellbeing" answer={capacity_planning.wellbeing} />
-        <CapacityQuestion question="Focus" answer={capacity_planning.focus} />
-        <CapacityQuestion question="Capacity" answer={capacity_planning.capacity} />
+        {check_in_answers.map((answer) => (
+          <CapacityQuestion
+            key={answer.question_key}
+            question={questionSet?.questions.find((question) => question.key === answer.question_key)?.prompt ?? answer.question_key}
+            answer={answer.answer}
+          />
+        ))}
       </Stack>
     </Card>
   );
@@ -78,6 +92,21 @@ export default function Person() {
   const person = useAppSelector((state) => state.people[personIdNum || -1]);
   const collective_involvements = useAppSelector((state) => state.involvements.current_interval?.collective_involvements);
   const collective_involvement = collective_involvements && oneForPerson(collective_involvements, personIdNum);
+  const currentInterval = useAppSelector((state) => state.intervals.currentInterval);
+
+  const [questionSet, setQuestionSet] = useState<CheckInQuestionSet | null>(null);
+  useEffect(() => {
+    if (!currentInterval) return;
+
+    getApi()
+      .api.getQuestionSet(currentInterval.id)
+      .then((response) => {
+        setQuestionSet(response.data);
+      })
+      .catch((error) => {
+        console.error("Error fetching check-in questions:", error);
+      });
+  }, [currentInterval?.id]);
 
   const canEdit = meId === person.id;
 
@@ -102,8 +131,8 @@ export default function Person() {
           </Text>
         )}
 
-        {!collective_involvement?.private_capacity_planning && collective_involvement?.capacity_planning && (
-          <CapacityPlanningSection capacity_planning={collective_involvement.capacity_planning} capacity_score={collective_involvement.capacity_score} />
+        {collective_involvement?.check_in_answers && (
+          <CapacityPlanningSection check_in_answers={collective_involvement.check_in_answers} capacity_score={collective_involvement.capacity_score} questionSet={questionSet} />
         )}
       </Stack>
     </Container>